@@ -1,10 +1,13 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 
 use rust_wasm_graphics_lib::canvas::Canvas;
+use rust_wasm_graphics_lib::drawing::clip::textured_triangle_clipped;
+use rust_wasm_graphics_lib::drawing::path::{fill_path, Path};
 use rust_wasm_graphics_lib::drawing::shape::{
     fill_polygon,
     fill_triangle,
     polygon,
+    shaded_textured_triangle,
     textured_triangle,
 };
 use rust_wasm_graphics_lib::drawing::rect::fill_rect;
@@ -12,8 +15,15 @@ use rust_wasm_graphics_lib::drawing::lines::{
     h_line,
     v_line,
 };
+use rust_wasm_graphics_lib::tilemap::TileMap;
 use rust_wasm_graphics_lib::types::{
     ARGBColour,
+    ClipPlanes,
+    ClipVertex,
+    EdgeMode,
+    ResizeFilter,
+    SampleFilter,
+    ShadedVertex,
     UVWrapMode,
     UVVertex,
 };
@@ -24,23 +34,97 @@ fn bench_canvas_clear(c: &mut Criterion) {
     c.bench_function("Canvas::clear()", move |b| b.iter(|| can.clear(&col)));
 }
 
+fn bench_canvas_convolve(c: &mut Criterion) {
+    let mut can = Canvas::new(128, 128);
+    let kernel = [1.0; 25];
+    c.bench_function(
+        "Canvas::convolve()",
+        move |b| b.iter(|| can.convolve(&kernel, 5, 5, 25.0, 0.0, EdgeMode::Clamp, true)),
+    );
+}
+
 fn bench_canvas_draw_canvas(c: &mut Criterion) {
     let src = Canvas::new(128, 128);
     let mut dst = Canvas::new(128, 128);
     c.bench_function("Canvas::draw_canvas()", move |b| b.iter(|| dst.draw_canvas(&src, 0, 0)));
 }
 
+fn bench_canvas_draw_canvas_transformed(c: &mut Criterion) {
+    let src = Canvas::new(64, 64);
+    let mut dst = Canvas::new(128, 128);
+    c.bench_function(
+        "Canvas::draw_canvas_transformed()",
+        move |b| b.iter(|| dst.draw_canvas_transformed(&src, 64.0, 64.0, 1.5, 1.5, 0.7, 32.0, 32.0, SampleFilter::Bilinear)),
+    );
+}
+
+fn bench_canvas_draw_tilemap(c: &mut Criterion) {
+    let mut map = TileMap::new(Canvas::new(64, 64), 16, 16, 64, 64);
+    map.load_layer(0, (0..64 * 64).map(|i| i % 16 + 1).collect());
+    let mut dst = Canvas::new(320, 240);
+    c.bench_function("Canvas::draw_tilemap()", move |b| b.iter(|| dst.draw_tilemap(&map, 100, 50, 0.0)));
+}
+
+fn bench_canvas_from_qoi(c: &mut Criterion) {
+    let mut src = Canvas::new(128, 128);
+    for (i, px) in src.buffer_mut().iter_mut().enumerate() {
+        *px = 0xFF00_0000 | (i as u32 * 0x0001_0203);
+    }
+    let data = src.to_qoi();
+    c.bench_function("Canvas::from_qoi()", move |b| b.iter(|| Canvas::from_qoi(&data)));
+}
+
+fn bench_canvas_gaussian_blur(c: &mut Criterion) {
+    let mut can = Canvas::new(128, 128);
+    c.bench_function("Canvas::gaussian_blur()", move |b| b.iter(|| can.gaussian_blur(12.0, EdgeMode::Clamp)));
+}
+
 fn bench_canvas_load_pixels(c: &mut Criterion) {
     let mut dst = Canvas::new(128, 128);
     let pixels = vec![0; 128 * 128];
     c.bench_function("Canvas::load_pixels()", move |b| b.iter(|| dst.load_pixels(pixels.clone())));
 }
 
+fn bench_canvas_resize(c: &mut Criterion) {
+    c.bench_function("Canvas::resize()", move |b| {
+        b.iter(|| Canvas::new(128, 128).resize(96, 160, ResizeFilter::Bicubic))
+    });
+}
+
 fn bench_canvas_sample(c: &mut Criterion) {
     let can = Canvas::new(128, 128);
     c.bench_function("Canvas::sample()", move |b| b.iter(|| can.sample(0.22, 0.77, UVWrapMode::Wrap)));
 }
 
+fn bench_canvas_transposed(c: &mut Criterion) {
+    let can = Canvas::new(512, 256);
+    c.bench_function("Canvas::transposed()", move |b| b.iter(|| can.transposed()));
+}
+
+
+fn bench_drawing_clip_textured_triangle(c: &mut Criterion) {
+    let mut c_dst = Canvas::new(128, 128);
+    let c_src = Canvas::new(64, 64);
+    let pta = ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0);
+    let ptb = ClipVertex::new(0.5, -0.5, 0.5, 1.0, 1.0, 0.0);
+    let ptc = ClipVertex::new(0.0, 1.5, -3.0, -1.0, 0.5, 1.0);
+    c.bench_function(
+        "drawing::textured_triangle_clipped()",
+        move |b| b.iter(|| {
+            textured_triangle_clipped(&mut c_dst, &c_src, &pta, &ptb, &ptc, UVWrapMode::Wrap, ClipPlanes::All);
+        }),
+    );
+}
+
+fn bench_drawing_fill_path(c: &mut Criterion) {
+    let mut can = Canvas::new(128, 128);
+    let col = ARGBColour::new(255, 255, 0, 0);
+    let mut path = Path::new();
+    path.move_to(64.0, 10.0);
+    path.cubic_to(120.0, 80.0, 100.0, 120.0, 64.0, 120.0);
+    path.cubic_to(28.0, 120.0, 8.0, 80.0, 64.0, 10.0);
+    c.bench_function("drawing::fill_path()", move |b| b.iter(|| fill_path(&mut can, &col, &path)));
+}
 
 fn bench_drawing_fill_polygon(c: &mut Criterion) {
     let col = ARGBColour::new(255, 255, 0, 0);
@@ -89,6 +173,20 @@ fn bench_drawing_polygon(c: &mut Criterion) {
     );
 }
 
+fn bench_drawing_shaded_textured_triangle(c: &mut Criterion) {
+    let mut c_dst = Canvas::new(128, 128);
+    let c_src = Canvas::new(64, 64);
+    let pta = ShadedVertex::new(16, 16, 0.0, 0.0, 1.0, 1.0, 0.5, 0.5);
+    let ptb = ShadedVertex::new(112, 96, 0.5, 1.0, 1.0, 0.5, 1.0, 0.5);
+    let ptc = ShadedVertex::new(32, 128, 1.0, 0.75, 1.0, 0.5, 0.5, 1.0);
+    c.bench_function(
+        "drawing::shaded_textured_triangle()",
+        move |b| b.iter(|| {
+            shaded_textured_triangle(&mut c_dst, &c_src, &pta, &ptb, &ptc, UVWrapMode::Wrap);
+        }),
+    );
+}
+
 fn bench_drawing_textured_triangle(c: &mut Criterion) {
     let mut c_dst = Canvas::new(128, 128);
     let c_src = Canvas::new(64, 64);
@@ -115,14 +213,24 @@ fn bench_drawing_v_line(c: &mut Criterion) {
 
 criterion_group!(benches,
     bench_canvas_clear,
+    bench_canvas_convolve,
     bench_canvas_draw_canvas,
+    bench_canvas_draw_canvas_transformed,
+    bench_canvas_draw_tilemap,
+    bench_canvas_from_qoi,
+    bench_canvas_gaussian_blur,
     bench_canvas_load_pixels,
+    bench_canvas_resize,
     bench_canvas_sample,
+    bench_canvas_transposed,
+    bench_drawing_clip_textured_triangle,
+    bench_drawing_fill_path,
     bench_drawing_fill_polygon,
     bench_drawing_fill_rect,
     bench_drawing_fill_triangle,
     bench_drawing_h_line,
     bench_drawing_polygon,
+    bench_drawing_shaded_textured_triangle,
     bench_drawing_textured_triangle,
     bench_drawing_v_line,
 );
@@ -1,6 +1,74 @@
 use wasm_bindgen::prelude::*;
 
-use crate::types::{ARGBColour, UVWrapMode};
+use crate::drawing::blend::{blend_over, premultiply, unpremultiply};
+use crate::transform::Transform2D;
+use crate::types::{ARGBColour, SampleFilter, UVWrapMode};
+
+/// A rectangular region of a source Canvas to be copied to a destination Canvas, clipped to the
+/// bounds of both
+pub(crate) struct BlitRegion {
+
+    /// Top-left corner and size of the source rectangle after clipping to the source Canvas
+    pub(crate) src_x: usize,
+    pub(crate) src_y: usize,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+
+    /// Visible range of X and Y offsets from the destination position after clipping to the
+    /// destination Canvas
+    pub(crate) off_x: (usize, usize),
+    pub(crate) off_y: (usize, usize),
+}
+
+impl BlitRegion {
+
+    /// Clips a source rectangle (sx,sy,sw,sh) drawn at a destination position (dx,dy), returning
+    /// `None` if nothing would be drawn
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(dst: &Canvas, src: &Canvas, sx: usize, sy: usize, sw: usize, sh: usize, dx: isize, dy: isize) -> Option<Self> {
+        let (width, height) = Self::clip_source(src, sx, sy, sw, sh)?;
+        Some(Self {
+            src_x: sx,
+            src_y: sy,
+            width,
+            height,
+            off_x: Self::clip_offsets(dx, width, dst.width)?,
+            off_y: Self::clip_offsets(dy, height, dst.height)?,
+        })
+    }
+
+    /// Clips a source rectangle (sx,sy,sw,sh) drawn with its X and Y axes swapped at a destination
+    /// position (dx,dy), so that `off_x` ranges over source rows and `off_y` over source columns.
+    /// Returns `None` if nothing would be drawn.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn transposed(dst: &Canvas, src: &Canvas, sx: usize, sy: usize, sw: usize, sh: usize, dx: isize, dy: isize) -> Option<Self> {
+        let (width, height) = Self::clip_source(src, sx, sy, sw, sh)?;
+        Some(Self {
+            src_x: sx,
+            src_y: sy,
+            width,
+            height,
+            off_x: Self::clip_offsets(dx, height, dst.width)?,
+            off_y: Self::clip_offsets(dy, width, dst.height)?,
+        })
+    }
+
+    /// Returns the size of a source rectangle after clipping it to the source Canvas
+    fn clip_source(src: &Canvas, sx: usize, sy: usize, sw: usize, sh: usize) -> Option<(usize, usize)> {
+        if sx >= src.width || sy >= src.height {
+            return None;
+        }
+        Some((sw.min(src.width - sx), sh.min(src.height - sy)))
+    }
+
+    /// Returns the range of offsets along one axis of `len` pixels drawn at `d` which fall within
+    /// a destination of size `bound`
+    fn clip_offsets(d: isize, len: usize, bound: usize) -> Option<(usize, usize)> {
+        let start = if d < 0 { (-d) as usize } else { 0 };
+        let end = (bound as isize - d).max(0).min(len as isize) as usize;
+        if start < end { Some((start, end)) } else { None }
+    }
+}
 
 #[wasm_bindgen]
 /// A single buffer of 32-bit ARGB pixels with a fixed width and height
@@ -9,6 +77,9 @@ pub struct Canvas {
     width:  usize,
 
     buffer: Vec<u32>,
+
+    transform: Transform2D,
+    transform_stack: Vec<Transform2D>,
 }
 
 #[wasm_bindgen]
@@ -20,6 +91,8 @@ impl Canvas {
             height,
             width,
             buffer: vec![0; width * height],
+            transform: Transform2D::identity(),
+            transform_stack: vec![],
         }
     }
 
@@ -61,6 +134,9 @@ impl Canvas {
 
     /// Copies the contents of one Canvas to another starting at a specified top-left co-ordinate
     ///
+    /// The co-ordinate may be negative or beyond the destination Canvas, in which case the copy is
+    /// clipped to its edges.  Fully transparent source pixels are not copied.
+    ///
     /// # Arguments:
     ///
     ///   - `src_canv`: Canvas whose contents will be copied
@@ -77,35 +153,185 @@ impl Canvas {
     ///
     /// // Draw c_dst to c_src at offset (4, 8)
     /// c_dst.draw_canvas(&c_src, 4, 8);
+    ///
+    /// // Draw c_src to c_dst partly off the top-left corner
+    /// c_dst.draw_canvas(&c_src, -4, -8);
     /// ```
-    pub fn draw_canvas(&mut self, src_canv: &Canvas, x: usize, y: usize) {
-        if x >= self.width || y >= self.height {
-            return
+    pub fn draw_canvas(&mut self, src_canv: &Canvas, x: isize, y: isize) {
+        self.draw_canvas_region(src_canv, 0, 0, src_canv.width, src_canv.height, x, y, false, false);
+    }
+
+    /// Copies a rectangular region of one Canvas to another at a signed top-left co-ordinate,
+    /// optionally flipping the region horizontally and/or vertically
+    ///
+    /// The source rectangle is clipped to the source Canvas and the destination is clipped to all
+    /// four edges of this Canvas.  As with [`draw_canvas()`], fully transparent source pixels are
+    /// not copied.
+    ///
+    /// [`draw_canvas()`]: #method.draw_canvas
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src_canv`: Canvas containing the region to copy
+    ///   - `sx`: X co-ordinate of the top-left corner of the source rectangle
+    ///   - `sy`: Y co-ordinate of the top-left corner of the source rectangle
+    ///   - `sw`: width of the source rectangle
+    ///   - `sh`: height of the source rectangle
+    ///   - `x`: X co-ordinate of starting top-left position in destination Canvas
+    ///   - `y`: Y co-ordinate of starting top-left position in destination Canvas
+    ///   - `flip_h`: if set, the region is mirrored horizontally
+    ///   - `flip_v`: if set, the region is mirrored vertically
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// // Sprite sheet containing four 16x16 frames side by side
+    /// let sheet = Canvas::new(64, 16);
+    /// let mut dst = Canvas::new(128, 128);
+    ///
+    /// // Draw the third frame mirrored, partly off the left edge
+    /// dst.draw_canvas_region(&sheet, 32, 0, 16, 16, -4, 8, true, false);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_canvas_region(
+        &mut self,
+        src_canv: &Canvas,
+        sx: usize,
+        sy: usize,
+        sw: usize,
+        sh: usize,
+        x: isize,
+        y: isize,
+        flip_h: bool,
+        flip_v: bool,
+    ) {
+        let region = match BlitRegion::new(self, src_canv, sx, sy, sw, sh, x, y) {
+            Some(r) => r,
+            None => return,
+        };
+        let (ox_start, ox_end) = region.off_x;
+        for oy in region.off_y.0..region.off_y.1 {
+            let src_row = region.src_y + if flip_v { region.height - 1 - oy } else { oy };
+            let dst_row = (y + oy as isize) as usize;
+            let dst_start = self.buffer_index((x + ox_start as isize) as usize, dst_row);
+            let dst_scanline = &mut self.buffer[dst_start..dst_start + (ox_end - ox_start)];
+
+            // Range of source pixels for this scanline, which is read in reverse when flipped
+            let (src_start, src_end) = if flip_h {
+                (region.width - ox_end, region.width - ox_start)
+            } else {
+                (ox_start, ox_end)
+            };
+            let src_idx = src_canv.buffer_index(region.src_x, src_row);
+            let src_scanline = &src_canv.buffer[src_idx + src_start..src_idx + src_end];
+
+            let copy = |(px, src_px): (&mut u32, &u32)| {
+                if *src_px >> 24 > 0 {
+                    *px = *src_px;
+                }
+            };
+            if flip_h {
+                dst_scanline.iter_mut().zip(src_scanline.iter().rev()).for_each(copy);
+            } else {
+                dst_scanline.iter_mut().zip(src_scanline.iter()).for_each(copy);
+            }
         }
+    }
 
-        let max_x = if x + src_canv.width()  > self.width  { self.width  - 1 } else { x + src_canv.width()  - 1 };
-        let max_y = if y + src_canv.height() > self.height { self.height - 1 } else { y + src_canv.height() - 1 };
-
-        self.buffer
-            .as_mut_slice()
-            .chunks_mut(self.width)
-            .skip(y)
-            .take(max_y - y + 1)
-            .enumerate()
-            .for_each(|(dy, scanline)| {
-                let src_idx = src_canv.buffer_index(0, dy);
-                scanline
-                    .iter_mut()
-                    .skip(x)
-                    .take(max_x - x + 1)
-                    .enumerate()
-                    .for_each(|(dx, px)| {
-                        let src_px = src_canv.buffer()[src_idx + dx];
-                        if src_px >> 24 > 0 {
-                            *px = src_px;
-                        }
-                    });
-            });
+    /// Draws one Canvas onto another with scaling and rotation around a pivot point
+    ///
+    /// The pivot point of the source Canvas is placed at (x,y) in the destination Canvas.  Every
+    /// destination pixel within the bounding box of the transformed source is mapped back into the
+    /// source Canvas and sampled with the given filter.  As with [`draw_canvas()`], fully
+    /// transparent samples are not copied, and partially transparent samples, such as those along
+    /// filtered edges, are blended over the destination.
+    ///
+    /// [`draw_canvas()`]: #method.draw_canvas
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src_canv`: Canvas whose contents will be drawn
+    ///   - `x`: X co-ordinate of the pivot point in the destination Canvas
+    ///   - `y`: Y co-ordinate of the pivot point in the destination Canvas
+    ///   - `scale_x`: horizontal scale factor
+    ///   - `scale_y`: vertical scale factor
+    ///   - `angle`: clockwise rotation in radians
+    ///   - `pivot_x`: X co-ordinate of the pivot point in the source Canvas
+    ///   - `pivot_y`: Y co-ordinate of the pivot point in the source Canvas
+    ///   - `filter`: sampling filter
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::SampleFilter;
+    ///
+    /// let src = Canvas::new(16, 16);
+    /// let mut dst = Canvas::new(64, 64);
+    ///
+    /// // Draw src at double size, rotated 45 degrees around its centre, centred on (32, 32)
+    /// dst.draw_canvas_transformed(
+    ///     &src,
+    ///     32.0, 32.0,
+    ///     2.0, 2.0,
+    ///     std::f64::consts::PI / 4.0,
+    ///     8.0, 8.0,
+    ///     SampleFilter::Bilinear,
+    /// );
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_canvas_transformed(
+        &mut self,
+        src_canv: &Canvas,
+        x: f64,
+        y: f64,
+        scale_x: f64,
+        scale_y: f64,
+        angle: f64,
+        pivot_x: f64,
+        pivot_y: f64,
+        filter: SampleFilter,
+    ) {
+        let fwd = Transform2D::translation(x, y)
+            .rotate(angle)
+            .scale(scale_x, scale_y)
+            .translate(-pivot_x, -pivot_y);
+        let inv = match fwd.invert() {
+            Some(t) => t,
+            None => return,
+        };
+
+        // Bounding box of the transformed source Canvas, clamped to the destination Canvas
+        let (sw, sh) = (src_canv.width() as f64, src_canv.height() as f64);
+        let corners = [fwd.apply(0.0, 0.0), fwd.apply(sw, 0.0), fwd.apply(sw, sh), fwd.apply(0.0, sh)];
+        let min_x = corners.iter().map(|p| p.0).fold(f64::INFINITY, f64::min).floor().max(0.0);
+        let min_y = corners.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor().max(0.0);
+        let max_x = corners.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max).ceil().min(self.width as f64);
+        let max_y = corners.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil().min(self.height as f64);
+        if min_x >= max_x || min_y >= max_y {
+            return;
+        }
+
+        for dy in min_y as usize..max_y as usize {
+            for dx in min_x as usize..max_x as usize {
+
+                // Map the centre of each destination pixel back into the source Canvas
+                let (sx, sy) = inv.apply(dx as f64 + 0.5, dy as f64 + 0.5);
+                if sx < 0.0 || sy < 0.0 || sx >= sw || sy >= sh {
+                    continue;
+                }
+                let src_px = match filter {
+                    SampleFilter::Nearest => src_canv.buffer[src_canv.buffer_index(sx as usize, sy as usize)],
+                    SampleFilter::Bilinear => src_canv.sample_bilinear(sx - 0.5, sy - 0.5),
+                };
+                if src_px >> 24 > 0 {
+                    let idx = self.buffer_index(dx, dy);
+                    self.buffer[idx] = blend_over(src_px, self.buffer[idx]);
+                }
+            }
+        }
     }
 
     /// Copies a source Vec<u32> into the Canvas `buffer`
@@ -176,6 +402,83 @@ impl Canvas {
         let idx = self.buffer_index(x, y);
         self.buffer[idx]
     }
+
+    /// Returns the current transform applied to vector drawing functions
+    pub fn transform(&self) -> Transform2D {
+        self.transform
+    }
+
+    /// Replaces the current transform
+    pub fn set_transform(&mut self, transform: &Transform2D) {
+        self.transform = *transform;
+    }
+
+    /// Resets the current transform to the identity transform
+    pub fn reset_transform(&mut self) {
+        self.transform = Transform2D::identity();
+    }
+
+    /// Combines a transform with the current transform.  The new transform is applied to
+    /// co-ordinates before the existing transform.
+    pub fn apply_transform(&mut self, transform: &Transform2D) {
+        self.transform = self.transform.multiply(transform);
+    }
+
+    /// Adds a translation to the current transform (see [`apply_transform()`])
+    ///
+    /// [`apply_transform()`]: #method.apply_transform
+    pub fn translate(&mut self, tx: f64, ty: f64) {
+        self.transform = self.transform.translate(tx, ty);
+    }
+
+    /// Adds a clockwise rotation in radians to the current transform (see [`apply_transform()`])
+    ///
+    /// [`apply_transform()`]: #method.apply_transform
+    pub fn rotate(&mut self, angle: f64) {
+        self.transform = self.transform.rotate(angle);
+    }
+
+    /// Adds a scale to the current transform (see [`apply_transform()`])
+    ///
+    /// [`apply_transform()`]: #method.apply_transform
+    pub fn scale(&mut self, sx: f64, sy: f64) {
+        self.transform = self.transform.scale(sx, sy);
+    }
+
+    /// Pushes the current transform onto a stack so that it can be restored later with
+    /// [`restore()`]
+    ///
+    /// [`restore()`]: #method.restore
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::drawing::shape::fill_triangle;
+    /// use rust_wasm_graphics_lib::types::ARGBColour;
+    ///
+    /// let mut c = Canvas::new(128, 128);
+    /// let col = ARGBColour::new(255, 255, 0, 0);
+    ///
+    /// // Draw a triangle rotated around the centre of the Canvas
+    /// c.save();
+    /// c.translate(64.0, 64.0);
+    /// c.rotate(std::f64::consts::PI / 4.0);
+    /// fill_triangle(&mut c, &col, 0, -10, 10, 10, -10, 10);
+    /// c.restore();
+    ///
+    /// assert!(c.transform().is_identity());
+    /// ```
+    pub fn save(&mut self) {
+        self.transform_stack.push(self.transform);
+    }
+
+    /// Restores the most recently saved transform.  Does nothing if there is no saved transform.
+    pub fn restore(&mut self) {
+        if let Some(t) = self.transform_stack.pop() {
+            self.transform = t;
+        }
+    }
 }
 
 
@@ -190,4 +493,48 @@ impl Canvas {
     pub fn buffer_mut(&mut self) -> &mut Vec<u32> {
         &mut self.buffer
     }
+
+    /// Replaces the pixel buffer with one of a different size, keeping the current transform.
+    /// Returns `false` and leaves the Canvas unchanged if `src` is not `width * height` long.
+    pub fn replace_pixels(&mut self, width: usize, height: usize, src: Vec<u32>) -> bool {
+        if src.len() != width * height {
+            return false;
+        }
+        self.width = width;
+        self.height = height;
+        self.buffer = src;
+        true
+    }
+
+    /// Samples the pixel buffer at a pixel co-ordinate (x,y) by linearly interpolating between
+    /// the premultiplied colours of the four nearest pixels, so that transparent pixels do not
+    /// bleed their colour into the result.  Co-ordinates outside of the Canvas are clamped to its
+    /// edges.  Returns 0 if the Canvas is empty.
+    pub fn sample_bilinear(&self, x: f64, y: f64) -> u32 {
+        if self.width == 0 || self.height == 0 {
+            return 0;
+        }
+        let max_x = (self.width - 1) as f64;
+        let max_y = (self.height - 1) as f64;
+        let x = x.clamp(0.0, max_x);
+        let y = y.clamp(0.0, max_y);
+        let x0 = x.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let fx = x - x0 as f64;
+        let fy = y - y0 as f64;
+
+        let p00 = premultiply(self.buffer[self.buffer_index(x0, y0)]);
+        let p10 = premultiply(self.buffer[self.buffer_index(x1, y0)]);
+        let p01 = premultiply(self.buffer[self.buffer_index(x0, y1)]);
+        let p11 = premultiply(self.buffer[self.buffer_index(x1, y1)]);
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            let top = p00[i] + (p10[i] - p00[i]) * fx;
+            let bot = p01[i] + (p11[i] - p01[i]) * fx;
+            out[i] = top + (bot - top) * fy;
+        }
+        unpremultiply(out)
+    }
 }
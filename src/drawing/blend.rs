@@ -0,0 +1,71 @@
+//! Alpha blending and premultiplication of ARGB colours
+//!
+//! Most drawing functions write pixels without blending.  Functions which produce partially
+//! covered pixels, such as anti-aliased path filling, composite them with [`blend_over()`].
+//!
+//! [`blend_over()`]: ./fn.blend_over.html
+
+/// Composites a non-premultiplied ARGB source colour over a destination colour ("source-over").
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::blend::blend_over;
+///
+/// assert_eq!(blend_over(0xFFFF0000, 0xFF0000FF), 0xFFFF0000);
+/// assert_eq!(blend_over(0x00FF0000, 0xFF0000FF), 0xFF0000FF);
+/// assert_eq!(blend_over(0x80FFFFFF, 0xFF000000), 0xFF808080);
+/// ```
+pub fn blend_over(src: u32, dst: u32) -> u32 {
+    let sa = src >> 24;
+    if sa == 255 {
+        return src;
+    }
+    if sa == 0 {
+        return dst;
+    }
+    let da = dst >> 24;
+
+    // Output alpha scaled by 255, i.e. out_a = sa + da * (1 - sa)
+    let out_a = sa * 255 + da * (255 - sa);
+    let channel = |shift: u32| {
+        let s = (src >> shift) & 0xFF;
+        let d = (dst >> shift) & 0xFF;
+        ((s * sa * 255 + d * da * (255 - sa) + out_a / 2) / out_a) << shift
+    };
+    ((out_a + 127) / 255) << 24 | channel(16) | channel(8) | channel(0)
+}
+
+/// Returns an ARGB colour with its alpha multiplied by `amount` in the range [0,1]
+pub fn scale_alpha(col: u32, amount: f64) -> u32 {
+    let a = (f64::from(col >> 24) * amount.clamp(0.0, 1.0) + 0.5).floor() as u32;
+    (a << 24) | (col & 0x00FF_FFFF)
+}
+
+/// Converts an ARGB colour to premultiplied `[r, g, b, a]` components in the range [0,255], for
+/// filtering without colour bleeding from transparent pixels
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::blend::{premultiply, unpremultiply};
+///
+/// assert_eq!(premultiply(0x80FF0000), [128.0, 0.0, 0.0, 128.0]);
+/// assert_eq!(unpremultiply([128.0, 0.0, 0.0, 128.0]), 0x80FF0000);
+/// ```
+pub fn premultiply(col: u32) -> [f64; 4] {
+    let [a, r, g, b] = col.to_be_bytes().map(f64::from);
+    let f = a / 255.0;
+    [r * f, g * f, b * f, a]
+}
+
+/// Converts premultiplied `[r, g, b, a]` components back to an ARGB colour, clamping each
+/// component to its valid range
+pub fn unpremultiply(px: [f64; 4]) -> u32 {
+    let a = px[3].clamp(0.0, 255.0);
+    if a < 0.5 {
+        return 0;
+    }
+    let c = |v: f64| ((v * 255.0 / a).clamp(0.0, 255.0) + 0.5).floor() as u32;
+    (((a + 0.5).floor() as u32) << 24) | (c(px[0]) << 16) | (c(px[1]) << 8) | c(px[2])
+}
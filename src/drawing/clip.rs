@@ -0,0 +1,458 @@
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::types::{ARGBColour, ClipPlanes, ClipVertex, CullMode, FrontFace, ShadedVertex, UVVertex, UVWrapMode};
+use super::shape::{is_culled, shaded_textured_triangle, shaded_triangle, textured_triangle};
+
+/// Smallest W value allowed after clipping, prevents division by zero during the perspective
+/// divide
+const W_EPSILON: f64 = 1e-6;
+
+/// Returns the signed distance of a vertex from each clip plane in `planes`.  A vertex is on the
+/// visible side of a plane when the distance is >= 0.
+fn plane_distances(planes: ClipPlanes) -> &'static [fn(&ClipVertex) -> f64] {
+    const NEAR_FAR: [fn(&ClipVertex) -> f64; 3] = [
+        |v| v.w - W_EPSILON,
+        |v| v.z + v.w,
+        |v| v.w - v.z,
+    ];
+    const ALL: [fn(&ClipVertex) -> f64; 7] = [
+        |v| v.w - W_EPSILON,
+        |v| v.z + v.w,
+        |v| v.w - v.z,
+        |v| v.x + v.w,
+        |v| v.w - v.x,
+        |v| v.y + v.w,
+        |v| v.w - v.y,
+    ];
+    match planes {
+        ClipPlanes::NearFar => &NEAR_FAR,
+        ClipPlanes::All => &ALL,
+    }
+}
+
+/// Clips a triangle against a set of clip-space planes using the Sutherland-Hodgman algorithm.
+///
+/// Returns the clipped triangle as a convex polygon of between 3 and 9 vertices (or no vertices
+/// if the triangle is entirely outside of the view volume).  All vertex attributes (U,V and
+/// colour) are interpolated linearly in clip space.
+///
+/// [`ClipPlanes`]: ../../types/enum.ClipPlanes.html
+/// [`ClipVertex`]: ../../types/struct.ClipVertex.html
+///
+/// # Arguments:
+///
+///   - `a`: first vertex
+///   - `b`: second vertex
+///   - `c`: third vertex
+///   - `planes`: set of planes to clip against (see [`ClipPlanes`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::clip::clip_triangle;
+/// use rust_wasm_graphics_lib::types::{ClipPlanes, ClipVertex};
+///
+/// // Third vertex is behind the camera (W < 0)
+/// let poly = clip_triangle(
+///     &ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///     &ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0),
+///     &ClipVertex::new( 0.0,  0.5, -3.0, -1.0, 0.5, 1.0),
+///     ClipPlanes::NearFar,
+/// );
+/// assert_eq!(poly.len(), 4);
+/// ```
+pub fn clip_triangle(
+    a: &ClipVertex,
+    b: &ClipVertex,
+    c: &ClipVertex,
+    planes: ClipPlanes,
+) -> Vec<ClipVertex> {
+    let mut poly = vec![*a, *b, *c];
+    for dist in plane_distances(planes) {
+        if poly.is_empty() {
+            break;
+        }
+        let mut clipped = Vec::with_capacity(poly.len() + 1);
+        for (i, curr) in poly.iter().enumerate() {
+            let next = &poly[(i + 1) % poly.len()];
+            let d_curr = dist(curr);
+            let d_next = dist(next);
+            if d_curr >= 0.0 {
+                clipped.push(*curr);
+            }
+
+            // Edge crosses the plane: add the intersection point
+            if (d_curr >= 0.0) != (d_next >= 0.0) {
+                clipped.push(curr.lerp(next, d_curr / (d_curr - d_next)));
+            }
+        }
+        poly = clipped;
+    }
+    poly
+}
+
+#[wasm_bindgen]
+/// Clips a triangle against a set of clip-space planes and returns the result as a flat list of
+/// triangles (each group of three vertices forms one triangle).
+///
+/// See [`clip_triangle()`] for further details.
+///
+/// [`clip_triangle()`]: ./fn.clip_triangle.html
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::clip::clip_triangle_list;
+/// use rust_wasm_graphics_lib::types::{ClipPlanes, ClipVertex};
+///
+/// let tris = clip_triangle_list(
+///     &ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///     &ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0),
+///     &ClipVertex::new( 0.0,  0.5, -3.0, -1.0, 0.5, 1.0),
+///     ClipPlanes::NearFar,
+/// );
+/// assert_eq!(tris.len(), 6);
+/// ```
+pub fn clip_triangle_list(
+    a: &ClipVertex,
+    b: &ClipVertex,
+    c: &ClipVertex,
+    planes: ClipPlanes,
+) -> Vec<ClipVertex> {
+    let poly = clip_triangle(a, b, c, planes);
+    if poly.len() < 3 {
+        return vec![];
+    }
+
+    // Triangulate the convex polygon as a fan around the first vertex
+    poly.iter()
+        .skip(1)
+        .zip(poly.iter().skip(2))
+        .flat_map(|(curr, next)| vec![poly[0], *curr, *next])
+        .collect()
+}
+
+#[wasm_bindgen]
+/// Projects a clip-space vertex onto a [`Canvas`] of a given size.
+///
+/// Performs the perspective divide and then maps normalised device co-ordinates in the range
+/// [-1,1] to pixel co-ordinates.  The Y axis points up in clip space and down on the [`Canvas`].
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+///
+/// # Arguments:
+///
+///   - `v`: vertex to project (W must be > 0)
+///   - `width`: width of the target viewport in pixels
+///   - `height`: height of the target viewport in pixels
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::clip::project_vertex;
+/// use rust_wasm_graphics_lib::types::ClipVertex;
+///
+/// let v = project_vertex(&ClipVertex::new(0.0, 0.0, 0.0, 1.0, 0.5, 0.5), 129, 65);
+/// assert_eq!((v.x, v.y), (64, 32));
+/// ```
+pub fn project_vertex(v: &ClipVertex, width: usize, height: usize) -> UVVertex {
+    let (x, y) = project(v, width, height);
+    UVVertex::new(x, y, v.u, v.v)
+}
+
+/// Projects a clip-space vertex onto a viewport and returns its pixel co-ordinates
+fn project(v: &ClipVertex, width: usize, height: usize) -> (isize, isize) {
+    let nx = v.x / v.w;
+    let ny = v.y / v.w;
+    let sx = (nx + 1.0) * 0.5 * (width as f64 - 1.0);
+    let sy = (1.0 - ny) * 0.5 * (height as f64 - 1.0);
+
+    // Round to the nearest pixel with floor() rather than round(), as in Canvas::sample()
+    ((sx + 0.5).floor() as isize, (sy + 0.5).floor() as isize)
+}
+
+/// Clips, projects and culls a list of clip-space triangles, calling `draw` with the vertices of
+/// each visible piece
+fn for_each_visible_triangle<F>(
+    vertices: &[ClipVertex],
+    width: usize,
+    height: usize,
+    planes: ClipPlanes,
+    cull: CullMode,
+    front_face: FrontFace,
+    mut draw: F,
+) where
+    F: FnMut(&ShadedVertex, &ShadedVertex, &ShadedVertex),
+{
+    let to_shaded = |v: &ClipVertex| {
+        let (x, y) = project(v, width, height);
+        ShadedVertex::new(x, y, v.u, v.v, v.a, v.r, v.g, v.b)
+    };
+    vertices.chunks_exact(3).for_each(|tri| {
+        clip_triangle_list(&tri[0], &tri[1], &tri[2], planes)
+            .chunks(3)
+            .for_each(|piece| {
+                let a = to_shaded(&piece[0]);
+                let b = to_shaded(&piece[1]);
+                let c = to_shaded(&piece[2]);
+                if !is_culled(&(&a).into(), &(&b).into(), &(&c).into(), cull, front_face) {
+                    draw(&a, &b, &c);
+                }
+            });
+    });
+}
+
+#[wasm_bindgen]
+/// Draws a textured triangle given three [`ClipVertex`] vertices in homogeneous clip space.
+///
+/// The triangle is first clipped against the selected [`ClipPlanes`], so vertices behind the
+/// camera are handled correctly.  Each resulting piece is projected onto `canv_dst` and drawn with
+/// [`textured_triangle()`].
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ClipPlanes`]: ../../types/enum.ClipPlanes.html
+/// [`ClipVertex`]: ../../types/struct.ClipVertex.html
+/// [`UVWrapMode`]: ../../types/enum.UVWrapMode.html
+/// [`textured_triangle()`]: ../shape/fn.textured_triangle.html
+///
+/// # Arguments:
+///
+///   - `canv_dst`: target [`Canvas`]
+///   - `canv_src`: [`Canvas`] used for sampling pixels
+///   - `a`: first vertex
+///   - `b`: second vertex
+///   - `c`: third vertex
+///   - `uv_mode`: UV wrapping mode (see [`UVWrapMode`])
+///   - `planes`: set of planes to clip against (see [`ClipPlanes`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::clip::textured_triangle_clipped;
+/// use rust_wasm_graphics_lib::types::{ClipPlanes, ClipVertex, UVWrapMode};
+///
+/// let mut c = Canvas::new(128, 128);
+/// let c2 = Canvas::new(64, 64);
+///
+/// textured_triangle_clipped(
+///     &mut c,
+///     &c2,
+///     &ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///     &ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0),
+///     &ClipVertex::new( 0.0,  0.5, -3.0, -1.0, 0.5, 1.0),
+///     UVWrapMode::Clamp,
+///     ClipPlanes::All,
+/// );
+/// ```
+pub fn textured_triangle_clipped(
+    canv_dst: &mut Canvas,
+    canv_src: &Canvas,
+    a: &ClipVertex,
+    b: &ClipVertex,
+    c: &ClipVertex,
+    uv_mode: UVWrapMode,
+    planes: ClipPlanes,
+) {
+    let (w, h) = (canv_dst.width(), canv_dst.height());
+    clip_triangle_list(a, b, c, planes)
+        .chunks(3)
+        .for_each(|tri| {
+            textured_triangle(
+                canv_dst,
+                canv_src,
+                &project_vertex(&tri[0], w, h),
+                &project_vertex(&tri[1], w, h),
+                &project_vertex(&tri[2], w, h),
+                uv_mode,
+            );
+        });
+}
+
+/// Draws a batch of textured triangles given as [`ClipVertex`] vertices in homogeneous clip space,
+/// discarding triangles according to a [`CullMode`].
+///
+/// Every three vertices form a single triangle.  Each triangle is clipped and projected as in
+/// [`textured_triangle_clipped()`] and facing is determined from the projected vertices (see
+/// [`is_culled()`]).
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ClipPlanes`]: ../../types/enum.ClipPlanes.html
+/// [`ClipVertex`]: ../../types/struct.ClipVertex.html
+/// [`CullMode`]: ../../types/enum.CullMode.html
+/// [`FrontFace`]: ../../types/enum.FrontFace.html
+/// [`UVWrapMode`]: ../../types/enum.UVWrapMode.html
+/// [`is_culled()`]: ../shape/fn.is_culled.html
+/// [`textured_triangle_clipped()`]: ./fn.textured_triangle_clipped.html
+///
+/// # Arguments:
+///
+///   - `canv_dst`: target [`Canvas`]
+///   - `canv_src`: [`Canvas`] used for sampling pixels
+///   - `vertices`: list of vertices, three per triangle
+///   - `uv_mode`: UV wrapping mode (see [`UVWrapMode`])
+///   - `planes`: set of planes to clip against (see [`ClipPlanes`])
+///   - `cull`: cull mode (see [`CullMode`])
+///   - `front_face`: winding order of front-facing triangles (see [`FrontFace`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::clip::textured_triangles_clipped;
+/// use rust_wasm_graphics_lib::types::{ClipPlanes, ClipVertex, CullMode, FrontFace, UVWrapMode};
+///
+/// let mut c = Canvas::new(128, 128);
+/// let c2 = Canvas::new(64, 64);
+///
+/// textured_triangles_clipped(
+///     &mut c,
+///     &c2,
+///     &[
+///         ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///         ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0),
+///         ClipVertex::new( 0.0,  0.5, 0.5, 1.0, 0.5, 1.0),
+///     ],
+///     UVWrapMode::Clamp,
+///     ClipPlanes::All,
+///     CullMode::Back,
+///     FrontFace::Ccw,
+/// );
+/// ```
+pub fn textured_triangles_clipped(
+    canv_dst: &mut Canvas,
+    canv_src: &Canvas,
+    vertices: &[ClipVertex],
+    uv_mode: UVWrapMode,
+    planes: ClipPlanes,
+    cull: CullMode,
+    front_face: FrontFace,
+) {
+    let (w, h) = (canv_dst.width(), canv_dst.height());
+    for_each_visible_triangle(vertices, w, h, planes, cull, front_face, |a, b, c| {
+        textured_triangle(canv_dst, canv_src, &a.into(), &b.into(), &c.into(), uv_mode);
+    });
+}
+
+/// Draws a batch of filled triangles given as [`ClipVertex`] vertices in homogeneous clip space.
+/// The fill colour is multiplied by the interpolated vertex colours (see [`shaded_triangle()`]).
+///
+/// Clipping and culling are performed as in [`textured_triangles_clipped()`].
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ClipPlanes`]: ../../types/enum.ClipPlanes.html
+/// [`ClipVertex`]: ../../types/struct.ClipVertex.html
+/// [`CullMode`]: ../../types/enum.CullMode.html
+/// [`FrontFace`]: ../../types/enum.FrontFace.html
+/// [`shaded_triangle()`]: ../shape/fn.shaded_triangle.html
+/// [`textured_triangles_clipped()`]: ./fn.textured_triangles_clipped.html
+///
+/// # Arguments:
+///
+///   - `canv`: target [`Canvas`]
+///   - `col`: fill colour
+///   - `vertices`: list of vertices, three per triangle
+///   - `planes`: set of planes to clip against (see [`ClipPlanes`])
+///   - `cull`: cull mode (see [`CullMode`])
+///   - `front_face`: winding order of front-facing triangles (see [`FrontFace`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::clip::shaded_triangles_clipped;
+/// use rust_wasm_graphics_lib::types::{ARGBColour, ClipPlanes, ClipVertex, CullMode, FrontFace};
+///
+/// let mut c = Canvas::new(128, 128);
+///
+/// shaded_triangles_clipped(
+///     &mut c,
+///     &ARGBColour::new(255, 255, 255, 255),
+///     &[
+///         ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///         ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///         ClipVertex::new( 0.0,  0.5, 0.5, 1.0, 0.0, 0.0).with_colour(&ARGBColour::new(255, 255, 0, 0)),
+///     ],
+///     ClipPlanes::All,
+///     CullMode::Back,
+///     FrontFace::Ccw,
+/// );
+/// ```
+pub fn shaded_triangles_clipped(
+    canv: &mut Canvas,
+    col: &ARGBColour,
+    vertices: &[ClipVertex],
+    planes: ClipPlanes,
+    cull: CullMode,
+    front_face: FrontFace,
+) {
+    let (w, h) = (canv.width(), canv.height());
+    for_each_visible_triangle(vertices, w, h, planes, cull, front_face, |a, b, c| {
+        shaded_triangle(canv, col, a, b, c);
+    });
+}
+
+/// Draws a batch of textured triangles given as [`ClipVertex`] vertices in homogeneous clip space.
+/// Each sampled pixel is multiplied by the interpolated vertex colours (see
+/// [`shaded_textured_triangle()`]).
+///
+/// Clipping and culling are performed as in [`textured_triangles_clipped()`].
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ClipPlanes`]: ../../types/enum.ClipPlanes.html
+/// [`ClipVertex`]: ../../types/struct.ClipVertex.html
+/// [`CullMode`]: ../../types/enum.CullMode.html
+/// [`FrontFace`]: ../../types/enum.FrontFace.html
+/// [`UVWrapMode`]: ../../types/enum.UVWrapMode.html
+/// [`shaded_textured_triangle()`]: ../shape/fn.shaded_textured_triangle.html
+/// [`textured_triangles_clipped()`]: ./fn.textured_triangles_clipped.html
+///
+/// # Arguments:
+///
+///   - `canv_dst`: target [`Canvas`]
+///   - `canv_src`: [`Canvas`] used for sampling pixels
+///   - `vertices`: list of vertices, three per triangle
+///   - `uv_mode`: UV wrapping mode (see [`UVWrapMode`])
+///   - `planes`: set of planes to clip against (see [`ClipPlanes`])
+///   - `cull`: cull mode (see [`CullMode`])
+///   - `front_face`: winding order of front-facing triangles (see [`FrontFace`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::clip::shaded_textured_triangles_clipped;
+/// use rust_wasm_graphics_lib::types::{ClipPlanes, ClipVertex, CullMode, FrontFace, UVWrapMode};
+///
+/// let mut c = Canvas::new(128, 128);
+/// let c2 = Canvas::new(64, 64);
+///
+/// shaded_textured_triangles_clipped(
+///     &mut c,
+///     &c2,
+///     &[
+///         ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0),
+///         ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0),
+///         ClipVertex::new( 0.0,  0.5, 0.5, 1.0, 0.5, 1.0),
+///     ],
+///     UVWrapMode::Clamp,
+///     ClipPlanes::All,
+///     CullMode::Back,
+///     FrontFace::Ccw,
+/// );
+/// ```
+pub fn shaded_textured_triangles_clipped(
+    canv_dst: &mut Canvas,
+    canv_src: &Canvas,
+    vertices: &[ClipVertex],
+    uv_mode: UVWrapMode,
+    planes: ClipPlanes,
+    cull: CullMode,
+    front_face: FrontFace,
+) {
+    let (w, h) = (canv_dst.width(), canv_dst.height());
+    for_each_visible_triangle(vertices, w, h, planes, cull, front_face, |a, b, c| {
+        shaded_textured_triangle(canv_dst, canv_src, a, b, c, uv_mode);
+    });
+}
@@ -1,4 +1,17 @@
 use crate::canvas::Canvas;
+use crate::types::ARGBColour;
+use super::lines::{h_line, line_bresenham, v_line};
+
+/// Draws a line between two points in pixel co-ordinates, ignoring the Canvas transform
+pub fn draw_line(c: &mut Canvas, col: &ARGBColour, x1: isize, y1: isize, x2: isize, y2: isize) {
+    if x1 == x2 {
+        v_line(c, col, x1, y1, y2);
+    } else if y1 == y2 {
+        h_line(c, col, x1, y1, x2);
+    } else {
+        line_bresenham(c, col, x1, y1, x2, y2);
+    }
+}
 
 pub fn plot_line_low(c: &mut Canvas, col: u32, x1: usize, y1: usize, x2: usize, y2: usize) {
     let dx: isize = (x2 - x1) as isize;
@@ -8,7 +21,7 @@ pub fn plot_line_low(c: &mut Canvas, col: u32, x1: usize, y1: usize, x2: usize,
         yi = -1isize;
         dy = -dy;
     }
-    let mut d: isize = 2isize * (dy as isize) - (dx as isize);
+    let mut d: isize = 2isize * dy - dx;
     let mut y: isize = y1 as isize;
     for x in x1..=x2 {
         if y < 0 { break; }
@@ -31,7 +44,7 @@ pub fn plot_line_high(c: &mut Canvas, col: u32, x1: usize, y1: usize, x2: usize,
         xi = -1isize;
         dx = -dx;
     }
-    let mut d: isize = 2isize * (dx as isize) - (dy as isize);
+    let mut d: isize = 2isize * dx - dy;
     let mut x: isize = x1 as isize;
     for y in y1..=y2 {
         if x < 0 { break; }
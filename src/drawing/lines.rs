@@ -2,7 +2,7 @@ use wasm_bindgen::prelude::*;
 
 use crate::canvas::Canvas;
 use crate::types::ARGBColour;
-use super::line_utils::{plot_line_high, plot_line_low};
+use super::line_utils::{draw_line, plot_line_high, plot_line_low};
 
 #[wasm_bindgen]
 /// Draws a horizontal line of a given colour to a [`Canvas`]
@@ -157,7 +157,8 @@ pub fn line_bresenham(c: &mut Canvas, col: &ARGBColour, mut x1: isize, mut y1: i
 /// General-purpose line drawing function.  Draws a line of a given colour between points (x1,x2)
 /// and (y1,y2) to a [`Canvas`].
 ///
-/// Defers actual drawing to [`h_line`], [`v_line`] or [`line_bresenham`] as appropriate.
+/// Both points are mapped through the [`Canvas`]'s current transform, then actual drawing is
+/// deferred to [`h_line`], [`v_line`] or [`line_bresenham`] as appropriate.
 ///
 /// [`Canvas`]: ../../canvas/struct.Canvas.html
 /// [`h_line`]: ./fn.h_line.html
@@ -184,11 +185,8 @@ pub fn line_bresenham(c: &mut Canvas, col: &ARGBColour, mut x1: isize, mut y1: i
 /// line(&mut c, &ARGBColour::new(255, 255, 0, 0), 10, 20, 110, 120);
 /// ```
 pub fn line(c: &mut Canvas, col: &ARGBColour, x1: isize, y1: isize, x2: isize, y2: isize) {
-    if x1 == x2 {
-        v_line(c, col, x1, y1, y2);
-    } else if y1 == y2 {
-        h_line(c, col, x1, y1, x2);
-    } else {
-        line_bresenham(c, col, x1, y1, x2, y2);
-    }
+    let t = c.transform();
+    let (x1, y1) = t.apply_pixel(x1, y1);
+    let (x2, y2) = t.apply_pixel(x2, y2);
+    draw_line(c, col, x1, y1, x2, y2);
 }
@@ -5,6 +5,9 @@
 mod line_utils;
 mod shape_utils;
 
+pub mod blend;
+pub mod clip;
 pub mod lines;
+pub mod path;
 pub mod rect;
 pub mod shape;
@@ -0,0 +1,316 @@
+//! Anti-aliased filling of paths made up of lines and Bézier curves
+//!
+//! Paths are filled using the non-zero winding rule.  Coverage is computed exactly along each
+//! scanline and from a number of sub-scanlines vertically, and partially covered pixels are
+//! blended with the existing [`Canvas`] contents.
+//!
+//! [`Canvas`]: ../../canvas/struct.Canvas.html
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::transform::Transform2D;
+use crate::types::ARGBColour;
+use super::blend::{blend_over, scale_alpha};
+
+/// Number of sub-scanlines sampled per pixel row
+const SUBSAMPLES: usize = 5;
+
+/// Maximum distance in pixels between a curve and its flattened line segments
+const FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// Maximum number of line segments a single curve is flattened into
+const MAX_CURVE_SEGMENTS: usize = 64;
+
+/// A single path drawing command
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    QuadTo(f64, f64, f64, f64),
+    CubicTo(f64, f64, f64, f64, f64, f64),
+    Close,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+/// A path made up of one or more contours of lines and quadratic or cubic Bézier curves
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+#[wasm_bindgen]
+impl Path {
+
+    /// Creates a new empty Path
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new contour at (x,y)
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        self.segments.push(PathSegment::MoveTo(x, y));
+    }
+
+    /// Adds a straight line from the current point to (x,y)
+    pub fn line_to(&mut self, x: f64, y: f64) {
+        self.segments.push(PathSegment::LineTo(x, y));
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to (x,y) with control point (cx,cy)
+    pub fn quad_to(&mut self, cx: f64, cy: f64, x: f64, y: f64) {
+        self.segments.push(PathSegment::QuadTo(cx, cy, x, y));
+    }
+
+    /// Adds a cubic Bézier curve from the current point to (x,y) with control points (c1x,c1y)
+    /// and (c2x,c2y)
+    pub fn cubic_to(&mut self, c1x: f64, c1y: f64, c2x: f64, c2y: f64, x: f64, y: f64) {
+        self.segments.push(PathSegment::CubicTo(c1x, c1y, c2x, c2y, x, y));
+    }
+
+    /// Closes the current contour with a straight line back to its starting point
+    pub fn close(&mut self) {
+        self.segments.push(PathSegment::Close);
+    }
+
+    /// Returns `true` if the Path contains no segments
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl Path {
+
+    /// Returns all segments
+    pub fn segments(&self) -> &Vec<PathSegment> {
+        &self.segments
+    }
+
+    /// Returns a copy of this Path with all points mapped through a transform
+    pub fn transformed(&self, t: &Transform2D) -> Path {
+        let segments = self.segments.iter().map(|s| match *s {
+            PathSegment::MoveTo(x, y) => {
+                let (x, y) = t.apply(x, y);
+                PathSegment::MoveTo(x, y)
+            },
+            PathSegment::LineTo(x, y) => {
+                let (x, y) = t.apply(x, y);
+                PathSegment::LineTo(x, y)
+            },
+            PathSegment::QuadTo(cx, cy, x, y) => {
+                let (cx, cy) = t.apply(cx, cy);
+                let (x, y) = t.apply(x, y);
+                PathSegment::QuadTo(cx, cy, x, y)
+            },
+            PathSegment::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                let (c1x, c1y) = t.apply(c1x, c1y);
+                let (c2x, c2y) = t.apply(c2x, c2y);
+                let (x, y) = t.apply(x, y);
+                PathSegment::CubicTo(c1x, c1y, c2x, c2y, x, y)
+            },
+            PathSegment::Close => PathSegment::Close,
+        });
+        Path { segments: segments.collect() }
+    }
+
+    /// Converts the Path into closed polygons by approximating curves with line segments
+    pub fn flatten(&self) -> Vec<Vec<(f64, f64)>> {
+        let mut contours: Vec<Vec<(f64, f64)>> = vec![];
+        let mut current: Vec<(f64, f64)> = vec![];
+        for seg in &self.segments {
+            let p0 = current.last().copied().unwrap_or((0.0, 0.0));
+            match *seg {
+                PathSegment::MoveTo(x, y) => {
+                    if current.len() > 1 {
+                        contours.push(current);
+                    }
+                    current = vec![(x, y)];
+                },
+                PathSegment::LineTo(x, y) => current.push((x, y)),
+                PathSegment::QuadTo(cx, cy, x, y) => {
+                    let dd = (p0.0 - 2.0 * cx + x).hypot(p0.1 - 2.0 * cy + y);
+                    let n = curve_segments(dd / (8.0 * FLATTEN_TOLERANCE));
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        current.push((
+                            mt * mt * p0.0 + 2.0 * mt * t * cx + t * t * x,
+                            mt * mt * p0.1 + 2.0 * mt * t * cy + t * t * y,
+                        ));
+                    }
+                },
+                PathSegment::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                    let dd = (p0.0 - 2.0 * c1x + c2x)
+                        .hypot(p0.1 - 2.0 * c1y + c2y)
+                        .max((c1x - 2.0 * c2x + x).hypot(c1y - 2.0 * c2y + y));
+                    let n = curve_segments(3.0 * dd / (4.0 * FLATTEN_TOLERANCE));
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                        current.push((
+                            a * p0.0 + b * c1x + c * c2x + d * x,
+                            a * p0.1 + b * c1y + c * c2y + d * y,
+                        ));
+                    }
+                },
+                PathSegment::Close => {
+                    if let Some(&first) = current.first() {
+                        if current.len() > 1 {
+                            contours.push(current);
+                        }
+                        current = vec![first];
+                    }
+                },
+            }
+        }
+        if current.len() > 1 {
+            contours.push(current);
+        }
+        contours
+    }
+}
+
+/// Returns the number of line segments needed to flatten a curve, where `error` is the squared
+/// number of segments needed to keep within the flattening tolerance
+fn curve_segments(error: f64) -> usize {
+    (error.sqrt().ceil() as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+/// Calculates the fraction of each pixel in a region covered by a set of closed polygons using
+/// the non-zero winding rule.
+///
+/// # Arguments:
+///
+///   - `contours`: closed polygons, each implicitly joined from its last point to its first
+///   - `x0`: X co-ordinate of the left of the region
+///   - `y0`: Y co-ordinate of the top of the region
+///   - `width`: region width
+///   - `height`: region height
+///
+/// Returns a coverage value in the range [0,1] for each pixel in row-major order.
+pub fn coverage(contours: &[Vec<(f64, f64)>], x0: isize, y0: isize, width: usize, height: usize) -> Vec<f64> {
+    let mut cov = vec![0.0; width * height];
+
+    // Non-horizontal edges relative to the region as (x_top, y_top, x_bottom, y_bottom, winding)
+    let mut edges: Vec<(f64, f64, f64, f64, i32)> = vec![];
+    for contour in contours {
+        for (i, &(ax, ay)) in contour.iter().enumerate() {
+            let (bx, by) = contour[(i + 1) % contour.len()];
+            let (ax, ay, bx, by) = (ax - x0 as f64, ay - y0 as f64, bx - x0 as f64, by - y0 as f64);
+            if ay < by {
+                edges.push((ax, ay, bx, by, 1));
+            } else if ay > by {
+                edges.push((bx, by, ax, ay, -1));
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let sub_weight = 1.0 / SUBSAMPLES as f64;
+    let mut crossings: Vec<(f64, i32)> = vec![];
+    for y in 0..height {
+        let row = &mut cov[y * width..(y + 1) * width];
+        let row_edges: Vec<_> = edges
+            .iter()
+            .take_while(|e| e.1 < (y + 1) as f64)
+            .filter(|e| e.3 > y as f64)
+            .collect();
+        for s in 0..SUBSAMPLES {
+            let sy = y as f64 + (s as f64 + 0.5) * sub_weight;
+            crossings.clear();
+            for &&(xt, yt, xb, yb, dir) in &row_edges {
+                if sy >= yt && sy < yb {
+                    crossings.push((xt + (sy - yt) / (yb - yt) * (xb - xt), dir));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            // Accumulate coverage of each span with a non-zero winding number
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if winding == 0 {
+                    continue;
+                }
+                let (xa, xb) = (pair[0].0.max(0.0), pair[1].0.min(width as f64));
+                if xa >= xb {
+                    continue;
+                }
+                let (first, last) = (xa.floor() as usize, (xb.ceil() as usize).min(width));
+                for (px, c) in row.iter_mut().enumerate().take(last).skip(first) {
+                    let overlap = xb.min(px as f64 + 1.0) - xa.max(px as f64);
+                    *c += overlap * sub_weight;
+                }
+            }
+        }
+    }
+    cov
+}
+
+#[wasm_bindgen]
+/// Fills a path with a given colour using anti-aliasing and the non-zero winding rule.
+///
+/// All points are mapped through the [`Canvas`]'s current transform, and all contours are closed
+/// automatically.
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+///
+/// # Arguments:
+///
+///   - `c`: target [`Canvas`]
+///   - `col`: fill colour, which is blended with the existing pixels
+///   - `path`: path to fill
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::path::{fill_path, Path};
+/// use rust_wasm_graphics_lib::types::ARGBColour;
+///
+/// let mut c = Canvas::new(128, 128);
+///
+/// // Draw a filled teardrop shape
+/// let mut path = Path::new();
+/// path.move_to(64.0, 10.0);
+/// path.cubic_to(120.0, 80.0, 100.0, 120.0, 64.0, 120.0);
+/// path.cubic_to(28.0, 120.0, 8.0, 80.0, 64.0, 10.0);
+/// fill_path(&mut c, &ARGBColour::new(255, 255, 0, 0), &path);
+/// ```
+pub fn fill_path(c: &mut Canvas, col: &ARGBColour, path: &Path) {
+    let contours = if c.transform().is_identity() {
+        path.flatten()
+    } else {
+        path.transformed(&c.transform()).flatten()
+    };
+
+    // Bounding box of the path clipped to the Canvas
+    let mut min = (f64::INFINITY, f64::INFINITY);
+    let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for &(x, y) in contours.iter().flatten() {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    let x0 = min.0.floor().max(0.0);
+    let y0 = min.1.floor().max(0.0);
+    let x1 = max.0.ceil().min(c.width() as f64);
+    let y1 = max.1.ceil().min(c.height() as f64);
+    if !(x0 < x1 && y0 < y1) {
+        return;
+    }
+    let (x0, y0) = (x0 as usize, y0 as usize);
+    let (width, height) = (x1 as usize - x0, y1 as usize - y0);
+
+    let cov = coverage(&contours, x0 as isize, y0 as isize, width, height);
+    let col = u32::from(col);
+    for y in 0..height {
+        let start = c.buffer_index(x0, y0 + y);
+        let dst_row = &mut c.buffer_mut()[start..start + width];
+        for (d, &amount) in dst_row.iter_mut().zip(&cov[y * width..(y + 1) * width]) {
+            if amount > 0.0 {
+                *d = blend_over(scale_alpha(col, amount), *d);
+            }
+        }
+    }
+}
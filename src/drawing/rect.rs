@@ -2,11 +2,28 @@ use wasm_bindgen::prelude::*;
 
 use crate::canvas::Canvas;
 use crate::types::ARGBColour;
+use super::line_utils::draw_line;
 use super::lines::{h_line, v_line};
+use super::shape_utils::fill_triangle_pixels;
+
+/// Maps the corners of a rectangle through the Canvas transform, returning them in clockwise
+/// order starting from (x1,y1)
+fn transformed_corners(c: &Canvas, x1: isize, y1: isize, x2: isize, y2: isize) -> [(isize, isize); 4] {
+    let t = c.transform();
+    [
+        t.apply_pixel(x1, y1),
+        t.apply_pixel(x2, y1),
+        t.apply_pixel(x2, y2),
+        t.apply_pixel(x1, y2),
+    ]
+}
 
 #[wasm_bindgen]
 /// Draws a filled rectangle of a given colour to a [`Canvas`]
 ///
+/// The corners are mapped through the [`Canvas`]'s current transform.  If the transform contains
+/// a rotation or skew, the rectangle is drawn as two filled triangles.
+///
 /// [`Canvas`]: ../../canvas/struct.Canvas.html
 ///
 /// # Arguments:
@@ -29,6 +46,16 @@ use super::lines::{h_line, v_line};
 /// fill_rect(&mut c, &ARGBColour::new(255, 255, 0, 0), 4, 4, 12, 12);
 /// ```
 pub fn fill_rect(c: &mut Canvas, col: &ARGBColour, mut x1: isize, mut y1: isize, mut x2: isize, mut y2: isize) {
+    if !c.transform().is_identity() {
+        let [p1, p2, p3, p4] = transformed_corners(c, x1, y1, x2, y2);
+        if !c.transform().is_axis_aligned() {
+            fill_triangle_pixels(c, col, p1.0, p1.1, p2.0, p2.1, p3.0, p3.1);
+            fill_triangle_pixels(c, col, p1.0, p1.1, p3.0, p3.1, p4.0, p4.1);
+            return;
+        }
+        x1 = p1.0; y1 = p1.1;
+        x2 = p3.0; y2 = p3.1;
+    }
     if x1 > x2 {
         std::mem::swap(&mut x1, &mut x2);
     }
@@ -68,6 +95,8 @@ pub fn fill_rect(c: &mut Canvas, col: &ARGBColour, mut x1: isize, mut y1: isize,
 #[wasm_bindgen]
 /// Draws an un-filled rectangle with a given stroke colour to a [`Canvas`]
 ///
+/// The corners are mapped through the [`Canvas`]'s current transform.
+///
 /// [`Canvas`]: ../../canvas/struct.Canvas.html
 ///
 /// # Arguments:
@@ -90,6 +119,15 @@ pub fn fill_rect(c: &mut Canvas, col: &ARGBColour, mut x1: isize, mut y1: isize,
 /// rect(&mut c, &ARGBColour::new(255, 255, 0, 0), 4, 4, 12, 12);
 /// ```
 pub fn rect(c: &mut Canvas, col: &ARGBColour, x1: isize, y1: isize, x2: isize, y2: isize) {
+    let corners = transformed_corners(c, x1, y1, x2, y2);
+    if !c.transform().is_axis_aligned() {
+        corners.iter().zip(corners.iter().cycle().skip(1)).for_each(|(s, e)| {
+            draw_line(c, col, s.0, s.1, e.0, e.1);
+        });
+        return;
+    }
+    let (x1, y1) = corners[0];
+    let (x2, y2) = corners[2];
     h_line(c, col, x1, y1, x2);
     h_line(c, col, x1, y2, x2);
     v_line(c, col, x1, y1, y2);
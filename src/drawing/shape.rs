@@ -1,12 +1,13 @@
 use wasm_bindgen::prelude::*;
 
 use crate::canvas::Canvas;
-use crate::types::{ARGBColour, UVWrapMode, UVVertex};
-use super::lines::{h_line, line, v_line};
+use crate::types::{ARGBColour, CullMode, FrontFace, ShadedVertex, UVWrapMode, UVVertex};
+use super::lines::{h_line, line};
 use super::shape_utils::{
-    downward_triangle,
     downward_triangle_textured,
-    upward_triangle,
+    fill_triangle_pixels,
+    modulate,
+    shaded_triangle_fill,
     upward_triangle_textured,
 };
 
@@ -19,6 +20,8 @@ use super::shape_utils::{
 /// If the `close` flag is set, a line will also be drawn from the last vertex to the first to
 /// close the polygon.
 ///
+/// All vertices are mapped through the [`Canvas`]'s current transform.
+///
 /// [`Canvas`]: ../../canvas/struct.Canvas.html
 ///
 /// # Arguments:
@@ -60,7 +63,7 @@ pub fn polygon(c: &mut Canvas, col: &ARGBColour, close: bool, points: Vec<i32>)
 /// Note that the polygon should be closed, meaning that the last vertex should match the first, in
 /// order for the routine to work correctly.
 ///
-/// See [`polygon()`] for further details.
+/// See [`polygon()`] for further details, including how vertices are transformed.
 ///
 /// [`Canvas`]: ../../canvas/struct.Canvas.html
 /// [`polygon()`]: ./fn.polygon.html
@@ -85,10 +88,20 @@ pub fn polygon(c: &mut Canvas, col: &ARGBColour, close: bool, points: Vec<i32>)
 /// // Draw a filled triangle
 /// fill_polygon(&mut c, &ARGBColour::new(255, 255, 0, 0), vec![0, -10, 10, 10, -10, 10, 0, -10]);
 /// ```
-pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, points: Vec<i32>) {
+pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, mut points: Vec<i32>) {
 
     // TODO: optimise!
 
+    // Map all vertices through the Canvas transform
+    let t = c.transform();
+    if !t.is_identity() {
+        points.as_mut_slice().chunks_mut(2).filter(|p| p.len() == 2).for_each(|p| {
+            let (x, y) = t.apply_pixel(p[0] as isize, p[1] as isize);
+            p[0] = x as i32;
+            p[1] = y as i32;
+        });
+    }
+
     let ymin = points.as_slice().chunks(2).map(|x| x[1]).min().unwrap_or(0);
     let ymax = points.as_slice().chunks(2).map(|x| x[1]).max().unwrap_or(0);
 
@@ -102,7 +115,7 @@ pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, points: Vec<i32>) {
             .map(|(s, e)| ((s[0], s[1]), (e[0], e[1])))
 
             // Get x co-ordinate of each edge at y
-            .map(|e| {
+            .filter_map(|e| {
                 let miny = if (e.1).1 < (e.0).1 { (e.1).1 } else { (e.0).1 };
                 let maxy = if (e.1).1 > (e.0).1 { (e.1).1 } else { (e.0).1 };
                 let sx = (e.0).0;
@@ -118,8 +131,6 @@ pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, points: Vec<i32>) {
                     Some(sx + ((ex as f32 - sx as f32) * dy) as i32)
                 }
             })
-            .filter(|x| x.is_some())
-            .map(|x| x.unwrap())
             .collect();
 
         xs.sort_unstable();
@@ -147,7 +158,8 @@ pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, points: Vec<i32>) {
 #[wasm_bindgen]
 /// Draws a filled triangle with a given fill colour given three vertices.
 ///
-/// Vertices are specified as two `isize` co-ordinates in x,y order.
+/// Vertices are specified as two `isize` co-ordinates in x,y order and are mapped through the
+/// [`Canvas`]'s current transform.
 ///
 /// _NOTE_: this routine is much faster than [`fill_polygon()`] and should be used whenever
 /// possible.
@@ -176,73 +188,22 @@ pub fn fill_polygon(c: &mut Canvas, col: &ARGBColour, points: Vec<i32>) {
 /// let mut c = Canvas::new(128, 128);
 /// fill_triangle(&mut c, &ARGBColour::new(255, 255, 0, 0), 0, -10, 10, 10, -10, 10);
 /// ```
+#[allow(clippy::too_many_arguments)]
 pub fn fill_triangle(
     c: &mut Canvas,
     col: &ARGBColour,
-    mut x1: isize,
-    mut y1: isize,
-    mut x2: isize,
-    mut y2: isize,
-    mut x3: isize,
-    mut y3: isize,
+    x1: isize,
+    y1: isize,
+    x2: isize,
+    y2: isize,
+    x3: isize,
+    y3: isize,
 ) {
-    // Sort vertices in y order
-    if y1 > y2 {
-        std::mem::swap(&mut x1, &mut x2);
-        std::mem::swap(&mut y1, &mut y2);
-    }
-    if y1 > y3 {
-        std::mem::swap(&mut x1, &mut x3);
-        std::mem::swap(&mut y1, &mut y3);
-    }
-    if y2 > y3 {
-        std::mem::swap(&mut x2, &mut x3);
-        std::mem::swap(&mut y2, &mut y3);
-    }
-
-    // Draw a single line if the triangle vertices are aligned along an axis
-    if y1 == y2 && y2 == y3 {
-        let pts = vec![x1, x2, x3];
-        let minx = pts.iter().min().unwrap();
-        let maxx = pts.iter().max().unwrap();
-        h_line(c, col, *minx, y1, *maxx);
-    }
-    if x1 == x2 && x2 == x3 {
-        let pts = vec![y1, y2, y3];
-        let miny = pts.iter().min().unwrap();
-        let maxy = pts.iter().max().unwrap();
-        v_line(c, col, x1, *miny, *maxy);
-    }
-
-    if y1 == y2 {
-        if x1 > x2 {
-            std::mem::swap(&mut x1, &mut x2);
-            std::mem::swap(&mut y1, &mut y2);
-        }
-        downward_triangle(c, col, x1, y1, x2, y2, x3, y3);
-    } else if y2 == y3 {
-        if x2 > x3 {
-            std::mem::swap(&mut x2, &mut x3);
-            std::mem::swap(&mut y2, &mut y3);
-        }
-        upward_triangle(c, col, x1, y1, x2, y2, x3, y3);
-    } else {
-
-        // Split triangle in two
-        let mut new_pt_y = y2;
-        let dy = (y2 - y1) as f64 / (y3 - y1) as f64;
-        let mut new_pt_x = x1 + ((x3 - x1) as f64 * dy) as isize;
-
-        // Make sure new point is to the right
-        if x2 > new_pt_x {
-            std::mem::swap(&mut x2, &mut new_pt_x);
-            std::mem::swap(&mut y2, &mut new_pt_y);
-        }
-
-        // Call downward_triangle() and upward_triangle() for new split triangles
-        upward_triangle(c, col, x1, y1, x2, y2, new_pt_x, new_pt_y);
-        downward_triangle(c, col, x2, y2, new_pt_x, new_pt_y, x3, y3);
-    }
+    let t = c.transform();
+    let (x1, y1) = t.apply_pixel(x1, y1);
+    let (x2, y2) = t.apply_pixel(x2, y2);
+    let (x3, y3) = t.apply_pixel(x3, y3);
+    fill_triangle_pixels(c, col, x1, y1, x2, y2, x3, y3);
 }
 
 #[wasm_bindgen]
@@ -347,3 +308,209 @@ pub fn textured_triangle(
         downward_triangle_textured(canv_dst, canv_src, b, new_vert, c, uv_mode);
     }
 }
+
+#[wasm_bindgen]
+/// Determines whether a triangle should be discarded given a [`CullMode`] and the winding order of
+/// front-facing triangles.
+///
+/// Facing is determined from the sign of the triangle's screen-space area.  Degenerate triangles
+/// (with zero area) are culled whenever `cull` is not `CullMode::None`.
+///
+/// [`CullMode`]: ../../types/enum.CullMode.html
+/// [`FrontFace`]: ../../types/enum.FrontFace.html
+/// [`UVVertex`]: ../../types/struct.UVVertex.html
+///
+/// # Arguments:
+///
+///   - `a`: first vertex
+///   - `b`: second vertex
+///   - `c`: third vertex
+///   - `cull`: cull mode (see [`CullMode`])
+///   - `front_face`: winding order of front-facing triangles (see [`FrontFace`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::drawing::shape::is_culled;
+/// use rust_wasm_graphics_lib::types::{CullMode, FrontFace, UVVertex};
+///
+/// // Clockwise on the Canvas (Y axis points down)
+/// let a = UVVertex::new(0, 0, 0.0, 0.0);
+/// let b = UVVertex::new(10, 0, 1.0, 0.0);
+/// let c = UVVertex::new(0, 10, 0.0, 1.0);
+///
+/// assert!(!is_culled(&a, &b, &c, CullMode::Back, FrontFace::Cw));
+/// assert!(is_culled(&a, &b, &c, CullMode::Back, FrontFace::Ccw));
+/// ```
+pub fn is_culled(a: &UVVertex, b: &UVVertex, c: &UVVertex, cull: CullMode, front_face: FrontFace) -> bool {
+    if cull == CullMode::None {
+        return false;
+    }
+
+    // Twice the signed area: positive when clockwise on the Canvas as the Y axis points down
+    let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+    if area == 0 {
+        return true;
+    }
+    let front = (area > 0) == (front_face == FrontFace::Cw);
+    match cull {
+        CullMode::Back => !front,
+        CullMode::Front => front,
+        CullMode::None => false,
+    }
+}
+
+#[wasm_bindgen]
+/// Draws a batch of textured triangles, discarding triangles according to a [`CullMode`].
+///
+/// Vertices should be listed as a flat array of x,y,u,v components, therefore every 12 values
+/// form a single triangle.  Any trailing values which do not form a complete triangle are ignored.
+///
+/// See [`textured_triangle()`] and [`is_culled()`] for further details.
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`CullMode`]: ../../types/enum.CullMode.html
+/// [`FrontFace`]: ../../types/enum.FrontFace.html
+/// [`UVWrapMode`]: ../../types/enum.UVWrapMode.html
+/// [`is_culled()`]: ./fn.is_culled.html
+/// [`textured_triangle()`]: ./fn.textured_triangle.html
+///
+/// # Arguments:
+///
+///   - `canv_dst`: target [`Canvas`]
+///   - `canv_src`: [`Canvas`] used for sampling pixels
+///   - `vertices`: flat list of vertices with components in x,y,u,v order
+///   - `uv_mode`: UV wrapping mode (see [`UVWrapMode`])
+///   - `cull`: cull mode (see [`CullMode`])
+///   - `front_face`: winding order of front-facing triangles (see [`FrontFace`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::shape::textured_triangles;
+/// use rust_wasm_graphics_lib::types::{CullMode, FrontFace, UVWrapMode};
+///
+/// let mut c = Canvas::new(128, 128);
+/// let c2 = Canvas::new(64, 64);
+///
+/// // Draw a quad as two clockwise triangles
+/// textured_triangles(
+///     &mut c,
+///     &c2,
+///     vec![
+///         10.0, 10.0, 0.0, 0.0,   100.0, 10.0, 1.0, 0.0,   10.0, 100.0, 0.0, 1.0,
+///         100.0, 10.0, 1.0, 0.0,  100.0, 100.0, 1.0, 1.0,  10.0, 100.0, 0.0, 1.0,
+///     ],
+///     UVWrapMode::Clamp,
+///     CullMode::Back,
+///     FrontFace::Cw,
+/// );
+/// ```
+pub fn textured_triangles(
+    canv_dst: &mut Canvas,
+    canv_src: &Canvas,
+    vertices: Vec<f64>,
+    uv_mode: UVWrapMode,
+    cull: CullMode,
+    front_face: FrontFace,
+) {
+    let to_vert = |v: &[f64]| UVVertex::new(v[0] as isize, v[1] as isize, v[2], v[3]);
+    vertices.as_slice().chunks_exact(12).for_each(|tri| {
+        let a = to_vert(&tri[0..4]);
+        let b = to_vert(&tri[4..8]);
+        let c = to_vert(&tri[8..12]);
+        if !is_culled(&a, &b, &c, cull, front_face) {
+            textured_triangle(canv_dst, canv_src, &a, &b, &c, uv_mode);
+        }
+    });
+}
+
+#[wasm_bindgen]
+/// Draws a filled triangle given three [`ShadedVertex`] vertices.  The fill colour is multiplied by
+/// the vertex colours, which are interpolated across the triangle (Gouraud shading).
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ShadedVertex`]: ../../types/struct.ShadedVertex.html
+///
+/// # Arguments:
+///
+///   - `canv`: target [`Canvas`]
+///   - `col`: fill colour
+///   - `a`: first vertex
+///   - `b`: second vertex
+///   - `c`: third vertex
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::shape::shaded_triangle;
+/// use rust_wasm_graphics_lib::types::{ARGBColour, ShadedVertex};
+///
+/// let mut c = Canvas::new(128, 128);
+///
+/// // Red triangle fading to black at the bottom
+/// shaded_triangle(
+///     &mut c,
+///     &ARGBColour::new(255, 255, 0, 0),
+///     &ShadedVertex::new( 64,  10, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0),
+///     &ShadedVertex::new(110, 110, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
+///     &ShadedVertex::new( 10, 110, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
+/// );
+/// ```
+pub fn shaded_triangle(canv: &mut Canvas, col: &ARGBColour, a: &ShadedVertex, b: &ShadedVertex, c: &ShadedVertex) {
+    let col: u32 = col.into();
+    shaded_triangle_fill(canv, a, b, c, |at| modulate(col, at[3], at[4], at[5], at[6]));
+}
+
+#[wasm_bindgen]
+/// Draws a textured triangle given three [`ShadedVertex`] vertices and samples pixels from the
+/// `canv_src` [`Canvas`].  Each sampled pixel is multiplied by the vertex colours, which are
+/// interpolated across the triangle (Gouraud shading).
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+/// [`ShadedVertex`]: ../../types/struct.ShadedVertex.html
+/// [`UVWrapMode`]: ../../types/enum.UVWrapMode.html
+///
+/// # Arguments:
+///
+///   - `canv_dst`: target [`Canvas`]
+///   - `canv_src`: [`Canvas`] used for sampling pixels
+///   - `a`: first vertex
+///   - `b`: second vertex
+///   - `c`: third vertex
+///   - `uv_mode`: UV wrapping mode (see [`UVWrapMode`])
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::canvas::Canvas;
+/// use rust_wasm_graphics_lib::drawing::shape::shaded_textured_triangle;
+/// use rust_wasm_graphics_lib::types::{ShadedVertex, UVWrapMode};
+///
+/// let mut c = Canvas::new(128, 128);
+/// let c2 = Canvas::new(64, 64);
+///
+/// // Texture darkened to half brightness
+/// shaded_textured_triangle(
+///     &mut c,
+///     &c2,
+///     &ShadedVertex::new( 64,  10, 0.5, 0.0, 1.0, 0.5, 0.5, 0.5),
+///     &ShadedVertex::new(110, 110, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5),
+///     &ShadedVertex::new( 10, 110, 0.0, 1.0, 1.0, 0.5, 0.5, 0.5),
+///     UVWrapMode::Wrap,
+/// );
+/// ```
+pub fn shaded_textured_triangle(
+    canv_dst: &mut Canvas,
+    canv_src: &Canvas,
+    a: &ShadedVertex,
+    b: &ShadedVertex,
+    c: &ShadedVertex,
+    uv_mode: UVWrapMode,
+) {
+    shaded_triangle_fill(canv_dst, a, b, c, |at| {
+        modulate(canv_src.sample(at[1], at[2], uv_mode), at[3], at[4], at[5], at[6])
+    });
+}
@@ -1,7 +1,8 @@
-use super::lines::h_line;
+use super::lines::{h_line, v_line};
 use crate::canvas::Canvas;
-use crate::types::{ARGBColour, UVWrapMode, UVVertex};
+use crate::types::{ARGBColour, ShadedVertex, UVWrapMode, UVVertex};
 
+#[allow(clippy::too_many_arguments)]
 pub fn downward_triangle(
     c: &mut Canvas,
     col: &ARGBColour,
@@ -21,6 +22,7 @@ pub fn downward_triangle(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn upward_triangle(
     c: &mut Canvas,
     col: &ARGBColour,
@@ -40,6 +42,78 @@ pub fn upward_triangle(
     }
 }
 
+/// Draws a filled triangle given three vertices in pixel co-ordinates, ignoring the Canvas
+/// transform
+#[allow(clippy::too_many_arguments)]
+pub fn fill_triangle_pixels(
+    c: &mut Canvas,
+    col: &ARGBColour,
+    mut x1: isize,
+    mut y1: isize,
+    mut x2: isize,
+    mut y2: isize,
+    mut x3: isize,
+    mut y3: isize,
+) {
+    // Sort vertices in y order
+    if y1 > y2 {
+        std::mem::swap(&mut x1, &mut x2);
+        std::mem::swap(&mut y1, &mut y2);
+    }
+    if y1 > y3 {
+        std::mem::swap(&mut x1, &mut x3);
+        std::mem::swap(&mut y1, &mut y3);
+    }
+    if y2 > y3 {
+        std::mem::swap(&mut x2, &mut x3);
+        std::mem::swap(&mut y2, &mut y3);
+    }
+
+    // Draw a single line if the triangle vertices are aligned along an axis
+    if y1 == y2 && y2 == y3 {
+        let pts = [x1, x2, x3];
+        let minx = pts.iter().min().unwrap();
+        let maxx = pts.iter().max().unwrap();
+        h_line(c, col, *minx, y1, *maxx);
+    }
+    if x1 == x2 && x2 == x3 {
+        let pts = [y1, y2, y3];
+        let miny = pts.iter().min().unwrap();
+        let maxy = pts.iter().max().unwrap();
+        v_line(c, col, x1, *miny, *maxy);
+    }
+
+    if y1 == y2 {
+        if x1 > x2 {
+            std::mem::swap(&mut x1, &mut x2);
+            std::mem::swap(&mut y1, &mut y2);
+        }
+        downward_triangle(c, col, x1, y1, x2, y2, x3, y3);
+    } else if y2 == y3 {
+        if x2 > x3 {
+            std::mem::swap(&mut x2, &mut x3);
+            std::mem::swap(&mut y2, &mut y3);
+        }
+        upward_triangle(c, col, x1, y1, x2, y2, x3, y3);
+    } else {
+
+        // Split triangle in two
+        let mut new_pt_y = y2;
+        let dy = (y2 - y1) as f64 / (y3 - y1) as f64;
+        let mut new_pt_x = x1 + ((x3 - x1) as f64 * dy) as isize;
+
+        // Make sure new point is to the right
+        if x2 > new_pt_x {
+            std::mem::swap(&mut x2, &mut new_pt_x);
+            std::mem::swap(&mut y2, &mut new_pt_y);
+        }
+
+        // Call downward_triangle() and upward_triangle() for new split triangles
+        upward_triangle(c, col, x1, y1, x2, y2, new_pt_x, new_pt_y);
+        downward_triangle(c, col, x2, y2, new_pt_x, new_pt_y, x3, y3);
+    }
+}
+
 pub fn downward_triangle_textured(
     canv_dst: &mut Canvas,
     canv_src: &Canvas,
@@ -50,10 +124,10 @@ pub fn downward_triangle_textured(
 ) {
     let dxl = (bot.x - tl.x) as f64 / (bot.y - tl.y) as f64;
     let dxr = (bot.x - tr.x) as f64 / (bot.y - tr.y) as f64;
-    let dul = (bot.u - tl.u) as f64 / (bot.y - tl.y) as f64;
-    let dur = (bot.u - tr.u) as f64 / (bot.y - tr.y) as f64;
-    let dvl = (bot.v - tl.v) as f64 / (bot.y - tl.y) as f64;
-    let dvr = (bot.v - tr.v) as f64 / (bot.y - tr.y) as f64;
+    let dul = (bot.u - tl.u) / (bot.y - tl.y) as f64;
+    let dur = (bot.u - tr.u) / (bot.y - tr.y) as f64;
+    let dvl = (bot.v - tl.v) / (bot.y - tl.y) as f64;
+    let dvr = (bot.v - tr.v) / (bot.y - tr.y) as f64;
     let y_start = if tl.y > 0 { tl.y } else { 0 };
     let y_end = if bot.y < canv_dst.height() as isize {
         bot.y
@@ -102,10 +176,10 @@ pub fn upward_triangle_textured(
 ) {
     let dxl = (bl.x - top.x) as f64 / (bl.y - top.y) as f64;
     let dxr = (br.x - top.x) as f64 / (br.y - top.y) as f64;
-    let dul = (bl.u - top.u) as f64 / (bl.y - top.y) as f64;
-    let dur = (br.u - top.u) as f64 / (br.y - top.y) as f64;
-    let dvl = (bl.v - top.v) as f64 / (bl.y - top.y) as f64;
-    let dvr = (br.v - top.v) as f64 / (br.y - top.y) as f64;
+    let dul = (bl.u - top.u) / (bl.y - top.y) as f64;
+    let dur = (br.u - top.u) / (br.y - top.y) as f64;
+    let dvl = (bl.v - top.v) / (bl.y - top.y) as f64;
+    let dvr = (br.v - top.v) / (br.y - top.y) as f64;
     let y_start = if top.y > 0 { top.y } else { 0 };
     let y_end = if bl.y < canv_dst.height() as isize {
         bl.y
@@ -143,3 +217,68 @@ pub fn upward_triangle_textured(
         }
     }
 }
+
+/// Multiplies each component of a 32-bit ARGB colour by a factor in the range [0,1]
+pub fn modulate(col: u32, a: f64, r: f64, g: f64, b: f64) -> u32 {
+    let scale = |shift: u32, f: f64| {
+        let c = f64::from((col >> shift) & 0xFF) * f.clamp(0.0, 1.0);
+        ((c + 0.5).floor() as u32) << shift
+    };
+    scale(24, a) | scale(16, r) | scale(8, g) | scale(0, b)
+}
+
+/// Interpolated attributes of a [`ShadedVertex`] in x,u,v,a,r,g,b order
+type Attrs = [f64; 7];
+
+fn attrs(v: &ShadedVertex) -> Attrs {
+    [v.x as f64, v.u, v.v, v.a, v.r, v.g, v.b]
+}
+
+fn lerp_attrs(s: &Attrs, e: &Attrs, t: f64) -> Attrs {
+    let mut out = *s;
+    out.iter_mut().zip(e.iter()).for_each(|(o, e)| *o += (e - *o) * t);
+    out
+}
+
+/// Rasterises a triangle, interpolating (U,V) co-ordinates and colours across it.  The `shade`
+/// function is called for each pixel with the interpolated attributes and returns the pixel's
+/// colour.  Pixels with an alpha of zero are not written.
+pub fn shaded_triangle_fill<F>(c: &mut Canvas, a: &ShadedVertex, b: &ShadedVertex, cv: &ShadedVertex, shade: F)
+where
+    F: Fn(&Attrs) -> u32,
+{
+    let mut v = [*a, *b, *cv];
+    v.sort_by_key(|x| x.y);
+    let [top, mid, bot] = v;
+    if top.y == bot.y {
+        return;
+    }
+    let (at, am, ab) = (attrs(&top), attrs(&mid), attrs(&bot));
+
+    let y_start = if top.y > 0 { top.y } else { 0 };
+    let y_end = if bot.y < c.height() as isize { bot.y } else { c.height() as isize - 1 };
+    let w = c.width() as isize;
+    for y in y_start..=y_end {
+
+        // Long edge runs from top to bottom, short edges from top to middle and middle to bottom
+        let long = lerp_attrs(&at, &ab, (y - top.y) as f64 / (bot.y - top.y) as f64);
+        let short = if y < mid.y || mid.y == bot.y {
+            lerp_attrs(&at, &am, (y - top.y) as f64 / (mid.y - top.y) as f64)
+        } else {
+            lerp_attrs(&am, &ab, (y - mid.y) as f64 / (bot.y - mid.y) as f64)
+        };
+        let (l, r) = if long[0] <= short[0] { (long, short) } else { (short, long) };
+
+        let xl = (l[0] + 0.5).floor() as isize;
+        let xr = (r[0] + 0.5).floor() as isize;
+        let span = (xr - xl) as f64;
+        for x in xl.max(0)..=xr.min(w - 1) {
+            let t = if span > 0.0 { (x - xl) as f64 / span } else { 0.0 };
+            let col = shade(&lerp_attrs(&l, &r, t));
+            if col >> 24 > 0 {
+                let idx = c.buffer_index(x as usize, y as usize);
+                c.buffer_mut()[idx] = col;
+            }
+        }
+    }
+}
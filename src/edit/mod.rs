@@ -0,0 +1,6 @@
+//! Image editing operations which change the size or layout of a whole [`Canvas`]
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+
+pub mod orient;
+pub mod resize;
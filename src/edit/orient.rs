@@ -0,0 +1,143 @@
+//! Flipping, rotation by multiples of 90° and transposition of a Canvas
+//!
+//! Each operation has an in-place version and a version returning a new Canvas.  Operations
+//! which swap rows and columns work through the Canvas in square tiles, so that both the rows
+//! being read and the rows being written stay in cache.
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+
+/// Width and height of the tiles used when swapping rows and columns
+const TILE_SIZE: usize = 32;
+
+/// Copies a `width` x `height` buffer into a new buffer whose width and height are swapped,
+/// where `dst_index(x, y)` gives the destination index of source pixel (x,y)
+fn swap_axes<F: Fn(usize, usize) -> usize>(src: &[u32], width: usize, height: usize, dst_index: F) -> Vec<u32> {
+    let mut dst = vec![0; src.len()];
+    for ty in (0..height).step_by(TILE_SIZE) {
+        for tx in (0..width).step_by(TILE_SIZE) {
+            for y in ty..(ty + TILE_SIZE).min(height) {
+                for x in tx..(tx + TILE_SIZE).min(width) {
+                    dst[dst_index(x, y)] = src[y * width + x];
+                }
+            }
+        }
+    }
+    dst
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Mirrors the Canvas from left to right
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canv = Canvas::new(2, 1);
+    /// canv.load_pixels(vec![1, 2]);
+    /// canv.flip_horizontal();
+    /// assert_eq!(canv.buffer(), &vec![2, 1]);
+    /// ```
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width().max(1);
+        self.buffer_mut().chunks_mut(width).for_each(|row| row.reverse());
+    }
+
+    /// Mirrors the Canvas from top to bottom
+    pub fn flip_vertical(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let buffer = self.buffer_mut();
+        for y in 0..height / 2 {
+            let (top, bottom) = buffer.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+
+    /// Rotates the Canvas 90° clockwise, swapping its width and height
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canv = Canvas::new(2, 1);
+    /// canv.load_pixels(vec![1, 2]);
+    /// canv.rotate_90();
+    /// assert_eq!((canv.width(), canv.height()), (1, 2));
+    /// assert_eq!(canv.buffer(), &vec![1, 2]);
+    /// ```
+    pub fn rotate_90(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let pixels = swap_axes(self.buffer(), width, height, |x, y| x * height + (height - 1 - y));
+        self.replace_pixels(height, width, pixels);
+    }
+
+    /// Rotates the Canvas 180°
+    pub fn rotate_180(&mut self) {
+        self.buffer_mut().reverse();
+    }
+
+    /// Rotates the Canvas 270° clockwise (90° anticlockwise), swapping its width and height
+    pub fn rotate_270(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let pixels = swap_axes(self.buffer(), width, height, |x, y| (width - 1 - x) * height + y);
+        self.replace_pixels(height, width, pixels);
+    }
+
+    /// Mirrors the Canvas along its top-left to bottom-right diagonal, swapping its width and
+    /// height
+    pub fn transpose(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let pixels = swap_axes(self.buffer(), width, height, |x, y| x * height + y);
+        self.replace_pixels(height, width, pixels);
+    }
+
+    /// Returns a copy of the Canvas mirrored from left to right
+    pub fn flipped_horizontal(&self) -> Canvas {
+        let mut out = self.crop(0, 0, self.width(), self.height());
+        out.flip_horizontal();
+        out
+    }
+
+    /// Returns a copy of the Canvas mirrored from top to bottom
+    pub fn flipped_vertical(&self) -> Canvas {
+        let mut out = self.crop(0, 0, self.width(), self.height());
+        out.flip_vertical();
+        out
+    }
+
+    /// Returns a copy of the Canvas rotated 90° clockwise
+    pub fn rotated_90(&self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let mut out = Canvas::new(height, width);
+        *out.buffer_mut() = swap_axes(self.buffer(), width, height, |x, y| x * height + (height - 1 - y));
+        out
+    }
+
+    /// Returns a copy of the Canvas rotated 180°
+    pub fn rotated_180(&self) -> Canvas {
+        let mut out = self.crop(0, 0, self.width(), self.height());
+        out.rotate_180();
+        out
+    }
+
+    /// Returns a copy of the Canvas rotated 270° clockwise (90° anticlockwise)
+    pub fn rotated_270(&self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let mut out = Canvas::new(height, width);
+        *out.buffer_mut() = swap_axes(self.buffer(), width, height, |x, y| (width - 1 - x) * height + y);
+        out
+    }
+
+    /// Returns a copy of the Canvas mirrored along its top-left to bottom-right diagonal
+    pub fn transposed(&self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let mut out = Canvas::new(height, width);
+        *out.buffer_mut() = swap_axes(self.buffer(), width, height, |x, y| x * height + y);
+        out
+    }
+}
@@ -0,0 +1,251 @@
+//! Cropping and resampling of a Canvas
+//!
+//! Resampling is separable: rows are resampled first and then columns.  Colours are weighted by
+//! alpha (i.e. premultiplied) while filtering so that transparent pixels don't darken their
+//! neighbours.  When shrinking, each filter is widened to cover every source pixel which falls
+//! within a destination pixel.
+
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::drawing::blend::{premultiply, unpremultiply};
+use crate::types::{Anchor, ResizeFilter};
+
+/// Returns the radius and weight function of a resampling filter
+fn kernel(filter: ResizeFilter) -> (f64, fn(f64) -> f64) {
+    match filter {
+        ResizeFilter::Nearest | ResizeFilter::Bilinear => (1.0, |x| (1.0 - x.abs()).max(0.0)),
+        ResizeFilter::Bicubic => (2.0, |x| {
+            // Catmull-Rom spline (a = -0.5)
+            let x = x.abs();
+            if x < 1.0 {
+                1.5 * x * x * x - 2.5 * x * x + 1.0
+            } else if x < 2.0 {
+                -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+            } else {
+                0.0
+            }
+        }),
+        ResizeFilter::Lanczos => (3.0, |x| {
+            let sinc = |x: f64| if x.abs() < 1e-8 { 1.0 } else { (PI * x).sin() / (PI * x) };
+            if x.abs() < 3.0 { sinc(x) * sinc(x / 3.0) } else { 0.0 }
+        }),
+    }
+}
+
+/// Contributions of source pixels to a single destination pixel
+struct Contribution {
+    start: usize,
+    weights: Vec<f64>,
+}
+
+/// Calculates the source pixels and weights contributing to each destination pixel along one axis
+fn contributions(src_len: usize, dst_len: usize, filter: ResizeFilter) -> Vec<Contribution> {
+    let scale = src_len as f64 / dst_len as f64;
+    if filter == ResizeFilter::Nearest {
+        return (0..dst_len)
+            .map(|i| {
+                let start = (((i as f64 + 0.5) * scale) as usize).min(src_len - 1);
+                Contribution { start, weights: vec![1.0] }
+            })
+            .collect();
+    }
+
+    let (radius, weight) = kernel(filter);
+    let filter_scale = scale.max(1.0);
+    let support = radius * filter_scale;
+    (0..dst_len)
+        .map(|i| {
+            let centre = (i as f64 + 0.5) * scale - 0.5;
+            let first = ((centre - support).ceil().max(0.0) as usize).min(src_len - 1);
+            let last = ((centre + support).floor().max(0.0) as usize).min(src_len - 1);
+            let mut weights = (first..=last)
+                .map(|j| weight((j as f64 - centre) / filter_scale))
+                .collect::<Vec<f64>>();
+            let total: f64 = weights.iter().sum();
+            if total.abs() > 1e-12 {
+                weights.iter_mut().for_each(|w| *w /= total);
+            }
+            Contribution { start: first, weights }
+        })
+        .collect()
+}
+
+/// Resamples a pixel buffer to a new size
+fn resample(src: &[u32], sw: usize, sh: usize, dw: usize, dh: usize, filter: ResizeFilter) -> Vec<u32> {
+    if filter == ResizeFilter::Nearest {
+        let cols = contributions(sw, dw, filter);
+        let rows = contributions(sh, dh, filter);
+        return rows
+            .iter()
+            .flat_map(|row| cols.iter().map(move |col| src[row.start * sw + col.start]))
+            .collect();
+    }
+
+    // Resample each row horizontally into a premultiplied intermediate buffer
+    let src = src.iter().map(|px| premultiply(*px)).collect::<Vec<[f64; 4]>>();
+    let cols = contributions(sw, dw, filter);
+    let mut tmp = vec![[0.0; 4]; dw * sh];
+    for y in 0..sh {
+        for (x, col) in cols.iter().enumerate() {
+            let mut sum = [0.0; 4];
+            for (i, w) in col.weights.iter().enumerate() {
+                let px = &src[y * sw + col.start + i];
+                sum.iter_mut().zip(px).for_each(|(s, c)| *s += c * w);
+            }
+            tmp[y * dw + x] = sum;
+        }
+    }
+
+    // Then resample each column vertically
+    let rows = contributions(sh, dh, filter);
+    let mut out = vec![0; dw * dh];
+    for (y, row) in rows.iter().enumerate() {
+        for x in 0..dw {
+            let mut sum = [0.0; 4];
+            for (i, w) in row.weights.iter().enumerate() {
+                let px = &tmp[(row.start + i) * dw + x];
+                sum.iter_mut().zip(px).for_each(|(s, c)| *s += c * w);
+            }
+            out[y * dw + x] = unpremultiply(sum);
+        }
+    }
+    out
+}
+
+/// Returns the offset of content of length `src` within a length `dst`, given whether the content
+/// is kept at the start (0), the middle (1) or the end (2)
+fn anchor_offset(src: usize, dst: usize, position: u8) -> isize {
+    let space = dst as isize - src as isize;
+    match position {
+        0 => 0,
+        1 => space / 2,
+        _ => space,
+    }
+}
+
+/// Copies the intersection of a source buffer placed at (x,y) into a destination buffer
+#[allow(clippy::too_many_arguments)]
+fn copy_region(src: &[u32], sw: usize, sh: usize, dst: &mut [u32], dw: usize, dh: usize, x: isize, y: isize) {
+    let x0 = x.max(0) as usize;
+    let x1 = (x + sw as isize).clamp(0, dw as isize) as usize;
+    if x0 >= x1 {
+        return;
+    }
+    for dy in y.max(0) as usize..(y + sh as isize).clamp(0, dh as isize) as usize {
+        let sy = (dy as isize - y) as usize;
+        let sx = (x0 as isize - x) as usize;
+        dst[dy * dw + x0..dy * dw + x1].copy_from_slice(&src[sy * sw + sx..sy * sw + sx + x1 - x0]);
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Returns a new Canvas containing a rectangular region of this Canvas
+    ///
+    /// Parts of the region outside of this Canvas are transparent.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `x`: X co-ordinate of the top-left corner of the region
+    ///   - `y`: Y co-ordinate of the top-left corner of the region
+    ///   - `width`: width of the region
+    ///   - `height`: height of the region
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canv = Canvas::new(4, 4);
+    /// canv.buffer_mut()[5] = 0xFFFF0000;
+    /// let cropped = canv.crop(1, 1, 2, 2);
+    /// assert_eq!(cropped.buffer(), &vec![0xFFFF0000, 0, 0, 0]);
+    /// ```
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Canvas {
+        let mut out = Canvas::new(width, height);
+        copy_region(
+            self.buffer(),
+            self.width(),
+            self.height(),
+            out.buffer_mut(),
+            width,
+            height,
+            -(x as isize),
+            -(y as isize),
+        );
+        out
+    }
+
+    /// Resamples the Canvas to a new size
+    ///
+    /// # Arguments:
+    ///
+    ///   - `width`: new width in pixels
+    ///   - `height`: new height in pixels
+    ///   - `filter`: ResizeFilter used to resample the pixels
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::{ARGBColour, ResizeFilter};
+    ///
+    /// let mut canv = Canvas::new(64, 32);
+    /// canv.clear(&ARGBColour::new(255, 10, 20, 30));
+    /// canv.resize(16, 48, ResizeFilter::Lanczos);
+    /// assert_eq!((canv.width(), canv.height()), (16, 48));
+    /// assert_eq!(canv.buffer()[100], 0xFF0A141E);
+    /// ```
+    pub fn resize(&mut self, width: usize, height: usize, filter: ResizeFilter) {
+        let pixels = if self.width() == 0 || self.height() == 0 || width == 0 || height == 0 {
+            vec![0; width * height]
+        } else {
+            resample(self.buffer(), self.width(), self.height(), width, height, filter)
+        };
+        self.replace_pixels(width, height, pixels);
+    }
+
+    /// Changes the size of the Canvas without scaling its content, which is positioned within
+    /// the new size according to an anchor.  New areas are transparent and content outside of the
+    /// new size is discarded.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `width`: new width in pixels
+    ///   - `height`: new height in pixels
+    ///   - `anchor`: Anchor giving the position of the existing content
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::Anchor;
+    ///
+    /// let mut canv = Canvas::new(1, 1);
+    /// canv.buffer_mut()[0] = 0xFFFFFFFF;
+    /// canv.resize_canvas(2, 2, Anchor::BottomRight);
+    /// assert_eq!(canv.buffer(), &vec![0, 0, 0, 0xFFFFFFFF]);
+    /// ```
+    pub fn resize_canvas(&mut self, width: usize, height: usize, anchor: Anchor) {
+        let (h_pos, v_pos) = match anchor {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+        let x = anchor_offset(self.width(), width, h_pos);
+        let y = anchor_offset(self.height(), height, v_pos);
+        let mut pixels = vec![0; width * height];
+        copy_region(self.buffer(), self.width(), self.height(), &mut pixels, width, height, x, y);
+        self.replace_pixels(width, height, pixels);
+    }
+}
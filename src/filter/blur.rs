@@ -0,0 +1,179 @@
+//! Box and Gaussian blurs
+//!
+//! Both blurs are separable, filtering every row and then every column.  Box blurs use a running
+//! sum so their cost doesn't depend on the radius.  Gaussian blurs with a small radius use an
+//! exact Gaussian kernel, while larger radii are approximated by three successive box blurs.
+
+use wasm_bindgen::prelude::*;
+
+use super::edge_index;
+use crate::canvas::Canvas;
+use crate::drawing::blend::{premultiply, unpremultiply};
+use crate::types::EdgeMode;
+
+/// Largest Gaussian radius (standard deviation) for which an exact kernel is used
+const MAX_EXACT_GAUSSIAN: f64 = 3.0;
+
+/// Number of box blurs approximating a large Gaussian blur
+const GAUSSIAN_BOXES: usize = 3;
+
+/// Blurs a line of premultiplied pixels with a box of `2 * radius + 1` pixels using a running sum
+fn box_line(line: &[[f64; 4]], out: &mut [[f64; 4]], radius: usize, mode: EdgeMode) {
+    let len = line.len();
+    let get = |i: isize| edge_index(i, len, mode).map_or([0.0; 4], |i| line[i]);
+    let r = radius as isize;
+    let scale = 1.0 / (2 * radius + 1) as f64;
+
+    let mut sum = [0.0; 4];
+    for i in -r..=r {
+        sum.iter_mut().zip(&get(i)).for_each(|(s, c)| *s += c);
+    }
+    for (i, dst) in out.iter_mut().enumerate() {
+        *dst = sum.map(|s| s * scale);
+        let (add, sub) = (get(i as isize + r + 1), get(i as isize - r));
+        for c in 0..4 {
+            sum[c] += add[c] - sub[c];
+        }
+    }
+}
+
+/// Convolves a line of premultiplied pixels with a symmetric kernel of `2 * radius + 1` weights
+fn kernel_line(line: &[[f64; 4]], out: &mut [[f64; 4]], kernel: &[f64], mode: EdgeMode) {
+    let len = line.len();
+    let r = (kernel.len() / 2) as isize;
+    for (i, dst) in out.iter_mut().enumerate() {
+        let mut sum = [0.0; 4];
+        for (k, w) in kernel.iter().enumerate() {
+            if let Some(j) = edge_index(i as isize + k as isize - r, len, mode) {
+                sum.iter_mut().zip(&line[j]).for_each(|(s, c)| *s += c * w);
+            }
+        }
+        *dst = sum;
+    }
+}
+
+/// Applies a line filter to every row and then every column of a Canvas
+fn separable<F: Fn(&[[f64; 4]], &mut [[f64; 4]])>(canvas: &mut Canvas, filter: F) {
+    let (width, height) = (canvas.width(), canvas.height());
+    if width == 0 || height == 0 {
+        return;
+    }
+    let mut pixels = canvas.buffer().iter().map(|px| premultiply(*px)).collect::<Vec<[f64; 4]>>();
+
+    let mut out = vec![[0.0; 4]; width.max(height)];
+    for row in pixels.chunks_mut(width) {
+        filter(row, &mut out[..width]);
+        row.copy_from_slice(&out[..width]);
+    }
+    let mut column = vec![[0.0; 4]; height];
+    for x in 0..width {
+        for (y, px) in column.iter_mut().enumerate() {
+            *px = pixels[y * width + x];
+        }
+        filter(&column, &mut out[..height]);
+        for (y, px) in out[..height].iter().enumerate() {
+            pixels[y * width + x] = *px;
+        }
+    }
+
+    for (dst, px) in canvas.buffer_mut().iter_mut().zip(pixels) {
+        *dst = unpremultiply(px);
+    }
+}
+
+/// Returns the radii of box blurs which together approximate a Gaussian blur with standard
+/// deviation `sigma`
+fn gaussian_boxes(sigma: f64) -> [usize; GAUSSIAN_BOXES] {
+    let n = GAUSSIAN_BOXES as f64;
+    let ideal = (12.0 * sigma * sigma / n + 1.0).sqrt();
+    let mut lower = ideal.floor() as usize;
+    if lower.is_multiple_of(2) {
+        lower -= 1;
+    }
+    let wl = lower as f64;
+    let m = ((12.0 * sigma * sigma - n * wl * wl - 4.0 * n * wl - 3.0 * n) / (-4.0 * wl - 4.0)).round();
+    let mut radii = [0; GAUSSIAN_BOXES];
+    for (i, r) in radii.iter_mut().enumerate() {
+        let size = if (i as f64) < m { lower } else { lower + 2 };
+        *r = (size - 1) / 2;
+    }
+    radii
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Blurs the Canvas by averaging each pixel with every pixel within a square around it
+    ///
+    /// # Arguments:
+    ///
+    ///   - `radius`: distance from the centre of the square to its edges, so each pixel is the
+    ///     average of `(2 * radius + 1)²` pixels.  Radii larger than the Canvas are reduced to its
+    ///     larger dimension.
+    ///   - `edge_mode`: EdgeMode for pixels beyond the edges of the Canvas
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::EdgeMode;
+    ///
+    /// let mut canv = Canvas::new(3, 1);
+    /// canv.load_pixels(vec![0xFF000000, 0xFF000000, 0xFFFFFFFF]);
+    /// canv.box_blur(1, EdgeMode::Clamp);
+    /// assert_eq!(canv.buffer(), &vec![0xFF000000, 0xFF555555, 0xFFAAAAAA]);
+    /// ```
+    pub fn box_blur(&mut self, radius: usize, edge_mode: EdgeMode) {
+        let radius = radius.min(self.width().max(self.height()));
+        if radius > 0 {
+            separable(self, |line, out| box_line(line, out, radius, edge_mode));
+        }
+    }
+
+    /// Blurs the Canvas with a Gaussian filter
+    ///
+    /// # Arguments:
+    ///
+    ///   - `radius`: standard deviation of the Gaussian in pixels, as used by CSS `blur()`.  Radii
+    ///     which are not positive and finite leave the Canvas unchanged, and radii larger than the
+    ///     Canvas are reduced to its larger dimension, which already blurs it almost uniformly.
+    ///   - `edge_mode`: EdgeMode for pixels beyond the edges of the Canvas
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::{Anchor, ARGBColour, EdgeMode};
+    ///
+    /// // A drop shadow: a blurred, offset copy of a shape which fades out at the edges
+    /// let mut shadow = Canvas::new(64, 64);
+    /// shadow.clear(&ARGBColour::new(128, 0, 0, 0));
+    /// shadow.resize_canvas(96, 96, Anchor::Center);
+    /// shadow.gaussian_blur(8.0, EdgeMode::Transparent);
+    /// assert!(shadow.buffer()[0] >> 24 < shadow.buffer()[48 * 96 + 48] >> 24);
+    /// ```
+    pub fn gaussian_blur(&mut self, radius: f64, edge_mode: EdgeMode) {
+        if !radius.is_finite() || radius <= 0.0 {
+            return;
+        }
+        let radius = radius.min(self.width().max(self.height()) as f64);
+        if radius <= MAX_EXACT_GAUSSIAN {
+            let r = (radius * 3.0).ceil() as isize;
+            let mut kernel = (-r..=r)
+                .map(|x| (-(x * x) as f64 / (2.0 * radius * radius)).exp())
+                .collect::<Vec<f64>>();
+            let total: f64 = kernel.iter().sum();
+            kernel.iter_mut().for_each(|w| *w /= total);
+            separable(self, |line, out| kernel_line(line, out, &kernel, edge_mode));
+        } else {
+            let radii = gaussian_boxes(radius);
+            separable(self, |line, out| {
+                let mut tmp = line.to_vec();
+                for r in &radii {
+                    box_line(&tmp, out, *r, edge_mode);
+                    tmp.copy_from_slice(out);
+                }
+            });
+        }
+    }
+}
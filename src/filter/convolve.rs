@@ -0,0 +1,282 @@
+//! Convolution of a Canvas with arbitrary kernels, and preset filters built on it
+//!
+//! Kernels are given row by row and are applied without being flipped, so the first weight
+//! applies to the top-left neighbour of each pixel.  The centre of a kernel is at
+//! `(width / 2, height / 2)`.
+//!
+//! Colours are premultiplied by alpha before they are convolved.  Alpha can either be convolved
+//! along with the colour channels, or preserved, in which case only the colour channels are
+//! convolved and each pixel keeps its alpha, with fully transparent pixels left unchanged.
+
+use wasm_bindgen::prelude::*;
+
+use super::edge_index;
+use crate::canvas::Canvas;
+use crate::drawing::blend::{premultiply, unpremultiply};
+use crate::types::EdgeMode;
+
+/// 3x3 kernel which increases the contrast between each pixel and its neighbours
+pub const SHARPEN: [f64; 9] = [0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0];
+
+/// 3x3 kernel which makes the image appear raised, lit from the top-left
+pub const EMBOSS: [f64; 9] = [-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0];
+
+/// 3x3 Laplacian kernel, which responds to edges in every direction
+pub const LAPLACIAN: [f64; 9] = [-1.0, -1.0, -1.0, -1.0, 8.0, -1.0, -1.0, -1.0, -1.0];
+
+/// 3x3 Sobel kernels, which measure the horizontal and vertical gradients
+pub const SOBEL_X: [f64; 9] = [-1.0, 0.0, 1.0, -2.0, 0.0, 2.0, -1.0, 0.0, 1.0];
+pub const SOBEL_Y: [f64; 9] = [-1.0, -2.0, -1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 1.0];
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// A convolution kernel: a grid of weights, and a divisor and bias applied to each weighted sum.
+/// See [`Canvas::convolve_kernel()`].
+///
+/// [`Canvas::convolve_kernel()`]: ../../canvas/struct.Canvas.html#method.convolve_kernel
+pub struct Kernel {
+    weights: Vec<f64>,
+    width: usize,
+    height: usize,
+    divisor: f64,
+    bias: f64,
+}
+
+#[wasm_bindgen]
+impl Kernel {
+
+    /// Creates a new Kernel
+    ///
+    /// # Arguments:
+    ///
+    ///   - `weights`: weights, row by row
+    ///   - `width`: number of columns
+    ///   - `height`: number of rows
+    ///   - `divisor`: value each weighted sum is divided by (0 is treated as 1)
+    ///   - `bias`: value added to each channel after dividing, in the range [0,255]
+    pub fn new(weights: Vec<f64>, width: usize, height: usize, divisor: f64, bias: f64) -> Self {
+        Self { weights, width, height, divisor, bias }
+    }
+}
+
+/// Returns the premultiplied [r, g, b, a] components of every pixel of a Canvas
+fn premultiplied_pixels(canvas: &Canvas) -> Vec<[f64; 4]> {
+    canvas.buffer().iter().map(|px| premultiply(*px)).collect()
+}
+
+/// Replaces the colour of a pixel with premultiplied [r, g, b] components plus a bias, keeping
+/// its alpha.  Fully transparent pixels are left unchanged.
+fn set_colour(dst: &mut u32, px: [f64; 4], bias: f64) {
+    let alpha = *dst >> 24;
+    if alpha == 0 {
+        return;
+    }
+    let scale = 255.0 / f64::from(alpha);
+    let c = |v: f64| ((v * scale + bias).clamp(0.0, 255.0) + 0.5).floor() as u32;
+    *dst = (alpha << 24) | (c(px[0]) << 16) | (c(px[1]) << 8) | c(px[2]);
+}
+
+/// Correlates pixels with a `kw` x `kh` kernel, returning the weighted sum at each pixel
+fn correlate(
+    canvas: &Canvas,
+    pixels: &[[f64; 4]],
+    kernel: &[f64],
+    kw: usize,
+    kh: usize,
+    mode: EdgeMode,
+) -> Vec<[f64; 4]> {
+    let (width, height) = (canvas.width(), canvas.height());
+    let (cx, cy) = ((kw / 2) as isize, (kh / 2) as isize);
+    let mut out = vec![[0.0; 4]; pixels.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0; 4];
+            for (k, w) in kernel.iter().enumerate() {
+                if *w == 0.0 {
+                    continue;
+                }
+                let sx = edge_index(x as isize + (k % kw) as isize - cx, width, mode);
+                let sy = edge_index(y as isize + (k / kw) as isize - cy, height, mode);
+                if let (Some(sx), Some(sy)) = (sx, sy) {
+                    sum.iter_mut().zip(&pixels[sy * width + sx]).for_each(|(s, c)| *s += c * w);
+                }
+            }
+            out[y * width + x] = sum;
+        }
+    }
+    out
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Convolves the Canvas with a kernel
+    ///
+    /// Each output channel is the weighted sum of the surrounding pixels divided by `divisor`,
+    /// plus `bias`.  Returns `false` and leaves the Canvas unchanged if `kernel` does not contain
+    /// `width * height` weights.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `kernel`: weights, row by row
+    ///   - `width`: number of columns in the kernel
+    ///   - `height`: number of rows in the kernel
+    ///   - `divisor`: value each weighted sum is divided by (0 is treated as 1)
+    ///   - `bias`: value added to each channel after dividing, in the range [0,255]
+    ///   - `edge_mode`: EdgeMode for pixels beyond the edges of the Canvas
+    ///   - `include_alpha`: if `true` alpha is convolved along with premultiplied colours,
+    ///     otherwise only the colour channels are convolved and alpha is preserved
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::EdgeMode;
+    ///
+    /// // Horizontal motion blur
+    /// let mut canv = Canvas::new(3, 1);
+    /// canv.load_pixels(vec![0xFF000000, 0xFFFFFFFF, 0xFF000000]);
+    /// assert!(canv.convolve(&[1.0, 1.0, 1.0], 3, 1, 3.0, 0.0, EdgeMode::Clamp, false));
+    /// assert_eq!(canv.buffer(), &vec![0xFF555555, 0xFF555555, 0xFF555555]);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn convolve(
+        &mut self,
+        kernel: &[f64],
+        width: usize,
+        height: usize,
+        divisor: f64,
+        bias: f64,
+        edge_mode: EdgeMode,
+        include_alpha: bool,
+    ) -> bool {
+        if width == 0 || height == 0 || width.checked_mul(height) != Some(kernel.len()) {
+            return false;
+        }
+        let divisor = if divisor == 0.0 { 1.0 } else { divisor };
+        let pixels = premultiplied_pixels(self);
+        let sums = correlate(self, &pixels, kernel, width, height, edge_mode);
+        for (dst, sum) in self.buffer_mut().iter_mut().zip(sums) {
+            let px = sum.map(|s| s / divisor);
+            if include_alpha {
+                *dst = unpremultiply(px.map(|c| c + bias));
+            } else {
+                set_colour(dst, px, bias);
+            }
+        }
+        true
+    }
+
+    /// Convolves the Canvas with a Kernel.  See [`convolve()`].
+    ///
+    /// [`convolve()`]: #method.convolve
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::filter::convolve::Kernel;
+    /// use rust_wasm_graphics_lib::types::EdgeMode;
+    ///
+    /// let mut canv = Canvas::new(3, 1);
+    /// canv.load_pixels(vec![0xFF000000, 0xFFFFFFFF, 0xFF000000]);
+    /// let kernel = Kernel::new(vec![1.0, 1.0, 1.0], 3, 1, 3.0, 0.0);
+    /// assert!(canv.convolve_kernel(&kernel, EdgeMode::Clamp, false));
+    /// assert_eq!(canv.buffer(), &vec![0xFF555555, 0xFF555555, 0xFF555555]);
+    /// ```
+    pub fn convolve_kernel(&mut self, kernel: &Kernel, edge_mode: EdgeMode, include_alpha: bool) -> bool {
+        self.convolve(
+            &kernel.weights,
+            kernel.width,
+            kernel.height,
+            kernel.divisor,
+            kernel.bias,
+            edge_mode,
+            include_alpha,
+        )
+    }
+
+    /// Sharpens the Canvas with the [`SHARPEN`] kernel, preserving alpha
+    ///
+    /// [`SHARPEN`]: ../filter/convolve/constant.SHARPEN.html
+    pub fn sharpen(&mut self, edge_mode: EdgeMode) {
+        self.convolve(&SHARPEN, 3, 3, 1.0, 0.0, edge_mode, false);
+    }
+
+    /// Embosses the Canvas with the [`EMBOSS`] kernel, preserving alpha
+    ///
+    /// [`EMBOSS`]: ../filter/convolve/constant.EMBOSS.html
+    pub fn emboss(&mut self, edge_mode: EdgeMode) {
+        self.convolve(&EMBOSS, 3, 3, 1.0, 0.0, edge_mode, false);
+    }
+
+    /// Replaces each colour channel with the magnitude of its gradient, measured with the
+    /// [`SOBEL_X`] and [`SOBEL_Y`] kernels, so edges are bright and flat areas are black.  Alpha
+    /// is preserved.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::EdgeMode;
+    ///
+    /// let mut canv = Canvas::new(4, 1);
+    /// canv.load_pixels(vec![0xFF000000, 0xFF000000, 0xFF202020, 0xFF202020]);
+    /// canv.sobel(EdgeMode::Clamp);
+    /// assert_eq!(canv.buffer(), &vec![0xFF000000, 0xFF808080, 0xFF808080, 0xFF000000]);
+    /// ```
+    ///
+    /// [`SOBEL_X`]: ../filter/convolve/constant.SOBEL_X.html
+    /// [`SOBEL_Y`]: ../filter/convolve/constant.SOBEL_Y.html
+    pub fn sobel(&mut self, edge_mode: EdgeMode) {
+        let pixels = premultiplied_pixels(self);
+        let gx = correlate(self, &pixels, &SOBEL_X, 3, 3, edge_mode);
+        let gy = correlate(self, &pixels, &SOBEL_Y, 3, 3, edge_mode);
+        for ((dst, gx), gy) in self.buffer_mut().iter_mut().zip(gx).zip(gy) {
+            let magnitude = [0, 1, 2, 3].map(|c| (gx[c] * gx[c] + gy[c] * gy[c]).sqrt());
+            set_colour(dst, magnitude, 0.0);
+        }
+    }
+
+    /// Highlights edges in every direction with the [`LAPLACIAN`] kernel, preserving alpha
+    ///
+    /// [`LAPLACIAN`]: ../filter/convolve/constant.LAPLACIAN.html
+    pub fn laplacian(&mut self, edge_mode: EdgeMode) {
+        self.convolve(&LAPLACIAN, 3, 3, 1.0, 0.0, edge_mode, false);
+    }
+
+    /// Sharpens the Canvas by adding the difference between it and a Gaussian blurred copy of
+    /// itself.  Alpha is preserved.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `radius`: radius of the Gaussian blur (see [`Canvas::gaussian_blur()`])
+    ///   - `amount`: multiple of the difference to add, e.g. 0.5 for 50%
+    ///   - `threshold`: smallest difference in a channel which is sharpened, so that noise in flat
+    ///     areas isn't amplified
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canv = Canvas::new(4, 1);
+    /// canv.load_pixels(vec![0xFF404040, 0xFF404040, 0xFF808080, 0xFF808080]);
+    /// canv.unsharp_mask(1.0, 1.0, 0);
+    /// assert!(canv.buffer()[1] & 0xFF < 0x40 && canv.buffer()[2] & 0xFF > 0x80);
+    /// ```
+    ///
+    /// [`Canvas::gaussian_blur()`]: #method.gaussian_blur
+    pub fn unsharp_mask(&mut self, radius: f64, amount: f64, threshold: u8) {
+        let mut blurred = self.crop(0, 0, self.width(), self.height());
+        blurred.gaussian_blur(radius, EdgeMode::Clamp);
+        for (dst, blur) in self.buffer_mut().iter_mut().zip(blurred.buffer()) {
+            let (src, blur) = (premultiply(*dst), premultiply(*blur));
+            let px = [0, 1, 2, 3].map(|c| {
+                let diff = src[c] - blur[c];
+                if diff.abs() < f64::from(threshold) { src[c] } else { src[c] + diff * amount }
+            });
+            set_colour(dst, px, 0.0);
+        }
+    }
+}
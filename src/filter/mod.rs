@@ -0,0 +1,26 @@
+//! Neighbourhood filters such as blurs, which compute each pixel of a [`Canvas`] from the pixels
+//! surrounding it
+//!
+//! Filters work on premultiplied colours so that transparent pixels don't darken their
+//! neighbours, and treat pixels beyond the edges of the Canvas according to an [`EdgeMode`].
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+//! [`EdgeMode`]: ../types/enum.EdgeMode.html
+
+pub mod blur;
+pub mod convolve;
+
+use crate::types::EdgeMode;
+
+/// Returns the index of the pixel used for position `i` along a line of `len` pixels, or `None`
+/// if the pixel is transparent
+fn edge_index(i: isize, len: usize, mode: EdgeMode) -> Option<usize> {
+    if i >= 0 && (i as usize) < len {
+        return Some(i as usize);
+    }
+    match mode {
+        EdgeMode::Clamp => Some(i.clamp(0, len as isize - 1) as usize),
+        EdgeMode::Wrap => Some(i.rem_euclid(len as isize) as usize),
+        EdgeMode::Transparent => None,
+    }
+}
@@ -0,0 +1,249 @@
+//! AngelCode BMFont descriptor loading
+//!
+//! Both the text and binary (version 3) `.fnt` formats are supported.  Only the line height, base,
+//! character and kerning blocks are used, and all characters must be on a single texture page,
+//! which is passed in as the font atlas.
+
+use wasm_bindgen::prelude::*;
+
+use super::{BitmapFont, FontError};
+use crate::canvas::Canvas;
+
+/// Magic bytes at the start of a binary BMFont file
+const BINARY_MAGIC: &[u8] = b"BMF";
+
+/// A character description shared by the text and binary formats
+struct CharDesc {
+    id: u32,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    offset_x: isize,
+    offset_y: isize,
+    advance: isize,
+    page: u32,
+}
+
+/// The parts of a BMFont descriptor used to build a BitmapFont
+#[derive(Default)]
+struct Descriptor {
+    line_height: usize,
+    base: usize,
+
+    /// Texture page file names with their IDs, sorted by ID
+    pages: Vec<(usize, String)>,
+    chars: Vec<CharDesc>,
+    kerning: Vec<(u32, u32, isize)>,
+}
+
+/// Splits a text format line into its tag and `key=value` pairs, removing quotes from values
+fn tokenise(line: &str) -> Option<(&str, Vec<(&str, &str)>)> {
+    let line = line.trim();
+    let tag_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let tag = &line[..tag_end];
+    if tag.is_empty() {
+        return None;
+    }
+    let mut pairs = vec![];
+    let mut rest = line[tag_end..].trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            rest = quoted.get(end + 1..).unwrap_or("");
+            &quoted[..end]
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        pairs.push((key, value));
+        rest = rest.trim_start();
+    }
+    Some((tag, pairs))
+}
+
+fn parse_text(src: &str) -> Result<Descriptor, FontError> {
+    let mut desc = Descriptor::default();
+    for (line_idx, line) in src.lines().enumerate() {
+        let (tag, pairs) = match tokenise(line) {
+            Some(x) => x,
+            None => continue,
+        };
+        let get = |key: &str| pairs.iter().find(|p| p.0 == key).map(|p| p.1);
+        let num = |key: &str| -> Result<isize, FontError> {
+            let value = get(key).unwrap_or("0");
+            value.parse::<isize>().map_err(|_| {
+                FontError::new(&format!("line {}: invalid value '{}' for '{}'", line_idx + 1, value, key))
+            })
+        };
+        match tag {
+            "common" => {
+                desc.line_height = num("lineHeight")?.max(0) as usize;
+                desc.base = num("base")?.max(0) as usize;
+            },
+            "page" => {
+                let id = num("id")?.max(0) as usize;
+                let file = get("file").unwrap_or("").to_string();
+                match desc.pages.binary_search_by_key(&id, |p| p.0) {
+                    Ok(idx) => desc.pages[idx].1 = file,
+                    Err(idx) => desc.pages.insert(idx, (id, file)),
+                }
+            },
+            "char" => desc.chars.push(CharDesc {
+                id: num("id")? as u32,
+                x: num("x")?.max(0) as usize,
+                y: num("y")?.max(0) as usize,
+                width: num("width")?.max(0) as usize,
+                height: num("height")?.max(0) as usize,
+                offset_x: num("xoffset")?,
+                offset_y: num("yoffset")?,
+                advance: num("xadvance")?,
+                page: num("page")? as u32,
+            }),
+            "kerning" => desc.kerning.push((num("first")? as u32, num("second")? as u32, num("amount")?)),
+            _ => {},
+        }
+    }
+    Ok(desc)
+}
+
+fn parse_binary(src: &[u8]) -> Result<Descriptor, FontError> {
+    if src.len() < 4 || src[3] != 3 {
+        return Err(FontError::new("unsupported binary BMFont version"));
+    }
+    let u16_at = |b: &[u8], i: usize| u16::from_le_bytes([b[i], b[i + 1]]);
+    let i16_at = |b: &[u8], i: usize| i16::from_le_bytes([b[i], b[i + 1]]) as isize;
+    let u32_at = |b: &[u8], i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+
+    let mut desc = Descriptor::default();
+    let mut pos = 4;
+    while pos < src.len() {
+        if pos + 5 > src.len() {
+            return Err(FontError::new("truncated block header"));
+        }
+        let block_type = src[pos];
+        let size = u32_at(src, pos + 1) as usize;
+        let end = (pos + 5).checked_add(size);
+        let block = end
+            .and_then(|end| src.get(pos + 5..end))
+            .ok_or_else(|| FontError::new(&format!("truncated block {}", block_type)))?;
+        match block_type {
+            2 => {
+                if block.len() < 4 {
+                    return Err(FontError::new("invalid common block"));
+                }
+                desc.line_height = u16_at(block, 0) as usize;
+                desc.base = u16_at(block, 2) as usize;
+            },
+            3 => {
+                desc.pages = block
+                    .split(|b| *b == 0)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| String::from_utf8_lossy(s).into_owned())
+                    .enumerate()
+                    .collect();
+            },
+            4 => {
+                for c in block.chunks_exact(20) {
+                    desc.chars.push(CharDesc {
+                        id: u32_at(c, 0),
+                        x: u16_at(c, 4) as usize,
+                        y: u16_at(c, 6) as usize,
+                        width: u16_at(c, 8) as usize,
+                        height: u16_at(c, 10) as usize,
+                        offset_x: i16_at(c, 12),
+                        offset_y: i16_at(c, 14),
+                        advance: i16_at(c, 16),
+                        page: u32::from(c[18]),
+                    });
+                }
+            },
+            5 => {
+                for k in block.chunks_exact(10) {
+                    desc.kerning.push((u32_at(k, 0), u32_at(k, 4), i16_at(k, 8)));
+                }
+            },
+            _ => {},
+        }
+        pos += 5 + block.len();
+    }
+    Ok(desc)
+}
+
+fn parse(src: &[u8]) -> Result<Descriptor, FontError> {
+    if src.starts_with(BINARY_MAGIC) {
+        parse_binary(src)
+    } else {
+        let text = std::str::from_utf8(src).map_err(|_| FontError::new("text descriptor is not valid UTF-8"))?;
+        parse_text(text)
+    }
+}
+
+/// Returns the file names of all texture pages referenced by a BMFont descriptor, ordered by page
+/// ID
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::font::bmfont::bmfont_pages;
+///
+/// let src = "common lineHeight=16 base=12\npage id=0 file=\"my font_0.png\"\n";
+/// assert_eq!(bmfont_pages(src.as_bytes()).unwrap(), vec!["my font_0.png"]);
+/// ```
+pub fn bmfont_pages(src: &[u8]) -> Result<Vec<String>, FontError> {
+    parse(src).map(|d| d.pages.into_iter().map(|p| p.1).collect())
+}
+
+#[wasm_bindgen]
+impl BitmapFont {
+
+    /// Creates a new BitmapFont from a BMFont descriptor in either the text or binary format.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `atlas`: Canvas containing the font's texture page
+    ///   - `src`: `.fnt` file contents
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    ///
+    /// let font = BitmapFont::from_bmfont(Canvas::new(64, 64), b"
+    ///     common lineHeight=16 base=12 scaleW=64 scaleH=64 pages=1
+    ///     page id=0 file=\"font.png\"
+    ///     chars count=2
+    ///     char id=65 x=0 y=0 width=8 height=10 xoffset=0 yoffset=2 xadvance=9 page=0 chnl=15
+    ///     char id=86 x=8 y=0 width=8 height=10 xoffset=0 yoffset=2 xadvance=9 page=0 chnl=15
+    ///     kernings count=1
+    ///     kerning first=65 second=86 amount=-2
+    /// ").unwrap();
+    /// assert_eq!(font.line_height(), 16);
+    /// assert_eq!(font.kerning('A', 'V'), -2);
+    /// ```
+    pub fn from_bmfont(atlas: Canvas, src: &[u8]) -> Result<BitmapFont, FontError> {
+        let desc = parse(src)?;
+        let mut font = BitmapFont::new(atlas, desc.line_height, desc.base);
+        for c in &desc.chars {
+            if c.page != 0 {
+                return Err(FontError::new(&format!("character {} is on page {}, only one page is supported", c.id, c.page)));
+            }
+
+            // Some tools describe a fallback glyph with an ID of -1, which is ignored
+            if let Some(ch) = std::char::from_u32(c.id) {
+                font.add_glyph(ch, c.x, c.y, c.width, c.height, c.offset_x, c.offset_y, c.advance);
+            }
+        }
+        for (first, second, amount) in desc.kerning {
+            if let (Some(a), Some(b)) = (std::char::from_u32(first), std::char::from_u32(second)) {
+                font.add_kerning_pair(a, b, amount);
+            }
+        }
+        Ok(font)
+    }
+}
@@ -0,0 +1,296 @@
+//! Text measurement and layout of wrapped text within a box
+//!
+//! Text is split into paragraphs at each `\n` and each paragraph is word-wrapped at spaces to fit
+//! the width of the box.  Words which are too wide for a line on their own are broken between
+//! characters.  Lines which do not fit the height of the box are discarded, optionally ending the
+//! last visible line with an ellipsis.
+
+use wasm_bindgen::prelude::*;
+
+use super::BitmapFont;
+use crate::canvas::Canvas;
+use crate::types::{ARGBColour, TextAlign};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The size of a block of text drawn with a [`BitmapFont`]
+///
+/// [`BitmapFont`]: ../struct.BitmapFont.html
+pub struct TextMetrics {
+
+    /// Advance width of the widest line
+    pub width: isize,
+
+    /// Distance from the top of the first line to the bottom of the last line
+    pub height: isize,
+
+    /// Number of lines
+    pub line_count: usize,
+
+    /// Distance between the tops of consecutive lines, including line spacing
+    pub line_advance: isize,
+
+    /// Distance from the top of a line to the baseline
+    pub base: usize,
+}
+
+/// A single line of laid out text
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutLine {
+    pub text: String,
+
+    /// Characters with their pen positions relative to the left of the layout box
+    pub glyphs: Vec<(char, isize)>,
+
+    /// Y co-ordinate of the top of the line relative to the top of the layout box
+    pub y: isize,
+
+    /// Total advance of the line before any justification
+    pub width: isize,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// Text wrapped and aligned within a box, ready to be drawn with [`Canvas::draw_text_layout()`]
+///
+/// [`Canvas::draw_text_layout()`]: ../../canvas/struct.Canvas.html#method.draw_text_layout
+pub struct TextLayout {
+    lines: Vec<LayoutLine>,
+    height: isize,
+    truncated: bool,
+}
+
+#[wasm_bindgen]
+impl TextLayout {
+
+    /// Returns the number of lines
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns the text of a line, or an empty string if the line does not exist
+    pub fn line_text(&self, line: usize) -> String {
+        self.lines.get(line).map(|l| l.text.clone()).unwrap_or_default()
+    }
+
+    /// Returns the distance from the top of the first line to the bottom of the last line
+    pub fn height(&self) -> isize {
+        self.height
+    }
+
+    /// Returns `true` if lines were discarded because they did not fit the box height
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl TextLayout {
+
+    /// Returns all lines
+    pub fn lines(&self) -> &Vec<LayoutLine> {
+        &self.lines
+    }
+}
+
+/// Splits a paragraph into lines no wider than `max_width`
+fn wrap_paragraph(font: &BitmapFont, paragraph: &str, max_width: isize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if font.line_width(&candidate) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        // Break words which don't fit on a line of their own between characters
+        line = String::new();
+        for ch in word.chars() {
+            line.push(ch);
+            if font.line_width(&line) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(line);
+                line = ch.to_string();
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+#[wasm_bindgen]
+impl BitmapFont {
+
+    /// Measures text as it would be drawn by [`Canvas::draw_text()`]
+    ///
+    /// [`Canvas::draw_text()`]: ../canvas/struct.Canvas.html#method.draw_text
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    ///
+    /// let font = BitmapFont::fixed(Canvas::new(128, 48), 8, 8, 32);
+    /// let metrics = font.measure_text("Hello\nworld!");
+    /// assert_eq!((metrics.width, metrics.height, metrics.line_count), (48, 16, 2));
+    /// ```
+    pub fn measure_text(&self, text: &str) -> TextMetrics {
+        let width = text.split('\n').map(|l| self.line_width(l)).max().unwrap_or(0);
+        let line_count = text.split('\n').count();
+        TextMetrics {
+            width,
+            height: self.lines_height(line_count),
+            line_count,
+            line_advance: self.line_advance(),
+            base: self.base,
+        }
+    }
+
+    /// Wraps and aligns text within a box.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `text`: text to lay out, with paragraphs separated by `\n`
+    ///   - `width`: box width, which lines are wrapped to fit
+    ///   - `height`: box height, below which lines are discarded
+    ///   - `align`: horizontal alignment of each line within the box
+    ///   - `ellipsis`: if set and lines are discarded, the last visible line ends with an ellipsis
+    ///     (`…` if the font has a glyph for it, otherwise `...`)
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    /// use rust_wasm_graphics_lib::types::TextAlign;
+    ///
+    /// let font = BitmapFont::fixed(Canvas::new(128, 48), 8, 8, 32);
+    /// let layout = font.layout_text("The quick brown fox jumps over the lazy dog", 80, 16, TextAlign::Left, true);
+    /// assert_eq!(layout.line_text(0), "The quick");
+    /// assert_eq!(layout.line_text(1), "brown f...");
+    /// ```
+    pub fn layout_text(&self, text: &str, width: usize, height: usize, align: TextAlign, ellipsis: bool) -> TextLayout {
+        let max_width = width as isize;
+
+        // Wrap each paragraph, noting which lines end a paragraph as they are never justified
+        let mut wrapped: Vec<(String, bool)> = vec![];
+        for paragraph in text.split('\n') {
+            let lines = wrap_paragraph(self, paragraph, max_width);
+            let last = lines.len() - 1;
+            wrapped.extend(lines.into_iter().enumerate().map(|(i, l)| (l, i == last)));
+        }
+
+        // Discard lines below the box, ending the last visible line with an ellipsis
+        let advance = self.line_advance();
+        let mut max_lines = 0;
+        while max_lines < wrapped.len() && self.lines_height(max_lines + 1) <= height as isize {
+            max_lines += 1;
+        }
+        let truncated = wrapped.len() > max_lines;
+        wrapped.truncate(max_lines);
+        if truncated && ellipsis {
+            if let Some(last) = wrapped.last_mut() {
+                let dots = if self.glyph('\u{2026}').is_some() { "\u{2026}" } else { "..." };
+                while !last.0.is_empty() && self.line_width(&format!("{}{}", last.0, dots)) > max_width {
+                    last.0.pop();
+                }
+                last.0 = format!("{}{}", last.0.trim_end(), dots);
+                last.1 = true;
+            }
+        }
+
+        let lines = wrapped
+            .into_iter()
+            .enumerate()
+            .map(|(i, (text, paragraph_end))| {
+                let (glyphs, line_width) = self.line_glyphs(&text);
+                let spaces = glyphs.iter().filter(|g| g.0.ch == ' ').count() as isize;
+                let base = match align {
+                    TextAlign::Left | TextAlign::Justify => 0,
+                    TextAlign::Center => (max_width - line_width) / 2,
+                    TextAlign::Right => max_width - line_width,
+                };
+
+                // Distribute the remaining width between the spaces of justified lines
+                let extra = if align == TextAlign::Justify && !paragraph_end && spaces > 0 {
+                    (max_width - line_width).max(0)
+                } else {
+                    0
+                };
+                let mut line_x = base;
+                let mut space_idx = 0;
+                let glyphs = glyphs
+                    .iter()
+                    .map(|(g, pen_x)| {
+                        if g.ch == ' ' {
+                            space_idx += 1;
+                            line_x = base + extra * space_idx / spaces;
+                        }
+                        (g.ch, line_x + pen_x)
+                    })
+                    .collect();
+                LayoutLine { text, glyphs, y: i as isize * advance, width: line_width }
+            })
+            .collect::<Vec<_>>();
+
+        TextLayout {
+            height: self.lines_height(lines.len()),
+            lines,
+            truncated,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Draws a TextLayout with the top-left corner of its box at (x,y)
+    ///
+    /// # Arguments:
+    ///
+    ///   - `font`: BitmapFont the layout was created with
+    ///   - `layout`: TextLayout to draw
+    ///   - `x`: X co-ordinate of the left of the layout box
+    ///   - `y`: Y co-ordinate of the top of the layout box
+    ///   - `tint`: colour multiplied into each glyph pixel (white leaves glyphs unchanged)
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    /// use rust_wasm_graphics_lib::types::{ARGBColour, TextAlign};
+    ///
+    /// let font = BitmapFont::fixed(Canvas::new(128, 48), 8, 8, 32);
+    /// let layout = font.layout_text("A dialog box with justified text", 100, 40, TextAlign::Justify, true);
+    ///
+    /// let mut dst = Canvas::new(320, 240);
+    /// dst.draw_text_layout(&font, &layout, 110, 180, &ARGBColour::new(255, 255, 255, 255));
+    /// ```
+    pub fn draw_text_layout(&mut self, font: &BitmapFont, layout: &TextLayout, x: isize, y: isize, tint: &ARGBColour) {
+        let tint = u32::from(tint);
+        for line in &layout.lines {
+            for &(ch, pen_x) in &line.glyphs {
+                if let Some(glyph) = font.glyph_or_fallback(ch) {
+                    font.draw_glyph(self, glyph, x + pen_x, y + line.y, tint);
+                }
+            }
+        }
+    }
+}
+
+impl BitmapFont {
+
+    /// Returns the height of a number of lines, excluding the spacing after the last line
+    fn lines_height(&self, count: usize) -> isize {
+        if count == 0 {
+            0
+        } else {
+            count as isize * self.line_advance() - self.line_spacing
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Bitmap fonts drawn from glyphs on an atlas [`Canvas`]
+//!
+//! Glyphs are positioned using the same metrics as AngelCode BMFont: each glyph is drawn at an
+//! offset from the pen position at the top of the line, after which the pen moves right by the
+//! glyph's advance plus any kerning between it and the next character.  Glyph pixels are
+//! multiplied by a tint colour when drawn, so white glyphs take on the tint colour, and partially
+//! transparent (e.g. anti-aliased) glyph pixels are blended with the destination.
+//!
+//! Fonts can also be loaded from AngelCode BMFont descriptors (see [`bmfont`]) and PC Screen Font
+//! files (see [`psf`]), or rasterised on demand from TrueType outlines (see [`ttf`]).
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+//! [`bmfont`]: ./bmfont/index.html
+//! [`psf`]: ./psf/index.html
+//! [`ttf`]: ./ttf/index.html
+
+pub mod bmfont;
+pub mod layout;
+pub mod psf;
+pub mod ttf;
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::{BlitRegion, Canvas};
+use crate::drawing::blend::blend_over;
+use crate::types::{ARGBColour, TextAlign};
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// An error encountered while loading a font
+pub struct FontError {
+    message: String,
+}
+
+#[wasm_bindgen]
+impl FontError {
+
+    /// Returns a description of the error
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl FontError {
+    fn new(message: &str) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// A single glyph within a [`BitmapFont`] atlas
+///
+/// [`BitmapFont`]: ./struct.BitmapFont.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    pub ch: char,
+
+    /// Position and size of the glyph within the atlas
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+
+    /// Offset from the pen position at the top of the line to the top-left corner of the glyph
+    pub offset_x: isize,
+    pub offset_y: isize,
+
+    /// Distance to move the pen right after drawing the glyph
+    pub advance: isize,
+}
+
+#[wasm_bindgen]
+/// A font made up of glyphs on an atlas [`Canvas`]
+///
+/// [`Canvas`]: ../canvas/struct.Canvas.html
+pub struct BitmapFont {
+    atlas: Canvas,
+    glyphs: Vec<Glyph>,
+
+    /// Pairs of characters with the adjustment to the advance between them, ordered by pair
+    kerning: Vec<((char, char), isize)>,
+    line_height: usize,
+    base: usize,
+    line_spacing: isize,
+}
+
+/// Multiplies each component of an ARGB colour by the corresponding component of a tint colour
+fn tint_pixel(px: u32, tint: u32) -> u32 {
+    let mul = |shift: u32| ((((px >> shift) & 0xFF) * ((tint >> shift) & 0xFF) + 127) / 255) << shift;
+    mul(24) | mul(16) | mul(8) | mul(0)
+}
+
+#[wasm_bindgen]
+impl BitmapFont {
+
+    /// Creates a new variable-width BitmapFont with no glyphs
+    ///
+    /// # Arguments:
+    ///
+    ///   - `atlas`: Canvas containing all glyphs
+    ///   - `line_height`: distance in pixels between the tops of consecutive lines
+    ///   - `base`: distance in pixels from the top of a line to the baseline
+    pub fn new(atlas: Canvas, line_height: usize, base: usize) -> Self {
+        Self {
+            atlas,
+            glyphs: vec![],
+            kerning: vec![],
+            line_height,
+            base,
+            line_spacing: 0,
+        }
+    }
+
+    /// Creates a new fixed-width BitmapFont from an atlas containing a grid of equally sized
+    /// glyphs, ordered left to right and top to bottom by character code.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `atlas`: Canvas containing all glyphs
+    ///   - `glyph_width`: width of each glyph cell in pixels
+    ///   - `glyph_height`: height of each glyph cell in pixels
+    ///   - `first_char`: character code of the glyph in the top-left cell (e.g. 32 for a font
+    ///     starting at the space character)
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    ///
+    /// // 16x6 grid of 8x8 glyphs covering ASCII characters 32 to 127
+    /// let font = BitmapFont::fixed(Canvas::new(128, 48), 8, 8, 32);
+    /// assert_eq!(font.glyph_count(), 96);
+    /// assert_eq!(font.glyph('A').unwrap().x, 8);
+    /// ```
+    pub fn fixed(atlas: Canvas, glyph_width: usize, glyph_height: usize, first_char: u32) -> Self {
+        let glyph_width = glyph_width.max(1);
+        let glyph_height = glyph_height.max(1);
+        let columns = atlas.width() / glyph_width;
+        let rows = atlas.height() / glyph_height;
+        let mut font = Self::new(atlas, glyph_height, glyph_height);
+        for i in 0..columns * rows {
+            if let Some(ch) = first_char.checked_add(i as u32).and_then(std::char::from_u32) {
+                font.add_glyph(
+                    ch,
+                    (i % columns) * glyph_width,
+                    (i / columns) * glyph_height,
+                    glyph_width,
+                    glyph_height,
+                    0,
+                    0,
+                    glyph_width as isize,
+                );
+            }
+        }
+        font
+    }
+
+    /// Adds a glyph, replacing any existing glyph for the same character
+    ///
+    /// # Arguments:
+    ///
+    ///   - `ch`: character
+    ///   - `x`: X co-ordinate of the top-left corner of the glyph within the atlas
+    ///   - `y`: Y co-ordinate of the top-left corner of the glyph within the atlas
+    ///   - `width`: glyph width
+    ///   - `height`: glyph height
+    ///   - `offset_x`: X offset from the pen position to the left of the glyph
+    ///   - `offset_y`: Y offset from the top of the line to the top of the glyph
+    ///   - `advance`: distance to move the pen right after drawing the glyph
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_glyph(
+        &mut self,
+        ch: char,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        offset_x: isize,
+        offset_y: isize,
+        advance: isize,
+    ) {
+        let glyph = Glyph { ch, x, y, width, height, offset_x, offset_y, advance };
+        match self.glyphs.binary_search_by_key(&ch, |g| g.ch) {
+            Ok(idx) => self.glyphs[idx] = glyph,
+            Err(idx) => self.glyphs.insert(idx, glyph),
+        }
+    }
+
+    /// Sets the adjustment in pixels to the advance when `second` follows `first`, replacing any
+    /// existing adjustment for the same pair
+    pub fn add_kerning_pair(&mut self, first: char, second: char, amount: isize) {
+        match self.kerning.binary_search_by_key(&(first, second), |k| k.0) {
+            Ok(idx) => self.kerning[idx].1 = amount,
+            Err(idx) => self.kerning.insert(idx, ((first, second), amount)),
+        }
+    }
+
+    /// Returns the adjustment in pixels to the advance when `second` follows `first`
+    pub fn kerning(&self, first: char, second: char) -> isize {
+        match self.kerning.binary_search_by_key(&(first, second), |k| k.0) {
+            Ok(idx) => self.kerning[idx].1,
+            Err(_) => 0,
+        }
+    }
+
+    /// Returns the number of glyphs
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns the distance in pixels between the tops of consecutive lines
+    pub fn line_height(&self) -> usize {
+        self.line_height
+    }
+
+    /// Returns the distance in pixels from the top of a line to the baseline
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns the extra spacing in pixels added between lines
+    pub fn line_spacing(&self) -> isize {
+        self.line_spacing
+    }
+
+    /// Sets the extra spacing in pixels added between lines, which may be negative
+    pub fn set_line_spacing(&mut self, spacing: isize) {
+        self.line_spacing = spacing;
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Draws text using a BitmapFont.  Lines are separated by `\n` and characters without a
+    /// glyph are drawn as `?` if the font has one, otherwise they are skipped.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `font`: BitmapFont to draw with
+    ///   - `text`: text to draw
+    ///   - `x`: X co-ordinate which each line is aligned to
+    ///   - `y`: Y co-ordinate of the top of the first line
+    ///   - `tint`: colour multiplied into each glyph pixel (white leaves glyphs unchanged)
+    ///   - `align`: horizontal alignment of each line.  `Justify` is treated as `Left`, see
+    ///     [`BitmapFont::layout_text()`] for wrapped and justified text.
+    ///
+    /// [`BitmapFont::layout_text()`]: ../font/struct.BitmapFont.html#method.layout_text
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    /// use rust_wasm_graphics_lib::types::{ARGBColour, TextAlign};
+    ///
+    /// let font = BitmapFont::fixed(Canvas::new(128, 48), 8, 8, 32);
+    /// let mut dst = Canvas::new(320, 240);
+    ///
+    /// // Yellow score centred at the top of the Canvas
+    /// dst.draw_text(&font, "SCORE\n001200", 160, 4, &ARGBColour::new(255, 255, 255, 0), TextAlign::Center);
+    /// ```
+    pub fn draw_text(&mut self, font: &BitmapFont, text: &str, x: isize, y: isize, tint: &ARGBColour, align: TextAlign) {
+        let tint = u32::from(tint);
+        let mut line_y = y;
+        for line in text.split('\n') {
+            let (glyphs, width) = font.line_glyphs(line);
+            let line_x = match align {
+                TextAlign::Left | TextAlign::Justify => x,
+                TextAlign::Center => x - width / 2,
+                TextAlign::Right => x - width,
+            };
+            for (glyph, pen_x) in glyphs {
+                font.draw_glyph(self, glyph, line_x + pen_x, line_y, tint);
+            }
+            line_y += font.line_advance();
+        }
+    }
+}
+
+impl BitmapFont {
+
+    /// Returns the atlas Canvas
+    pub fn atlas(&self) -> &Canvas {
+        &self.atlas
+    }
+
+    /// Returns the atlas Canvas for modification, e.g. when adding glyphs
+    pub fn atlas_mut(&mut self) -> &mut Canvas {
+        &mut self.atlas
+    }
+
+    /// Returns the glyph for a character
+    pub fn glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.binary_search_by_key(&ch, |g| g.ch).ok().map(|i| &self.glyphs[i])
+    }
+
+    /// Returns all glyphs ordered by character
+    pub fn glyphs(&self) -> &Vec<Glyph> {
+        &self.glyphs
+    }
+
+    fn glyph_or_fallback(&self, ch: char) -> Option<&Glyph> {
+        self.glyph(ch).or_else(|| self.glyph('?'))
+    }
+
+    /// Returns the distance between the tops of consecutive lines, including line spacing
+    fn line_advance(&self) -> isize {
+        self.line_height as isize + self.line_spacing
+    }
+
+    /// Returns the glyphs of a single line of text with the pen position of each relative to the
+    /// start of the line, and the total advance of the line including kerning
+    fn line_glyphs(&self, line: &str) -> (Vec<(&Glyph, isize)>, isize) {
+        let mut glyphs = vec![];
+        let mut prev: Option<char> = None;
+        let mut pen_x = 0;
+        for glyph in line.chars().filter_map(|ch| self.glyph_or_fallback(ch)) {
+            if let Some(p) = prev {
+                pen_x += self.kerning(p, glyph.ch);
+            }
+            glyphs.push((glyph, pen_x));
+            pen_x += glyph.advance;
+            prev = Some(glyph.ch);
+        }
+        (glyphs, pen_x)
+    }
+
+    /// Returns the total advance of a single line of text, including kerning
+    fn line_width(&self, line: &str) -> isize {
+        self.line_glyphs(line).1
+    }
+
+    /// Draws a single glyph with the pen at (pen_x,line_y), skipping transparent pixels and
+    /// blending partially transparent pixels
+    fn draw_glyph(&self, dst: &mut Canvas, glyph: &Glyph, pen_x: isize, line_y: isize, tint: u32) {
+        let gx = pen_x + glyph.offset_x;
+        let gy = line_y + glyph.offset_y;
+        let region = match BlitRegion::new(dst, &self.atlas, glyph.x, glyph.y, glyph.width, glyph.height, gx, gy) {
+            Some(r) => r,
+            None => return,
+        };
+        let (ox_start, ox_end) = region.off_x;
+        for oy in region.off_y.0..region.off_y.1 {
+            let src_start = self.atlas.buffer_index(region.src_x + ox_start, region.src_y + oy);
+            let dst_start = dst.buffer_index((gx + ox_start as isize) as usize, (gy + oy as isize) as usize);
+            let src_row = &self.atlas.buffer()[src_start..src_start + (ox_end - ox_start)];
+            let dst_row = &mut dst.buffer_mut()[dst_start..dst_start + (ox_end - ox_start)];
+            for (d, px) in dst_row.iter_mut().zip(src_row) {
+                if px >> 24 > 0 {
+                    *d = blend_over(tint_pixel(*px, tint), *d);
+                }
+            }
+        }
+    }
+}
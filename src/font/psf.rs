@@ -0,0 +1,177 @@
+//! PC Screen Font (PSF1 and PSF2) loading
+//!
+//! PSF files contain monochrome glyph bitmaps, as used by the Linux console.  The glyphs are drawn
+//! into a new atlas [`Canvas`] as opaque white pixels so they can be tinted when drawn.  If the
+//! file has a Unicode table then each glyph is mapped to all of the characters listed for it,
+//! otherwise glyph N is mapped to character code N.
+//!
+//! [`Canvas`]: ../../canvas/struct.Canvas.html
+
+use wasm_bindgen::prelude::*;
+
+use super::{BitmapFont, FontError};
+use crate::canvas::Canvas;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_MODE_HAS_SEQ: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_START_SEQ: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_START_SEQ: u8 = 0xFE;
+
+/// Number of glyphs in each row of the generated atlas
+const ATLAS_COLUMNS: usize = 32;
+
+/// Glyph bitmaps and character mappings read from a PSF file
+struct PsfGlyphs<'a> {
+    width: usize,
+    height: usize,
+    count: usize,
+    bytes_per_glyph: usize,
+    bitmaps: &'a [u8],
+
+    /// Characters mapped to each glyph, or `None` if the file has no Unicode table
+    chars: Option<Vec<Vec<char>>>,
+}
+
+fn parse_psf1(src: &[u8]) -> Result<PsfGlyphs<'_>, FontError> {
+    if src.len() < 4 {
+        return Err(FontError::new("truncated PSF1 header"));
+    }
+    let mode = src[2];
+    let height = src[3] as usize;
+    if height == 0 {
+        return Err(FontError::new("invalid PSF1 glyph height"));
+    }
+    let count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+    let bitmaps = src
+        .get(4..4 + count * height)
+        .ok_or_else(|| FontError::new("truncated PSF1 glyph data"))?;
+
+    let chars = if mode & (PSF1_MODE_HAS_TABLE | PSF1_MODE_HAS_SEQ) != 0 {
+        let table: Vec<u16> = src[4 + count * height..]
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        let mut chars = vec![];
+        for entry in table.split(|x| *x == PSF1_SEPARATOR).take(count) {
+
+            // Multi-character sequences follow the single characters and are not supported
+            let singles = entry.split(|x| *x == PSF1_START_SEQ).next().unwrap_or(&[]);
+            chars.push(singles.iter().filter_map(|x| std::char::from_u32(u32::from(*x))).collect());
+        }
+        Some(chars)
+    } else {
+        None
+    };
+
+    Ok(PsfGlyphs { width: 8, height, count, bytes_per_glyph: height, bitmaps, chars })
+}
+
+fn parse_psf2(src: &[u8]) -> Result<PsfGlyphs<'_>, FontError> {
+    if src.len() < 32 {
+        return Err(FontError::new("truncated PSF2 header"));
+    }
+    let u32_at = |i: usize| u32::from_le_bytes([src[i], src[i + 1], src[i + 2], src[i + 3]]) as usize;
+    let header_size = u32_at(8);
+    let flags = u32_at(12) as u32;
+    let count = u32_at(16);
+    let bytes_per_glyph = u32_at(20);
+    let height = u32_at(24);
+    let width = u32_at(28);
+    if width == 0 || height == 0 {
+        return Err(FontError::new("invalid PSF2 glyph dimensions"));
+    }
+    if height.checked_mul(width.div_ceil(8)).is_none_or(|size| bytes_per_glyph < size) {
+        return Err(FontError::new("PSF2 glyph size is too small for its dimensions"));
+    }
+    let glyphs_end = count
+        .checked_mul(bytes_per_glyph)
+        .and_then(|x| x.checked_add(header_size))
+        .filter(|x| *x <= src.len())
+        .ok_or_else(|| FontError::new("truncated PSF2 glyph data"))?;
+    let bitmaps = &src[header_size..glyphs_end];
+
+    let chars = if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+        let mut chars = vec![];
+        for entry in src[glyphs_end..].split(|x| *x == PSF2_SEPARATOR).take(count) {
+            let singles = entry.split(|x| *x == PSF2_START_SEQ).next().unwrap_or(&[]);
+            let text = std::str::from_utf8(singles)
+                .map_err(|_| FontError::new("PSF2 Unicode table is not valid UTF-8"))?;
+            chars.push(text.chars().collect());
+        }
+        Some(chars)
+    } else {
+        None
+    };
+
+    Ok(PsfGlyphs { width, height, count, bytes_per_glyph, bitmaps, chars })
+}
+
+#[wasm_bindgen]
+impl BitmapFont {
+
+    /// Creates a new fixed-width BitmapFont from a PC Screen Font (PSF1 or PSF2) file
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: `.psf` file contents
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::font::BitmapFont;
+    ///
+    /// // PSF1 font with 256 8x8 glyphs and no Unicode table
+    /// let mut src = vec![0x36, 0x04, 0x00, 8];
+    /// src.extend(vec![0xFF; 256 * 8]);
+    ///
+    /// let font = BitmapFont::from_psf(&src).unwrap();
+    /// assert_eq!(font.line_height(), 8);
+    /// assert_eq!(font.glyph('A').unwrap().advance, 8);
+    /// ```
+    pub fn from_psf(src: &[u8]) -> Result<BitmapFont, FontError> {
+        let psf = if src.starts_with(&PSF1_MAGIC) {
+            parse_psf1(src)?
+        } else if src.starts_with(&PSF2_MAGIC) {
+            parse_psf2(src)?
+        } else {
+            return Err(FontError::new("not a PSF1 or PSF2 file"));
+        };
+
+        // Draw all glyphs into an atlas as opaque white pixels
+        let columns = ATLAS_COLUMNS.min(psf.count.max(1));
+        let rows = psf.count.div_ceil(columns);
+        let mut atlas = Canvas::new(columns * psf.width, rows * psf.height);
+        let row_bytes = psf.width.div_ceil(8);
+        for (i, bitmap) in psf.bitmaps.chunks_exact(psf.bytes_per_glyph).enumerate() {
+            let (gx, gy) = ((i % columns) * psf.width, (i / columns) * psf.height);
+            for y in 0..psf.height {
+                for x in 0..psf.width {
+                    if bitmap[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0 {
+                        let idx = atlas.buffer_index(gx + x, gy + y);
+                        atlas.buffer_mut()[idx] = 0xFFFF_FFFF;
+                    }
+                }
+            }
+        }
+
+        let mut font = BitmapFont::new(atlas, psf.height, psf.height);
+        for i in 0..psf.count {
+            let (gx, gy) = ((i % columns) * psf.width, (i / columns) * psf.height);
+            let chars = match &psf.chars {
+                Some(c) => c.get(i).cloned().unwrap_or_default(),
+                None => std::char::from_u32(i as u32).into_iter().collect(),
+            };
+            for ch in chars {
+                font.add_glyph(ch, gx, gy, psf.width, psf.height, 0, 0, psf.width as isize);
+            }
+        }
+        Ok(font)
+    }
+}
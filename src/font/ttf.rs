@@ -0,0 +1,662 @@
+//! TrueType outline loading and rasterisation
+//!
+//! Glyph outlines are read from the `glyf` table of TrueType and OpenType fonts, including
+//! composite glyphs, and characters are mapped to glyphs with format 4 or 12 `cmap` subtables.
+//! OpenType fonts with CFF outlines are not supported, and hinting instructions are ignored.
+//!
+//! Outlines are converted to a [`Path`] and rasterised with anti-aliasing by [`fill_path()`].  A
+//! [`GlyphCache`] rasterises glyphs at a fixed pixel size into the atlas of a [`BitmapFont`] as
+//! they are first needed.
+//!
+//! [`BitmapFont`]: ../struct.BitmapFont.html
+//! [`GlyphCache`]: ./struct.GlyphCache.html
+//! [`Path`]: ../../drawing/path/struct.Path.html
+//! [`fill_path()`]: ../../drawing/path/fn.fill_path.html
+
+use wasm_bindgen::prelude::*;
+
+use super::layout::{TextLayout, TextMetrics};
+use super::{BitmapFont, FontError};
+use crate::canvas::Canvas;
+use crate::drawing::path::{fill_path, Path};
+use crate::transform::Transform2D;
+use crate::types::{ARGBColour, TextAlign};
+
+/// Maximum nesting depth of composite glyphs
+const MAX_COMPOSITE_DEPTH: usize = 8;
+
+/// Minimum width of a GlyphCache atlas in pixels
+const MIN_ATLAS_WIDTH: usize = 256;
+
+// Simple glyph point flags
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const REPEAT: u8 = 0x08;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+// Composite glyph component flags
+const ARGS_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const HAVE_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const HAVE_X_AND_Y_SCALE: u16 = 0x0040;
+const HAVE_TWO_BY_TWO: u16 = 0x0080;
+
+/// A glyph outline point in font units
+#[derive(Clone, Copy)]
+struct Point {
+    x: f64,
+    y: f64,
+    on_curve: bool,
+}
+
+fn truncated() -> FontError {
+    FontError::new("unexpected end of font data")
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, FontError> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(truncated)
+}
+
+fn read_i16(data: &[u8], pos: usize) -> Result<i16, FontError> {
+    read_u16(data, pos).map(|x| x as i16)
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, FontError> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(truncated)
+}
+
+fn read_u8(data: &[u8], pos: usize) -> Result<u8, FontError> {
+    data.get(pos).copied().ok_or_else(truncated)
+}
+
+/// Reads a signed 2.14 fixed point number
+fn read_f2dot14(data: &[u8], pos: usize) -> Result<f64, FontError> {
+    read_i16(data, pos).map(|x| f64::from(x) / 16384.0)
+}
+
+#[wasm_bindgen]
+/// A TrueType or OpenType font with TrueType (`glyf`) outlines
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    units_per_em: u16,
+    glyph_count: u16,
+    long_loca: bool,
+    ascender: i16,
+    descender: i16,
+    line_gap: i16,
+    h_metric_count: u16,
+
+    /// Offsets of tables within the font data
+    loca: usize,
+    glyf: usize,
+    hmtx: usize,
+
+    /// Offset and format of the chosen character map subtable
+    cmap: Option<(usize, u16)>,
+}
+
+#[wasm_bindgen]
+impl TrueTypeFont {
+
+    /// Parses a TrueType (`.ttf`) or OpenType (`.otf`) font file
+    ///
+    /// # Arguments:
+    ///
+    ///   - `data`: font file contents
+    pub fn from_bytes(data: &[u8]) -> Result<TrueTypeFont, FontError> {
+        let data = data.to_vec();
+        let version = read_u32(&data, 0)?;
+        if version != 0x0001_0000 && &data[0..4] != b"true" && &data[0..4] != b"OTTO" {
+            return Err(FontError::new("not a TrueType or OpenType font"));
+        }
+
+        let table_count = read_u16(&data, 4)? as usize;
+        let mut tables = vec![];
+        for i in 0..table_count {
+            let rec = 12 + i * 16;
+            let tag = data.get(rec..rec + 4).ok_or_else(truncated)?.to_vec();
+            tables.push((tag, read_u32(&data, rec + 8)? as usize));
+        }
+        let table = |tag: &[u8]| -> Result<usize, FontError> {
+            tables.iter().find(|t| t.0 == tag).map(|t| t.1).ok_or_else(|| {
+                if tag == b"glyf" && tables.iter().any(|t| t.0 == b"CFF ") {
+                    FontError::new("CFF outlines are not supported")
+                } else {
+                    FontError::new(&format!("missing '{}' table", String::from_utf8_lossy(tag)))
+                }
+            })
+        };
+
+        let glyf = table(b"glyf")?;
+        let head = table(b"head")?;
+        let maxp = table(b"maxp")?;
+        let hhea = table(b"hhea")?;
+        let units_per_em = read_u16(&data, head + 18)?;
+        if units_per_em == 0 {
+            return Err(FontError::new("invalid units per em"));
+        }
+
+        let mut font = TrueTypeFont {
+            units_per_em,
+            glyph_count: read_u16(&data, maxp + 4)?,
+            long_loca: read_i16(&data, head + 50)? != 0,
+            ascender: read_i16(&data, hhea + 4)?,
+            descender: read_i16(&data, hhea + 6)?,
+            line_gap: read_i16(&data, hhea + 8)?,
+            h_metric_count: read_u16(&data, hhea + 34)?,
+            loca: table(b"loca")?,
+            glyf,
+            hmtx: table(b"hmtx")?,
+            cmap: None,
+            data: vec![],
+        };
+        font.cmap = Self::find_cmap(&data, table(b"cmap")?)?;
+        font.data = data;
+        Ok(font)
+    }
+
+    /// Returns the number of font units per em square
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    /// Returns the number of glyphs in the font
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    /// Returns the distance from the baseline to the top of the line in font units
+    pub fn ascender(&self) -> i16 {
+        self.ascender
+    }
+
+    /// Returns the distance from the baseline to the bottom of the line in font units, which is
+    /// usually negative
+    pub fn descender(&self) -> i16 {
+        self.descender
+    }
+
+    /// Returns the recommended gap between lines in font units
+    pub fn line_gap(&self) -> i16 {
+        self.line_gap
+    }
+
+    /// Returns the glyph index for a character, or 0 (the "missing glyph") if the font does not
+    /// contain the character
+    pub fn glyph_index(&self, ch: char) -> u32 {
+        let c = ch as u32;
+        let lookup = || -> Result<u32, FontError> {
+            let (sub, format) = match self.cmap {
+                Some(x) => x,
+                None => return Ok(0),
+            };
+            let data = &self.data;
+            if format == 12 {
+                let groups = read_u32(data, sub + 12)? as usize;
+                let (mut lo, mut hi) = (0, groups);
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let rec = sub + 16 + mid * 12;
+                    let (start, end) = (read_u32(data, rec)?, read_u32(data, rec + 4)?);
+                    if c < start {
+                        hi = mid;
+                    } else if c > end {
+                        lo = mid + 1;
+                    } else {
+                        return Ok(read_u32(data, rec + 8)?.saturating_add(c - start));
+                    }
+                }
+                return Ok(0);
+            }
+
+            // Format 4: segments of 16-bit character codes
+            if c > 0xFFFF {
+                return Ok(0);
+            }
+            let seg_x2 = read_u16(data, sub + 6)? as usize;
+            let ends = sub + 14;
+            let starts = ends + seg_x2 + 2;
+            let deltas = starts + seg_x2;
+            let ranges = deltas + seg_x2;
+            for i in (0..seg_x2).step_by(2) {
+                if c > u32::from(read_u16(data, ends + i)?) {
+                    continue;
+                }
+                let start = u32::from(read_u16(data, starts + i)?);
+                if c < start {
+                    return Ok(0);
+                }
+                let delta = u32::from(read_u16(data, deltas + i)?);
+                let range_offset = read_u16(data, ranges + i)? as usize;
+                if range_offset == 0 {
+                    return Ok((c + delta) & 0xFFFF);
+                }
+                let glyph = u32::from(read_u16(data, ranges + i + range_offset + 2 * (c - start) as usize)?);
+                return Ok(if glyph == 0 { 0 } else { (glyph + delta) & 0xFFFF });
+            }
+            Ok(0)
+        };
+        match lookup() {
+            Ok(g) if g < u32::from(self.glyph_count) => g,
+            _ => 0,
+        }
+    }
+
+    /// Returns the horizontal advance of a glyph in font units
+    pub fn advance_width(&self, glyph: u32) -> u16 {
+        let idx = (glyph as usize).min(usize::from(self.h_metric_count.max(1)) - 1);
+        read_u16(&self.data, self.hmtx + idx * 4).unwrap_or(0)
+    }
+}
+
+impl TrueTypeFont {
+
+    /// Chooses the best supported Unicode subtable of a `cmap` table
+    fn find_cmap(data: &[u8], cmap: usize) -> Result<Option<(usize, u16)>, FontError> {
+        let mut best: Option<(usize, u16)> = None;
+        for i in 0..read_u16(data, cmap + 2)? as usize {
+            let rec = cmap + 4 + i * 8;
+            let platform = read_u16(data, rec)?;
+            let encoding = read_u16(data, rec + 2)?;
+            let sub = cmap + read_u32(data, rec + 4)? as usize;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            let format = read_u16(data, sub)?;
+            if !unicode || (format != 4 && format != 12) {
+                continue;
+            }
+
+            // Prefer format 12 as it covers characters outside the Basic Multilingual Plane
+            if best.map(|b| b.1 != 12).unwrap_or(true) {
+                best = Some((sub, format));
+            }
+        }
+        Ok(best)
+    }
+
+    /// Returns the byte range of a glyph within the `glyf` table, or `None` for an empty glyph
+    fn glyph_range(&self, glyph: u32) -> Result<Option<(usize, usize)>, FontError> {
+        if glyph >= u32::from(self.glyph_count) {
+            return Err(FontError::new(&format!("glyph {} out of range", glyph)));
+        }
+        let g = glyph as usize;
+        let (start, end) = if self.long_loca {
+            (read_u32(&self.data, self.loca + g * 4)? as usize, read_u32(&self.data, self.loca + g * 4 + 4)? as usize)
+        } else {
+            (
+                read_u16(&self.data, self.loca + g * 2)? as usize * 2,
+                read_u16(&self.data, self.loca + g * 2 + 2)? as usize * 2,
+            )
+        };
+        Ok(if end > start { Some((self.glyf + start, self.glyf + end)) } else { None })
+    }
+
+    /// Reads the contours of a glyph in font units, resolving composite glyphs
+    fn glyph_contours(&self, glyph: u32, depth: usize) -> Result<Vec<Vec<Point>>, FontError> {
+        let (g, _) = match self.glyph_range(glyph)? {
+            Some(r) => r,
+            None => return Ok(vec![]),
+        };
+        let data = &self.data;
+        let contour_count = read_i16(data, g)?;
+        if contour_count < 0 {
+            return self.composite_contours(g + 10, depth);
+        }
+
+        let contour_count = contour_count as usize;
+        let mut end_points = vec![];
+        for i in 0..contour_count {
+            end_points.push(read_u16(data, g + 10 + i * 2)? as usize);
+        }
+        let point_count = end_points.last().map(|x| x + 1).unwrap_or(0);
+        let instructions_len = read_u16(data, g + 10 + contour_count * 2)? as usize;
+        let mut pos = g + 12 + contour_count * 2 + instructions_len;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = read_u8(data, pos)?;
+            pos += 1;
+            flags.push(flag);
+            if flag & REPEAT != 0 {
+                let count = read_u8(data, pos)?;
+                pos += 1;
+                for _ in 0..count {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(point_count);
+
+        // Co-ordinates are stored as deltas, first all X values then all Y values
+        let mut read_coords = |short: u8, same_or_positive: u8| -> Result<Vec<f64>, FontError> {
+            let mut value = 0i32;
+            let mut coords = Vec::with_capacity(point_count);
+            for flag in &flags {
+                if flag & short != 0 {
+                    let d = i32::from(read_u8(data, pos)?);
+                    pos += 1;
+                    value += if flag & same_or_positive != 0 { d } else { -d };
+                } else if flag & same_or_positive == 0 {
+                    value += i32::from(read_i16(data, pos)?);
+                    pos += 2;
+                }
+                coords.push(f64::from(value));
+            }
+            Ok(coords)
+        };
+        let xs = read_coords(X_SHORT, X_SAME_OR_POSITIVE)?;
+        let ys = read_coords(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+        let mut contours = vec![];
+        let mut start = 0;
+        for end in end_points {
+            if end < start || end >= point_count {
+                return Err(FontError::new(&format!("invalid contour in glyph {}", glyph)));
+            }
+            contours.push(
+                (start..=end)
+                    .map(|i| Point { x: xs[i], y: ys[i], on_curve: flags[i] & ON_CURVE != 0 })
+                    .collect(),
+            );
+            start = end + 1;
+        }
+        Ok(contours)
+    }
+
+    /// Reads the components of a composite glyph starting at `pos`
+    fn composite_contours(&self, mut pos: usize, depth: usize) -> Result<Vec<Vec<Point>>, FontError> {
+        if depth >= MAX_COMPOSITE_DEPTH {
+            return Err(FontError::new("composite glyphs are nested too deeply"));
+        }
+        let data = &self.data;
+        let mut contours = vec![];
+        loop {
+            let flags = read_u16(data, pos)?;
+            let component = u32::from(read_u16(data, pos + 2)?);
+            pos += 4;
+            let (arg1, arg2) = if flags & ARGS_ARE_WORDS != 0 {
+                pos += 4;
+                (f64::from(read_i16(data, pos - 4)?), f64::from(read_i16(data, pos - 2)?))
+            } else {
+                pos += 2;
+                (f64::from(read_u8(data, pos - 2)? as i8), f64::from(read_u8(data, pos - 1)? as i8))
+            };
+
+            // Components positioned by matching points are placed without an offset
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 { (arg1, arg2) } else { (0.0, 0.0) };
+            let (mut a, mut b, mut c, mut d) = (1.0, 0.0, 0.0, 1.0);
+            if flags & HAVE_SCALE != 0 {
+                a = read_f2dot14(data, pos)?;
+                d = a;
+                pos += 2;
+            } else if flags & HAVE_X_AND_Y_SCALE != 0 {
+                a = read_f2dot14(data, pos)?;
+                d = read_f2dot14(data, pos + 2)?;
+                pos += 4;
+            } else if flags & HAVE_TWO_BY_TWO != 0 {
+                a = read_f2dot14(data, pos)?;
+                b = read_f2dot14(data, pos + 2)?;
+                c = read_f2dot14(data, pos + 4)?;
+                d = read_f2dot14(data, pos + 6)?;
+                pos += 8;
+            }
+
+            for contour in self.glyph_contours(component, depth + 1)? {
+                contours.push(
+                    contour
+                        .iter()
+                        .map(|p| Point {
+                            x: a * p.x + c * p.y + dx,
+                            y: b * p.x + d * p.y + dy,
+                            on_curve: p.on_curve,
+                        })
+                        .collect(),
+                );
+            }
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(contours);
+            }
+        }
+    }
+
+    /// Returns the outline of a glyph as a Path in pixel co-ordinates.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `glyph`: glyph index
+    ///   - `scale`: pixels per font unit
+    ///   - `x`: X co-ordinate of the pen position
+    ///   - `y`: Y co-ordinate of the baseline
+    pub fn glyph_path(&self, glyph: u32, scale: f64, x: f64, y: f64) -> Result<Path, FontError> {
+        let mut path = Path::new();
+        let to_px = |p: &Point| (x + p.x * scale, y - p.y * scale);
+        let mid = |a: (f64, f64), b: (f64, f64)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        for contour in self.glyph_contours(glyph, 0)? {
+            if contour.is_empty() {
+                continue;
+            }
+
+            // Start at an on-curve point, or between two off-curve points if there are none
+            let n = contour.len();
+            let first_on = contour.iter().position(|p| p.on_curve);
+            let (start, points): (_, Vec<&Point>) = match first_on {
+                Some(s) => (to_px(&contour[s]), (1..n).map(|i| &contour[(s + i) % n]).collect()),
+                None => (mid(to_px(&contour[n - 1]), to_px(&contour[0])), contour.iter().collect()),
+            };
+            path.move_to(start.0, start.1);
+
+            // Consecutive off-curve points have an implied on-curve point between them
+            let mut control: Option<(f64, f64)> = None;
+            for p in points {
+                let pt = to_px(p);
+                match (p.on_curve, control) {
+                    (true, Some(c)) => {
+                        path.quad_to(c.0, c.1, pt.0, pt.1);
+                        control = None;
+                    },
+                    (true, None) => path.line_to(pt.0, pt.1),
+                    (false, Some(c)) => {
+                        let m = mid(c, pt);
+                        path.quad_to(c.0, c.1, m.0, m.1);
+                        control = Some(pt);
+                    },
+                    (false, None) => control = Some(pt),
+                }
+            }
+            match control {
+                Some(c) => path.quad_to(c.0, c.1, start.0, start.1),
+                None => path.line_to(start.0, start.1),
+            }
+            path.close();
+        }
+        Ok(path)
+    }
+
+    /// Returns the font data
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+}
+
+#[wasm_bindgen]
+/// Glyphs of a [`TrueTypeFont`] rasterised at a fixed pixel size into a [`BitmapFont`] atlas
+/// as they are first drawn or measured
+///
+/// [`BitmapFont`]: ../struct.BitmapFont.html
+/// [`TrueTypeFont`]: ./struct.TrueTypeFont.html
+pub struct GlyphCache {
+    ttf: TrueTypeFont,
+    pixel_size: f64,
+    font: BitmapFont,
+
+    /// Position and height of the current shelf of glyphs in the atlas
+    shelf_x: usize,
+    shelf_y: usize,
+    shelf_height: usize,
+}
+
+#[wasm_bindgen]
+impl GlyphCache {
+
+    /// Creates a new empty GlyphCache
+    ///
+    /// # Arguments:
+    ///
+    ///   - `ttf`: font to rasterise glyphs from
+    ///   - `pixel_size`: size of the font's em square in pixels
+    pub fn new(ttf: TrueTypeFont, pixel_size: f64) -> Self {
+        let scale = pixel_size / f64::from(ttf.units_per_em);
+        let px = |units: f64| (units * scale + 0.5).floor().max(0.0) as usize;
+        let line_height = px(f64::from(ttf.ascender) - f64::from(ttf.descender) + f64::from(ttf.line_gap));
+        let base = px(f64::from(ttf.ascender));
+        let atlas_width = MIN_ATLAS_WIDTH.max((pixel_size * 4.0).ceil() as usize);
+        Self {
+            ttf,
+            pixel_size,
+            font: BitmapFont::new(Canvas::new(atlas_width, 0), line_height, base),
+            shelf_x: 0,
+            shelf_y: 0,
+            shelf_height: 0,
+        }
+    }
+
+    /// Returns the size of the font's em square in pixels
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    /// Rasterises any glyphs needed to draw `text` which are not already in the atlas
+    pub fn prepare(&mut self, text: &str) {
+        for ch in text.chars() {
+            if ch != '\n' && self.font.glyph(ch).is_none() {
+                self.add_glyph(ch);
+            }
+        }
+    }
+
+    /// Draws text, rasterising any glyphs which are not already in the atlas.  See
+    /// [`Canvas::draw_text()`] for details of the arguments.
+    ///
+    /// [`Canvas::draw_text()`]: ../../canvas/struct.Canvas.html#method.draw_text
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::font::ttf::{GlyphCache, TrueTypeFont};
+    /// use rust_wasm_graphics_lib::types::{ARGBColour, TextAlign};
+    ///
+    /// let ttf = TrueTypeFont::from_bytes(&std::fs::read("DejaVuSans.ttf").unwrap()).unwrap();
+    /// let mut cache = GlyphCache::new(ttf, 24.0);
+    ///
+    /// let mut dst = Canvas::new(320, 240);
+    /// cache.draw_text(&mut dst, "Hello, world!", 160, 100, &ARGBColour::new(255, 255, 255, 255), TextAlign::Center);
+    /// ```
+    pub fn draw_text(&mut self, dst: &mut Canvas, text: &str, x: isize, y: isize, tint: &ARGBColour, align: TextAlign) {
+        self.prepare(text);
+        dst.draw_text(&self.font, text, x, y, tint, align);
+    }
+
+    /// Measures text, rasterising any glyphs which are not already in the atlas.  See
+    /// [`BitmapFont::measure_text()`].
+    ///
+    /// [`BitmapFont::measure_text()`]: ../struct.BitmapFont.html#method.measure_text
+    pub fn measure_text(&mut self, text: &str) -> TextMetrics {
+        self.prepare(text);
+        self.font.measure_text(text)
+    }
+
+    /// Wraps and aligns text within a box, rasterising any glyphs which are not already in the
+    /// atlas.  See [`BitmapFont::layout_text()`].
+    ///
+    /// [`BitmapFont::layout_text()`]: ../struct.BitmapFont.html#method.layout_text
+    pub fn layout_text(&mut self, text: &str, width: usize, height: usize, align: TextAlign, ellipsis: bool) -> TextLayout {
+        self.prepare(text);
+        if self.ttf.glyph_index('\u{2026}') != 0 {
+            self.prepare("\u{2026}");
+        } else {
+            self.prepare(".");
+        }
+        self.font.layout_text(text, width, height, align, ellipsis)
+    }
+
+    /// Draws a TextLayout created by [`layout_text()`] with the top-left corner of its box at
+    /// (x,y)
+    ///
+    /// [`layout_text()`]: #method.layout_text
+    pub fn draw_text_layout(&self, dst: &mut Canvas, layout: &TextLayout, x: isize, y: isize, tint: &ARGBColour) {
+        dst.draw_text_layout(&self.font, layout, x, y, tint);
+    }
+}
+
+impl GlyphCache {
+
+    /// Returns the BitmapFont containing all glyphs rasterised so far
+    pub fn font(&self) -> &BitmapFont {
+        &self.font
+    }
+
+    /// Returns the TrueTypeFont glyphs are rasterised from
+    pub fn ttf(&self) -> &TrueTypeFont {
+        &self.ttf
+    }
+
+    /// Rasterises the glyph for a character and adds it to the atlas
+    fn add_glyph(&mut self, ch: char) {
+        let scale = self.pixel_size / f64::from(self.ttf.units_per_em);
+        let glyph = self.ttf.glyph_index(ch);
+        let advance = (f64::from(self.ttf.advance_width(glyph)) * scale + 0.5).floor() as isize;
+        let base = self.font.base() as f64;
+
+        // Malformed outlines are treated as empty so the glyph still advances the pen
+        let contours = self
+            .ttf
+            .glyph_path(glyph, scale, 0.0, base)
+            .map(|p| (p.flatten(), p))
+            .unwrap_or_default();
+        let (points, path) = contours;
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in points.iter().flatten() {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        let atlas_width = self.font.atlas().width();
+        if !(min.0 < max.0 && min.1 < max.1) || (max.0.ceil() - min.0.floor()) as usize > atlas_width {
+            self.font.add_glyph(ch, 0, 0, 0, 0, 0, 0, advance);
+            return;
+        }
+        let (x0, y0) = (min.0.floor(), min.1.floor());
+        let width = (max.0.ceil() - x0) as usize;
+        let height = (max.1.ceil() - y0) as usize;
+
+        let mut glyph_canv = Canvas::new(width, height);
+        let white = ARGBColour::new(255, 255, 255, 255);
+        fill_path(&mut glyph_canv, &white, &path.transformed(&Transform2D::translation(-x0, -y0)));
+
+        // Pack glyphs into shelves, leaving a pixel of padding between them
+        if self.shelf_x + width > atlas_width {
+            self.shelf_x = 0;
+            self.shelf_y += self.shelf_height + 1;
+            self.shelf_height = 0;
+        }
+        let atlas_height = self.font.atlas().height();
+        if self.shelf_y + height > atlas_height {
+            let new_height = (atlas_height * 2).max(self.shelf_y + height);
+            let old = std::mem::replace(self.font.atlas_mut(), Canvas::new(atlas_width, new_height));
+            self.font.atlas_mut().draw_canvas(&old, 0, 0);
+        }
+        let (sx, sy) = (self.shelf_x, self.shelf_y);
+        self.font.atlas_mut().draw_canvas(&glyph_canv, sx as isize, sy as isize);
+        self.shelf_x += width + 1;
+        self.shelf_height = self.shelf_height.max(height);
+
+        self.font.add_glyph(ch, sx, sy, width, height, x0 as isize, y0 as isize, advance);
+    }
+}
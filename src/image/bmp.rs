@@ -0,0 +1,337 @@
+//! Windows bitmap (BMP) encoding and decoding
+//!
+//! Uncompressed 1, 4, 8, 16, 24 and 32-bit images can be decoded, including 16 and 32-bit images
+//! with `BI_BITFIELDS` colour masks, as can 4 and 8-bit `BI_RLE4`/`BI_RLE8` compressed images.
+//! Both bottom-up (positive height) and top-down (negative height) row orders are supported.
+//! Pixels skipped by RLE deltas or early end-of-line codes are left transparent.
+//!
+//! Canvases are encoded without compression, either as 24-bit images or as 32-bit images with a
+//! `BITMAPV4HEADER` describing an alpha channel.
+
+use wasm_bindgen::prelude::*;
+
+use super::ImageError;
+use crate::canvas::Canvas;
+
+/// Size of the BITMAPFILEHEADER
+const FILE_HEADER_SIZE: usize = 14;
+
+/// Size of the BITMAPV4HEADER written for 32-bit images
+const V4_HEADER_SIZE: usize = 108;
+
+/// Compression methods
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+fn read_u16(src: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([src[pos], src[pos + 1]])
+}
+
+fn read_u32(src: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([src[pos], src[pos + 1], src[pos + 2], src[pos + 3]])
+}
+
+/// Returns the opaque ARGB colour of a pixel or palette entry stored in blue, green, red order
+fn bgr(src: &[u8]) -> u32 {
+    0xFF00_0000 | (u32::from(src[2]) << 16) | (u32::from(src[1]) << 8) | u32::from(src[0])
+}
+
+/// A colour channel described by a bit mask within a pixel
+#[derive(Clone, Copy)]
+struct Channel {
+    mask: u32,
+    shift: u32,
+    max: u32,
+}
+
+impl Channel {
+    fn new(mask: u32) -> Self {
+        let shift = if mask == 0 { 0 } else { mask.trailing_zeros() };
+        Self { mask, shift, max: mask >> shift }
+    }
+
+    /// Extracts the channel from a pixel and scales it to the range 0-255
+    fn extract(&self, px: u32, default: u32) -> u32 {
+        (u64::from((px & self.mask) >> self.shift) * 255)
+            .checked_div(u64::from(self.max))
+            .map_or(default, |c| c as u32)
+    }
+}
+
+/// Decodes RLE8 or RLE4 compressed pixel data into palette indices, one per pixel, with `None`
+/// for skipped pixels.  Rows are returned in file order.
+fn decode_rle(data: &[u8], width: usize, height: usize, four_bit: bool) -> Result<Vec<Option<u8>>, ImageError> {
+    let truncated = || ImageError::new("truncated BMP RLE data");
+    let mut out = vec![None; width * height];
+    let (mut x, mut y, mut pos) = (0, 0, 0);
+    let mut put = |x: &mut usize, y: usize, value: u8| {
+        if *x < width && y < height {
+            out[y * width + *x] = Some(value);
+        }
+        *x += 1;
+    };
+    while y < height {
+        let count = *data.get(pos).ok_or_else(truncated)?;
+        let value = *data.get(pos + 1).ok_or_else(truncated)?;
+        pos += 2;
+        if count > 0 {
+            for i in 0..usize::from(count) {
+                let idx = if !four_bit { value } else if i % 2 == 0 { value >> 4 } else { value & 0x0F };
+                put(&mut x, y, idx);
+            }
+            continue;
+        }
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = data.get(pos..pos + 2).ok_or_else(truncated)?;
+                x += usize::from(delta[0]);
+                y += usize::from(delta[1]);
+                pos += 2;
+            }
+            n => {
+                // Absolute mode: literal indices, padded to a 16-bit boundary
+                let n = usize::from(n);
+                let len = if four_bit { n.div_ceil(2) } else { n };
+                let literal = data.get(pos..pos + len).ok_or_else(truncated)?;
+                for i in 0..n {
+                    let idx = if !four_bit {
+                        literal[i]
+                    } else if i % 2 == 0 {
+                        literal[i / 2] >> 4
+                    } else {
+                        literal[i / 2] & 0x0F
+                    };
+                    put(&mut x, y, idx);
+                }
+                pos += len + len % 2;
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Decodes a BMP file into a new Canvas
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: contents of the BMP file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(3, 2);
+    /// canvas.buffer_mut()[4] = 0xFF102030;
+    ///
+    /// let decoded = Canvas::from_bmp(&canvas.to_bmp(true)).unwrap();
+    /// assert_eq!(decoded.buffer(), canvas.buffer());
+    /// ```
+    pub fn from_bmp(src: &[u8]) -> Result<Canvas, ImageError> {
+        if src.len() < FILE_HEADER_SIZE + 12 || &src[0..2] != b"BM" {
+            return Err(ImageError::new("not a BMP file"));
+        }
+        let data_offset = read_u32(src, 10) as usize;
+        let header_size = read_u32(src, FILE_HEADER_SIZE) as usize;
+        if src.len() < FILE_HEADER_SIZE + header_size {
+            return Err(ImageError::new("truncated BMP header"));
+        }
+
+        // OS/2 BITMAPCOREHEADER has 16-bit dimensions and no compression
+        let h = FILE_HEADER_SIZE;
+        let (width, height, bpp, compression) = if header_size == 12 {
+            (i32::from(read_u16(src, h + 4)), i32::from(read_u16(src, h + 6)), read_u16(src, h + 10), BI_RGB)
+        } else if header_size >= 40 {
+            let (width, height) = (read_u32(src, h + 4) as i32, read_u32(src, h + 8) as i32);
+            (width, height, read_u16(src, h + 14), read_u32(src, h + 16))
+        } else {
+            return Err(ImageError::new("unsupported BMP header size"));
+        };
+        if width <= 0 || height == 0 || height == i32::MIN {
+            return Err(ImageError::new("BMP image has invalid dimensions"));
+        }
+        let top_down = height < 0;
+        let (width, height) = (width as usize, height.unsigned_abs() as usize);
+        if width.checked_mul(height).and_then(|n| n.checked_mul(4)).is_none() {
+            return Err(ImageError::new("BMP image is too large"));
+        }
+
+        // Colour masks either follow a BITMAPINFOHEADER or are part of a later header version
+        let masks = match compression {
+            BI_BITFIELDS | BI_ALPHABITFIELDS => {
+                let pos = h + 40;
+                let count = if compression == BI_ALPHABITFIELDS || header_size >= 56 { 4 } else { 3 };
+                if src.len() < pos + count * 4 {
+                    return Err(ImageError::new("truncated BMP colour masks"));
+                }
+                let mut masks = [0; 4];
+                for (i, mask) in masks.iter_mut().take(count).enumerate() {
+                    *mask = read_u32(src, pos + i * 4);
+                }
+                masks
+            }
+            BI_RGB if bpp == 16 => [0x7C00, 0x03E0, 0x001F, 0],
+            BI_RGB => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0],
+            BI_RLE8 | BI_RLE4 => [0; 4],
+            _ => return Err(ImageError::new("unsupported BMP compression method")),
+        };
+        let valid = match compression {
+            BI_RGB => [1, 4, 8, 16, 24, 32].contains(&bpp),
+            BI_RLE8 => bpp == 8,
+            BI_RLE4 => bpp == 4,
+            _ => bpp == 16 || bpp == 32,
+        };
+        if !valid {
+            return Err(ImageError::new("unsupported BMP bit depth for its compression method"));
+        }
+        let channels = masks.map(Channel::new);
+
+        // Palette of BGR(A) entries follows the header and any masks
+        let mut palette = vec![];
+        if bpp <= 8 {
+            let entry_size = if header_size == 12 { 3 } else { 4 };
+            let used = if header_size >= 40 { read_u32(src, h + 32) as usize } else { 0 };
+            let count = if used == 0 || used > 1 << bpp { 1 << bpp } else { used };
+            let pos = h + header_size;
+            for i in 0..count {
+                match src.get(pos + i * entry_size..pos + i * entry_size + 3) {
+                    Some(entry) => palette.push(bgr(entry)),
+                    None => break,
+                }
+            }
+            if palette.is_empty() {
+                return Err(ImageError::new("BMP palette is missing"));
+            }
+        }
+        let lookup = |idx: u8| {
+            palette.get(usize::from(idx)).copied().ok_or_else(|| ImageError::new("BMP palette index out of range"))
+        };
+
+        let data = src.get(data_offset..).ok_or_else(|| ImageError::new("BMP pixel data offset is out of range"))?;
+        let row_to_y = |row: usize| if top_down { row } else { height - 1 - row };
+
+        if compression == BI_RLE8 || compression == BI_RLE4 {
+            // A 4-byte delta skips at most 255 rows and columns, so reject dimensions which the data
+            // could not possibly cover rather than allocating a huge Canvas for a corrupt header
+            if width * height / (255 * 255) > data.len() {
+                return Err(ImageError::new("BMP RLE data is too short for the image dimensions"));
+            }
+            let mut canvas = Canvas::new(width, height);
+            let indices = decode_rle(data, width, height, compression == BI_RLE4)?;
+            for (i, idx) in indices.into_iter().enumerate() {
+                if let Some(idx) = idx {
+                    let dst = canvas.buffer_index(i % width, row_to_y(i / width));
+                    canvas.buffer_mut()[dst] = lookup(idx)?;
+                }
+            }
+            return Ok(canvas);
+        }
+
+        let bpp = usize::from(bpp);
+        let stride = (width * bpp).div_ceil(32) * 4;
+        if data.len() < stride * height {
+            return Err(ImageError::new("BMP pixel data is truncated"));
+        }
+        let mut canvas = Canvas::new(width, height);
+        for (row_idx, row) in data.chunks(stride).take(height).enumerate() {
+            let y = row_to_y(row_idx);
+            for x in 0..width {
+                let px = match bpp {
+                    1 | 4 | 8 => {
+                        let bit = x * bpp;
+                        let idx = (row[bit / 8] >> (8 - bpp - bit % 8)) & ((1 << bpp) - 1) as u8;
+                        lookup(idx)?
+                    }
+                    24 => bgr(&row[x * 3..]),
+                    _ => {
+                        let raw = if bpp == 16 { u32::from(read_u16(row, x * 2)) } else { read_u32(row, x * 4) };
+                        let [r, g, b, a] = [
+                            channels[0].extract(raw, 0),
+                            channels[1].extract(raw, 0),
+                            channels[2].extract(raw, 0),
+                            channels[3].extract(raw, 255),
+                        ];
+                        (a << 24) | (r << 16) | (g << 8) | b
+                    }
+                };
+                let dst = canvas.buffer_index(x, y);
+                canvas.buffer_mut()[dst] = px;
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Encodes the Canvas as an uncompressed bottom-up BMP file
+    ///
+    /// # Arguments:
+    ///
+    ///   - `alpha`: if `true` a 32-bit image with an alpha channel is written, otherwise a 24-bit
+    ///     image is written and alpha is discarded
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let bmp = Canvas::new(16, 16).to_bmp(false);
+    /// assert_eq!(&bmp[0..2], b"BM");
+    /// ```
+    pub fn to_bmp(&self, alpha: bool) -> Vec<u8> {
+        let (header_size, bpp) = if alpha { (V4_HEADER_SIZE, 32) } else { (40, 24) };
+        let stride = (self.width() * bpp).div_ceil(32) * 4;
+        let data_offset = FILE_HEADER_SIZE + header_size;
+        let file_size = data_offset + stride * self.height();
+
+        let mut out = Vec::with_capacity(file_size);
+        out.extend(b"BM");
+        out.extend(&(file_size as u32).to_le_bytes());
+        out.extend(&[0; 4]);
+        out.extend(&(data_offset as u32).to_le_bytes());
+
+        out.extend(&(header_size as u32).to_le_bytes());
+        out.extend(&(self.width() as i32).to_le_bytes());
+        out.extend(&(self.height() as i32).to_le_bytes());
+        out.extend(&1u16.to_le_bytes());
+        out.extend(&(bpp as u16).to_le_bytes());
+        out.extend(&(if alpha { BI_BITFIELDS } else { BI_RGB }).to_le_bytes());
+        out.extend(&((stride * self.height()) as u32).to_le_bytes());
+
+        // 72 DPI resolution, then no palette
+        out.extend(&2835u32.to_le_bytes());
+        out.extend(&2835u32.to_le_bytes());
+        out.extend(&[0; 8]);
+        if alpha {
+            for mask in &[0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+                out.extend(&mask.to_le_bytes());
+            }
+
+            // sRGB colour space, with unused endpoints and gamma
+            out.extend(b"BGRs");
+            out.extend(&[0; 48]);
+        }
+
+        for y in (0..self.height()).rev() {
+            let start = out.len();
+            for x in 0..self.width() {
+                let [a, r, g, b] = self.buffer()[self.buffer_index(x, y)].to_be_bytes();
+                out.extend(&[b, g, r]);
+                if alpha {
+                    out.push(a);
+                }
+            }
+            out.resize(start + stride, 0);
+        }
+        out
+    }
+}
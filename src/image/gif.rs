@@ -0,0 +1,321 @@
+//! Animated GIF encoding
+//!
+//! Each frame is written with its own colour table.  Frames with 256 or fewer distinct colours
+//! keep their exact colours, otherwise a palette is generated by median cut and each pixel is
+//! mapped to its nearest palette colour.  Pixels with less than 50% alpha become transparent, and
+//! every frame is disposed to the background before the next is drawn so that transparent areas
+//! never show earlier frames.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::ImageError;
+use crate::canvas::Canvas;
+
+/// Largest LZW code size, and so the largest number of codes in the LZW dictionary
+const MAX_CODE_BITS: u32 = 12;
+const MAX_CODES: u16 = 1 << MAX_CODE_BITS;
+
+/// Largest number of bytes in a data sub-block
+const MAX_SUB_BLOCK: usize = 255;
+
+/// Frames are disposed by restoring the background (i.e. transparency)
+const DISPOSE_TO_BACKGROUND: u8 = 2;
+
+/// A box of colours in RGB space, split repeatedly during median cut palette generation
+struct ColourBox {
+    colours: Vec<(u32, u32)>,
+}
+
+impl ColourBox {
+
+    /// Returns the channel shift (16 for red, 8 for green, 0 for blue) with the widest range,
+    /// and that range
+    fn widest_channel(&self) -> (u32, u32) {
+        [16, 8, 0]
+            .iter()
+            .map(|shift| {
+                let values = self.colours.iter().map(|(c, _)| (c >> shift) & 0xFF);
+                let (min, max) = values.fold((255, 0), |(min, max), v| (v.min(min), v.max(max)));
+                (*shift, max.saturating_sub(min))
+            })
+            .max_by_key(|(_, range)| *range)
+            .unwrap_or((0, 0))
+    }
+
+    /// Returns the average colour, weighted by the number of pixels of each colour
+    fn average(&self) -> u32 {
+        let total = self.colours.iter().map(|(_, n)| u64::from(*n)).sum::<u64>().max(1);
+        [16, 8, 0].iter().fold(0, |avg, shift| {
+            let sum = self.colours.iter().map(|(c, n)| u64::from((c >> shift) & 0xFF) * u64::from(*n)).sum::<u64>();
+            avg | (((sum + total / 2) / total) as u32) << shift
+        })
+    }
+}
+
+/// Reduces a set of colours and their pixel counts to at most `max_colours` colours
+fn median_cut(colours: Vec<(u32, u32)>, max_colours: usize) -> Vec<u32> {
+    let mut boxes = vec![ColourBox { colours }];
+    while boxes.len() < max_colours {
+        // Split the box with the widest channel range at the median pixel along that channel
+        let (idx, (shift, range)) = match boxes
+            .iter()
+            .map(|b| b.widest_channel())
+            .enumerate()
+            .filter(|(i, _)| boxes[*i].colours.len() > 1)
+            .max_by_key(|(_, (_, range))| *range)
+        {
+            Some(widest) => widest,
+            None => break,
+        };
+        if range == 0 {
+            break;
+        }
+        let mut colours = std::mem::take(&mut boxes[idx].colours);
+        colours.sort_by_key(|(c, _)| (c >> shift) & 0xFF);
+        let total = colours.iter().map(|(_, n)| u64::from(*n)).sum::<u64>();
+        let mut count = 0;
+        let split = colours
+            .iter()
+            .position(|(_, n)| {
+                count += u64::from(*n);
+                count * 2 >= total
+            })
+            .unwrap_or(0)
+            .clamp(0, colours.len() - 2);
+        let upper = colours.split_off(split + 1);
+        boxes[idx].colours = colours;
+        boxes.push(ColourBox { colours: upper });
+    }
+    boxes.iter().map(|b| b.average()).collect()
+}
+
+/// Returns the index of the palette colour closest to an RGB colour
+fn nearest(palette: &[u32], colour: u32) -> u8 {
+    let dist = |p: u32| {
+        [16, 8, 0]
+            .iter()
+            .map(|shift| {
+                let d = ((p >> shift) & 0xFF) as i32 - ((colour >> shift) & 0xFF) as i32;
+                d * d
+            })
+            .sum::<i32>()
+    };
+    (0..palette.len()).min_by_key(|i| dist(palette[*i])).unwrap_or(0) as u8
+}
+
+/// Writes LZW codes of increasing width, least significant bit first, split into sub-blocks
+struct CodeWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.bits |= u32::from(code) << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Compresses palette indices with GIF's variant of LZW
+fn lzw_compress(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut w = CodeWriter { out: vec![], bits: 0, count: 0 };
+    let mut dict: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size + 1;
+    w.write(clear, width);
+
+    let mut prefix = match indices.first() {
+        Some(idx) => u16::from(*idx),
+        None => {
+            w.write(end, width);
+            return w.finish();
+        }
+    };
+    for idx in &indices[1..] {
+        if let Some(code) = dict.get(&(prefix, *idx)) {
+            prefix = *code;
+            continue;
+        }
+        w.write(prefix, width);
+
+        // Start a new dictionary once it is full
+        if next == MAX_CODES {
+            w.write(clear, width);
+            dict.clear();
+            next = end + 1;
+            width = min_code_size + 1;
+        } else {
+            dict.insert((prefix, *idx), next);
+            next += 1;
+            if u32::from(next) > 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+        }
+        prefix = u16::from(*idx);
+    }
+    w.write(prefix, width);
+    w.write(end, width);
+    w.finish()
+}
+
+#[wasm_bindgen]
+/// Builds an animated GIF from a sequence of [`Canvas`] frames of the same size
+///
+/// [`Canvas`]: ../../canvas/struct.Canvas.html
+pub struct GifEncoder {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl GifEncoder {
+
+    /// Creates a GifEncoder for frames of a given size.  GIF dimensions are 16-bit, so an error is
+    /// returned if either is larger than 65535.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `width`: width of each frame (at most 65535)
+    ///   - `height`: height of each frame (at most 65535)
+    ///   - `loop_count`: number of times the animation repeats after it is first played, or 0 to
+    ///     repeat forever
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::image::gif::GifEncoder;
+    /// use rust_wasm_graphics_lib::types::ARGBColour;
+    ///
+    /// let mut frame = Canvas::new(32, 32);
+    /// let mut gif = GifEncoder::new(32, 32, 0).unwrap();
+    /// for i in 0..4 {
+    ///     frame.clear(&ARGBColour::new(255, i * 60, 0, 0));
+    ///     gif.add_frame(&frame, 10).unwrap();
+    /// }
+    /// let data = gif.finish();
+    /// assert_eq!(&data[0..6], b"GIF89a");
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, loop_count: u16) -> Result<GifEncoder, ImageError> {
+        if width > usize::from(u16::MAX) || height > usize::from(u16::MAX) {
+            return Err(ImageError::new("GIF images must be at most 65535 pixels wide and high"));
+        }
+        let mut data = b"GIF89a".to_vec();
+
+        // Logical screen descriptor without a global colour table
+        data.extend(&(width as u16).to_le_bytes());
+        data.extend(&(height as u16).to_le_bytes());
+        data.extend(&[0, 0, 0]);
+
+        // Netscape application extension giving the loop count
+        data.extend(&[0x21, 0xFF, 11]);
+        data.extend(b"NETSCAPE2.0");
+        data.extend(&[3, 1]);
+        data.extend(&loop_count.to_le_bytes());
+        data.push(0);
+
+        Ok(GifEncoder { width, height, data })
+    }
+
+    /// Adds a frame to the animation
+    ///
+    /// # Arguments:
+    ///
+    ///   - `frame`: Canvas containing the frame, which must be the size given to
+    ///     [`GifEncoder::new()`]
+    ///   - `delay`: time to show the frame for, in hundredths of a second
+    ///
+    /// [`GifEncoder::new()`]: #method.new
+    pub fn add_frame(&mut self, frame: &Canvas, delay: u16) -> Result<(), ImageError> {
+        if frame.width() != self.width || frame.height() != self.height {
+            return Err(ImageError::new("GIF frame size does not match the animation size"));
+        }
+
+        // Count the opaque colours, noting whether any pixels are transparent
+        let mut counts: HashMap<u32, u32> = HashMap::new();
+        let mut transparent = false;
+        for px in frame.buffer() {
+            if px >> 24 < 128 {
+                transparent = true;
+            } else {
+                *counts.entry(px & 0x00FF_FFFF).or_insert(0) += 1;
+            }
+        }
+        let max_colours = if transparent { 255 } else { 256 };
+        let mut colours = counts.into_iter().collect::<Vec<(u32, u32)>>();
+        colours.sort_unstable();
+        let palette = if colours.len() <= max_colours {
+            colours.iter().map(|(c, _)| *c).collect()
+        } else {
+            median_cut(colours, max_colours)
+        };
+        let transparent_idx = palette.len() as u8;
+
+        let mut lookup: HashMap<u32, u8> = HashMap::new();
+        let indices = frame
+            .buffer()
+            .iter()
+            .map(|px| {
+                if px >> 24 < 128 {
+                    transparent_idx
+                } else {
+                    *lookup.entry(px & 0x00FF_FFFF).or_insert_with(|| nearest(&palette, px & 0x00FF_FFFF))
+                }
+            })
+            .collect::<Vec<u8>>();
+
+        // Colour tables hold a power of two entries, at least 2
+        let entries = palette.len() + usize::from(transparent);
+        let table_bits = (usize::BITS - entries.saturating_sub(1).leading_zeros()).max(1);
+
+        // Graphic control extension
+        self.data.extend(&[0x21, 0xF9, 4, DISPOSE_TO_BACKGROUND << 2 | u8::from(transparent)]);
+        self.data.extend(&delay.to_le_bytes());
+        self.data.extend(&[if transparent { transparent_idx } else { 0 }, 0]);
+
+        // Image descriptor covering the whole frame, with a local colour table
+        self.data.extend(&[0x2C, 0, 0, 0, 0]);
+        self.data.extend(&(self.width as u16).to_le_bytes());
+        self.data.extend(&(self.height as u16).to_le_bytes());
+        self.data.push(0x80 | (table_bits - 1) as u8);
+        for i in 0..1 << table_bits {
+            let [_, r, g, b] = palette.get(i).copied().unwrap_or(0).to_be_bytes();
+            self.data.extend(&[r, g, b]);
+        }
+
+        let min_code_size = table_bits.max(2);
+        self.data.push(min_code_size as u8);
+        for block in lzw_compress(&indices, min_code_size).chunks(MAX_SUB_BLOCK) {
+            self.data.push(block.len() as u8);
+            self.data.extend(block);
+        }
+        self.data.push(0);
+        Ok(())
+    }
+
+    /// Returns the complete GIF file containing all frames added so far
+    pub fn finish(&self) -> Vec<u8> {
+        let mut data = self.data.clone();
+        data.push(0x3B);
+        data
+    }
+}
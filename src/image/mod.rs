@@ -0,0 +1,49 @@
+//! Encoding and decoding of Canvas pixels to and from image and video file formats
+//!
+//! Encoders and decoders work entirely on byte buffers so that images can be saved or loaded
+//! without relying on browser APIs such as `<canvas>.toDataURL()`, e.g. in headless or worker
+//! contexts.
+
+pub mod bmp;
+pub mod gif;
+pub mod netpbm;
+pub mod png;
+pub mod qoi;
+pub mod tga;
+pub mod video;
+
+mod zlib;
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// An error encountered while decoding or encoding an image
+pub struct ImageError {
+    message: String,
+}
+
+#[wasm_bindgen]
+impl ImageError {
+
+    /// Returns a description of the error
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl ImageError {
+    fn new(message: &str) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ImageError {}
@@ -0,0 +1,208 @@
+//! Netpbm (PPM, PGM and PAM) encoding and decoding
+//!
+//! Plain (P2, P3) and binary (P5, P6) greymaps and pixmaps can be read and written, as can P7
+//! arbitrary maps with 1 to 4 channels (greyscale, greyscale with alpha, RGB and RGB with alpha).
+//! Samples with a maximum value other than 255, including 16-bit samples, are scaled to 8 bits.
+//! Greyscale output uses the Rec. 601 luma of each pixel.
+
+use wasm_bindgen::prelude::*;
+
+use super::ImageError;
+use crate::canvas::Canvas;
+use crate::types::NetpbmFormat;
+
+/// Reads whitespace separated tokens from a Netpbm header, skipping `#` comments
+struct Tokens<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next_token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.src.get(self.pos)? {
+                b'#' => {
+                    while self.src.get(self.pos).is_some_and(|c| *c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self.src.get(self.pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        Some(&self.src[start..self.pos])
+    }
+
+    fn next_number(&mut self, what: &str) -> Result<usize, ImageError> {
+        self.next_token()
+            .and_then(|t| std::str::from_utf8(t).ok())
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| ImageError::new(&format!("invalid or missing Netpbm {}", what)))
+    }
+}
+
+/// Returns the Rec. 601 luma of an ARGB colour
+fn luma(px: u32) -> u8 {
+    let [_, r, g, b] = px.to_be_bytes();
+    (0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b) + 0.5).floor() as u8
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Decodes a P2, P3, P5, P6 or P7 Netpbm file into a new Canvas
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: contents of the Netpbm file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let canvas = Canvas::from_netpbm(b"P3\n# red, green\n2 1 255\n255 0 0  0 255 0\n").unwrap();
+    /// assert_eq!(canvas.buffer(), &vec![0xFFFF0000, 0xFF00FF00]);
+    /// ```
+    pub fn from_netpbm(src: &[u8]) -> Result<Canvas, ImageError> {
+        let magic = src.get(0..2).ok_or_else(|| ImageError::new("not a Netpbm file"))?;
+        let (plain, channels) = match magic {
+            b"P2" => (true, 1),
+            b"P3" => (true, 3),
+            b"P5" => (false, 1),
+            b"P6" => (false, 3),
+            b"P7" => (false, 0),
+            _ => return Err(ImageError::new("not a supported Netpbm file")),
+        };
+
+        let mut tokens = Tokens { src, pos: 2 };
+        let (width, height, channels, maxval) = if channels > 0 {
+            let width = tokens.next_number("width")?;
+            let height = tokens.next_number("height")?;
+            (width, height, channels, tokens.next_number("maximum value")?)
+        } else {
+            // PAM headers are a list of keyword/value lines ending with ENDHDR
+            let (mut width, mut height, mut depth, mut maxval) = (None, None, None, None);
+            loop {
+                let keyword = tokens.next_token().ok_or_else(|| ImageError::new("PAM header has no ENDHDR"))?;
+                match keyword {
+                    b"WIDTH" => width = Some(tokens.next_number("width")?),
+                    b"HEIGHT" => height = Some(tokens.next_number("height")?),
+                    b"DEPTH" => depth = Some(tokens.next_number("depth")?),
+                    b"MAXVAL" => maxval = Some(tokens.next_number("maximum value")?),
+                    b"TUPLTYPE" => {
+                        tokens.next_token();
+                    }
+                    b"ENDHDR" => break,
+                    _ => return Err(ImageError::new("unknown PAM header keyword")),
+                }
+            }
+            let header = match (width, height, depth, maxval) {
+                (Some(w), Some(h), Some(d), Some(m)) => (w, h, d, m),
+                _ => return Err(ImageError::new("PAM header is missing WIDTH, HEIGHT, DEPTH or MAXVAL")),
+            };
+            if !(1..=4).contains(&header.2) {
+                return Err(ImageError::new("unsupported PAM depth"));
+            }
+            header
+        };
+        if maxval == 0 || maxval > 65535 {
+            return Err(ImageError::new("invalid or missing Netpbm maximum value"));
+        }
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels))
+            .ok_or_else(|| ImageError::new("Netpbm image is too large"))?;
+
+        // Binary samples start after the single whitespace character ending the header
+        let samples = if plain {
+            (0..count).map(|_| tokens.next_number("sample")).collect::<Result<Vec<usize>, _>>()?
+        } else {
+            let start = tokens.pos + 1;
+            let size = if maxval > 255 { 2 } else { 1 };
+            let data = count
+                .checked_mul(size)
+                .and_then(|len| src.get(start..start + len))
+                .ok_or_else(|| ImageError::new("Netpbm pixel data is truncated"))?;
+            data.chunks(size).map(|s| s.iter().fold(0, |v, b| (v << 8) | usize::from(*b))).collect()
+        };
+        if samples.iter().any(|s| *s > maxval) {
+            return Err(ImageError::new("Netpbm sample exceeds the maximum value"));
+        }
+
+        let scale = |s: usize| ((s * 255 + maxval / 2) / maxval) as u32;
+        let mut canvas = Canvas::new(width, height);
+        for (dst, px) in canvas.buffer_mut().iter_mut().zip(samples.chunks(channels)) {
+            let (r, g, b, a) = match channels {
+                1 => (px[0], px[0], px[0], maxval),
+                2 => (px[0], px[0], px[0], px[1]),
+                3 => (px[0], px[1], px[2], maxval),
+                _ => (px[0], px[1], px[2], px[3]),
+            };
+            *dst = (scale(a) << 24) | (scale(r) << 16) | (scale(g) << 8) | scale(b);
+        }
+        Ok(canvas)
+    }
+
+    /// Encodes the Canvas as a Netpbm file
+    ///
+    /// Plain formats are written with one row of pixels per line, which makes them easy to read
+    /// and diff.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `format`: NetpbmFormat to write
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::NetpbmFormat;
+    ///
+    /// let mut canvas = Canvas::new(2, 1);
+    /// canvas.buffer_mut()[1] = 0xFFFFFFFF;
+    /// assert_eq!(canvas.to_netpbm(NetpbmFormat::PlainPgm), b"P2\n2 1\n255\n0 255\n");
+    /// ```
+    pub fn to_netpbm(&self, format: NetpbmFormat) -> Vec<u8> {
+        let (width, height) = (self.width(), self.height());
+        let mut out = match format {
+            NetpbmFormat::PlainPpm => format!("P3\n{} {}\n255\n", width, height),
+            NetpbmFormat::Ppm => format!("P6\n{} {}\n255\n", width, height),
+            NetpbmFormat::PlainPgm => format!("P2\n{} {}\n255\n", width, height),
+            NetpbmFormat::Pgm => format!("P5\n{} {}\n255\n", width, height),
+            NetpbmFormat::Pam => format!(
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+                width,
+                height
+            ),
+        }
+        .into_bytes();
+
+        for row in self.buffer().chunks(width.max(1)).take(height) {
+            let samples = row
+                .iter()
+                .flat_map(|px| {
+                    let [a, r, g, b] = px.to_be_bytes();
+                    match format {
+                        NetpbmFormat::PlainPpm | NetpbmFormat::Ppm => vec![r, g, b],
+                        NetpbmFormat::PlainPgm | NetpbmFormat::Pgm => vec![luma(*px)],
+                        NetpbmFormat::Pam => vec![r, g, b, a],
+                    }
+                })
+                .collect::<Vec<u8>>();
+            match format {
+                NetpbmFormat::PlainPpm | NetpbmFormat::PlainPgm => {
+                    let line = samples.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(" ");
+                    out.extend(line.as_bytes());
+                    out.push(b'\n');
+                }
+                _ => out.extend(samples),
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,429 @@
+//! Portable Network Graphics (PNG) encoding and decoding
+//!
+//! Canvases are encoded as 8-bit RGBA images so that alpha is preserved.  Each scanline is
+//! filtered with whichever of the five PNG filters gives the smallest sum of absolute differences,
+//! and the filtered data is compressed with DEFLATE.
+//!
+//! All standard colour types and bit depths can be decoded, including palette images, images with
+//! `tRNS` transparency and Adam7 interlaced images.  16-bit samples are reduced to 8 bits and
+//! samples of less than 8 bits are scaled up to the full 8-bit range.
+
+use wasm_bindgen::prelude::*;
+
+use super::{zlib, ImageError};
+use crate::canvas::Canvas;
+
+/// Bytes which begin every PNG file
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Colour types of PNG images
+const COLOUR_TYPE_GREY: u8 = 0;
+const COLOUR_TYPE_RGB: u8 = 2;
+const COLOUR_TYPE_PALETTE: u8 = 3;
+const COLOUR_TYPE_GREY_ALPHA: u8 = 4;
+const COLOUR_TYPE_RGBA: u8 = 6;
+
+/// Starting column, starting row, column step and row step of each Adam7 interlace pass
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// CRC-32 of each possible byte value, used to update a CRC one byte at a time
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Builds the CRC-32 lookup table for the reversed polynomial 0xEDB88320
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// Returns the CRC-32 (as used by PNG chunks) of data
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFF_FFFF, |c, b| CRC_TABLE[((c ^ u32::from(*b)) & 0xFF) as usize] ^ (c >> 8))
+}
+
+/// Appends a chunk with its length and CRC to a PNG stream
+fn write_chunk(out: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    out.extend(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(chunk_type);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(&crc.to_be_bytes());
+}
+
+/// Predicts a byte from its left (a), above (b) and upper-left (c) neighbours
+pub fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = ((p - i16::from(a)).abs(), (p - i16::from(b)).abs(), (p - i16::from(c)).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Applies a PNG filter type to a scanline given the previous (unfiltered) scanline
+fn filter_row(filter: u8, row: &[u8], prior: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.push(filter);
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prior[i];
+        let c = if i >= bpp { prior[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+}
+
+/// Reverses a PNG filter on a scanline in place given the previous (unfiltered) scanline
+fn unfilter_row(filter: u8, row: &mut [u8], prior: &[u8], bpp: usize) -> Result<(), ImageError> {
+    if filter > 4 {
+        return Err(ImageError::new("invalid PNG scanline filter type"));
+    }
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prior[i];
+        let c = if i >= bpp { prior[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+/// Image properties read from the IHDR chunk
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    colour_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, ImageError> {
+        if data.len() != 13 {
+            return Err(ImageError::new("IHDR chunk has an invalid length"));
+        }
+        let header = Self {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize,
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize,
+            bit_depth: data[8],
+            colour_type: data[9],
+            interlaced: data[12] == 1,
+        };
+        if header.width == 0 || header.height == 0 {
+            return Err(ImageError::new("PNG image has zero width or height"));
+        }
+        if header.width.checked_mul(header.height).and_then(|n| n.checked_mul(64)).is_none() {
+            return Err(ImageError::new("PNG image is too large"));
+        }
+        let valid_depth = match header.colour_type {
+            COLOUR_TYPE_GREY => [1, 2, 4, 8, 16].contains(&header.bit_depth),
+            COLOUR_TYPE_PALETTE => [1, 2, 4, 8].contains(&header.bit_depth),
+            COLOUR_TYPE_RGB | COLOUR_TYPE_GREY_ALPHA | COLOUR_TYPE_RGBA => [8, 16].contains(&header.bit_depth),
+            _ => return Err(ImageError::new("invalid PNG colour type")),
+        };
+        if !valid_depth {
+            return Err(ImageError::new("invalid bit depth for PNG colour type"));
+        }
+        if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(ImageError::new("unsupported PNG compression, filter or interlace method"));
+        }
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.colour_type {
+            COLOUR_TYPE_RGB => 3,
+            COLOUR_TYPE_GREY_ALPHA => 2,
+            COLOUR_TYPE_RGBA => 4,
+            _ => 1,
+        }
+    }
+
+    /// Returns the number of bytes in a scanline of a given width, excluding the filter byte
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.channels() * usize::from(self.bit_depth)).div_ceil(8)
+    }
+}
+
+/// Reads the samples of a scanline, each of `depth` bits
+fn row_samples(row: &[u8], depth: u8, count: usize) -> Vec<u16> {
+    match depth {
+        16 => row.chunks(2).take(count).map(|s| u16::from_be_bytes([s[0], s[1]])).collect(),
+        8 => row.iter().take(count).map(|s| u16::from(*s)).collect(),
+        _ => {
+            let depth = usize::from(depth);
+            let mask = (1 << depth) - 1;
+            (0..count)
+                .map(|i| {
+                    let bit = i * depth;
+                    u16::from(row[bit / 8] >> (8 - depth - bit % 8)) & mask
+                })
+                .collect()
+        }
+    }
+}
+
+/// Scales a sample of `depth` bits to the range 0-255
+fn to_8bit(sample: u16, depth: u8) -> u32 {
+    match depth {
+        16 => u32::from(sample >> 8),
+        8 => u32::from(sample),
+        _ => u32::from(sample) * 255 / ((1 << depth) - 1),
+    }
+}
+
+/// Converts a scanline to ARGB pixels
+fn row_pixels(header: &Header, row: &[u8], width: usize, palette: &[u32], trns: &[u8]) -> Result<Vec<u32>, ImageError> {
+    let depth = header.bit_depth;
+    let samples = row_samples(row, depth, width * header.channels());
+    let argb = |a: u32, r: u32, g: u32, b: u32| (a << 24) | (r << 16) | (g << 8) | b;
+
+    // Single transparent colour of greyscale and RGB images, as 16-bit samples
+    let key = trns.chunks_exact(2).map(|s| u16::from_be_bytes([s[0], s[1]])).collect::<Vec<u16>>();
+    let keyed_alpha = |px: &[u16]| if key == px { 0 } else { 255 };
+
+    samples
+        .chunks(header.channels())
+        .map(|px| {
+            let c = |i: usize| to_8bit(px[i], depth);
+            Ok(match header.colour_type {
+                COLOUR_TYPE_GREY => argb(keyed_alpha(px), c(0), c(0), c(0)),
+                COLOUR_TYPE_RGB => argb(keyed_alpha(px), c(0), c(1), c(2)),
+                COLOUR_TYPE_PALETTE => {
+                    let idx = usize::from(px[0]);
+                    let col = palette.get(idx).ok_or_else(|| ImageError::new("PNG palette index out of range"))?;
+                    let alpha = trns.get(idx).map(|a| u32::from(*a)).unwrap_or(255);
+                    (alpha << 24) | col
+                }
+                COLOUR_TYPE_GREY_ALPHA => argb(c(1), c(0), c(0), c(0)),
+                _ => argb(c(3), c(0), c(1), c(2)),
+            })
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Encodes the Canvas as an 8-bit RGBA PNG file.  PNG images must be at least one pixel wide
+    /// and high, so an error is returned if the Canvas is empty.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::types::ARGBColour;
+    ///
+    /// let mut canvas = Canvas::new(32, 16);
+    /// canvas.clear(&ARGBColour::new(255, 0, 128, 255));
+    /// let png = canvas.to_png().unwrap();
+    /// assert_eq!(&png[1..4], b"PNG");
+    /// assert!(Canvas::new(0, 16).to_png().is_err());
+    /// ```
+    pub fn to_png(&self) -> Result<Vec<u8>, ImageError> {
+        if self.width() == 0 || self.height() == 0 {
+            return Err(ImageError::new("PNG images must not be empty"));
+        }
+        let mut out = SIGNATURE.to_vec();
+
+        let mut ihdr = vec![];
+        ihdr.extend(&(self.width() as u32).to_be_bytes());
+        ihdr.extend(&(self.height() as u32).to_be_bytes());
+        ihdr.extend(&[8, COLOUR_TYPE_RGBA, 0, 0, 0]);
+        write_chunk(&mut out, b"IHDR", &ihdr);
+
+        // Filter each scanline with the filter giving the smallest sum of absolute differences
+        let stride = self.width() * 4;
+        let mut filtered = Vec::with_capacity((stride + 1) * self.height());
+        let mut prior = vec![0; stride];
+        let mut candidate = Vec::with_capacity(stride + 1);
+        let mut best = Vec::with_capacity(stride + 1);
+        let mut row = Vec::with_capacity(stride);
+        for pixels in self.buffer().chunks(self.width()) {
+            row.clear();
+            for px in pixels {
+                let [a, r, g, b] = px.to_be_bytes();
+                row.extend(&[r, g, b, a]);
+            }
+            let mut best_score = u64::MAX;
+            for filter in 0..5 {
+                candidate.clear();
+                filter_row(filter, &row, &prior, 4, &mut candidate);
+                let score = candidate[1..].iter().map(|b| u64::from((*b as i8).unsigned_abs())).sum();
+                if score < best_score {
+                    best_score = score;
+                    std::mem::swap(&mut best, &mut candidate);
+                }
+            }
+            filtered.extend(&best);
+            std::mem::swap(&mut prior, &mut row);
+        }
+
+        write_chunk(&mut out, b"IDAT", &zlib::compress(&filtered));
+        write_chunk(&mut out, b"IEND", &[]);
+        Ok(out)
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Decodes a PNG file into a new Canvas
+    ///
+    /// All PNG colour types and bit depths are supported, as are `tRNS` transparency and Adam7
+    /// interlacing.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: contents of the PNG file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(4, 4);
+    /// canvas.buffer_mut()[5] = 0x80FF8000;
+    ///
+    /// let decoded = Canvas::from_png(&canvas.to_png().unwrap()).unwrap();
+    /// assert_eq!(decoded.buffer(), canvas.buffer());
+    /// assert!(Canvas::from_png(b"not a PNG").is_err());
+    /// ```
+    pub fn from_png(src: &[u8]) -> Result<Canvas, ImageError> {
+        if src.len() < 8 || src[0..8] != SIGNATURE {
+            return Err(ImageError::new("not a PNG file"));
+        }
+
+        let mut header = None;
+        let mut palette = vec![];
+        let mut trns = vec![];
+        let mut idat = vec![];
+        let mut pos = 8;
+        loop {
+            let len_bytes = src.get(pos..pos + 4).ok_or_else(|| ImageError::new("PNG file has no IEND chunk"))?;
+            let len = u32::from_be_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+            if len > i32::MAX as usize {
+                return Err(ImageError::new("PNG chunk length is too large"));
+            }
+            let chunk = src.get(pos + 4..pos + 8 + len).ok_or_else(|| ImageError::new("truncated PNG chunk"))?;
+            let crc = src.get(pos + 8 + len..pos + 12 + len).ok_or_else(|| ImageError::new("truncated PNG chunk"))?;
+            if crc32(chunk) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+                return Err(ImageError::new(&format!(
+                    "CRC mismatch in PNG {} chunk",
+                    String::from_utf8_lossy(&chunk[0..4])
+                )));
+            }
+            pos += 12 + len;
+
+            let (chunk_type, data) = chunk.split_at(4);
+            if header.is_none() && chunk_type != b"IHDR" {
+                return Err(ImageError::new("PNG file does not start with an IHDR chunk"));
+            }
+            match chunk_type {
+                b"IHDR" => header = Some(Header::parse(data)?),
+                b"PLTE" => {
+                    if data.len() % 3 != 0 || data.len() > 256 * 3 {
+                        return Err(ImageError::new("PLTE chunk has an invalid length"));
+                    }
+                    palette = data
+                        .chunks(3)
+                        .map(|c| (u32::from(c[0]) << 16) | (u32::from(c[1]) << 8) | u32::from(c[2]))
+                        .collect();
+                }
+                b"tRNS" => trns = data.to_vec(),
+                b"IDAT" => idat.extend(data),
+                b"IEND" => break,
+                _ => {
+                    if chunk_type[0] & 0x20 == 0 {
+                        return Err(ImageError::new(&format!(
+                            "unsupported critical PNG chunk {}",
+                            String::from_utf8_lossy(chunk_type)
+                        )));
+                    }
+                }
+            }
+        }
+
+        let header = header.ok_or_else(|| ImageError::new("PNG file has no IHDR chunk"))?;
+        if header.colour_type == COLOUR_TYPE_PALETTE && palette.is_empty() {
+            return Err(ImageError::new("palette PNG has no PLTE chunk"));
+        }
+        if idat.is_empty() {
+            return Err(ImageError::new("PNG file has no IDAT chunk"));
+        }
+
+        // Non-interlaced images are decoded as a single pass covering every pixel
+        let passes = if header.interlaced { &ADAM7_PASSES[..] } else { &[(0, 0, 1, 1)][..] };
+        let pass_size = |&(x0, y0, dx, dy): &(usize, usize, usize, usize)| {
+            let pass_width = (header.width + dx - 1 - x0) / dx;
+            let pass_height = (header.height + dy - 1 - y0) / dy;
+            (pass_width, if pass_width == 0 { 0 } else { pass_height })
+        };
+        let expected: usize = passes
+            .iter()
+            .map(|p| {
+                let (w, h) = pass_size(p);
+                (header.row_bytes(w) + 1) * h
+            })
+            .sum();
+        let data = zlib::decompress(&idat, expected)?;
+        if data.len() < expected {
+            return Err(ImageError::new("PNG image data is truncated"));
+        }
+
+        let mut canvas = Canvas::new(header.width, header.height);
+        let bpp = (header.channels() * usize::from(header.bit_depth)).div_ceil(8);
+        let mut pos = 0;
+        for pass in passes {
+            let (x0, y0, dx, dy) = *pass;
+            let (pass_width, pass_height) = pass_size(pass);
+            let stride = header.row_bytes(pass_width);
+            let mut prior = vec![0; stride];
+            for row_idx in 0..pass_height {
+                let mut row = data[pos + 1..pos + 1 + stride].to_vec();
+                unfilter_row(data[pos], &mut row, &prior, bpp)?;
+                let y = y0 + row_idx * dy;
+                for (i, px) in row_pixels(&header, &row, pass_width, &palette, &trns)?.into_iter().enumerate() {
+                    let idx = canvas.buffer_index(x0 + i * dx, y);
+                    canvas.buffer_mut()[idx] = px;
+                }
+                prior = row;
+                pos += stride + 1;
+            }
+        }
+        Ok(canvas)
+    }
+}
@@ -0,0 +1,189 @@
+//! "Quite OK Image" (QOI) encoding and decoding
+//!
+//! QOI is a simple lossless format which keeps alpha and needs very little code to decode, so it
+//! suits shipping assets such as sprite atlases in a WASM bundle.  Canvases are always encoded
+//! with 4 channels, so decoding an encoded Canvas gives back exactly the same ARGB buffer.
+
+use wasm_bindgen::prelude::*;
+
+use super::ImageError;
+use crate::canvas::Canvas;
+
+const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Chunk tags
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xC0;
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const OP_MASK: u8 = 0xC0;
+
+/// Longest run of identical pixels in a single QOI_OP_RUN chunk
+const MAX_RUN: usize = 62;
+
+/// Upper limit on the number of pixels, as recommended by the specification
+const MAX_PIXELS: usize = 400_000_000;
+
+/// Returns the position of a colour within the array of previously seen colours
+fn index_position(px: [u8; 4]) -> usize {
+    let [r, g, b, a] = px.map(usize::from);
+    (r * 3 + g * 5 + b * 7 + a * 11) % 64
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Encodes the Canvas as a 4-channel QOI file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let qoi = Canvas::new(64, 64).to_qoi();
+    /// assert_eq!(&qoi[0..4], b"qoif");
+    /// ```
+    pub fn to_qoi(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend(&(self.width() as u32).to_be_bytes());
+        out.extend(&(self.height() as u32).to_be_bytes());
+        out.extend(&[4, 0]);
+
+        let mut index = [[0u8; 4]; 64];
+        let mut prev = [0, 0, 0, 255];
+        let mut run = 0;
+        for argb in self.buffer() {
+            let [a, r, g, b] = argb.to_be_bytes();
+            let px = [r, g, b, a];
+            if px == prev {
+                run += 1;
+                if run == MAX_RUN {
+                    out.push(OP_RUN | (run - 1) as u8);
+                    run = 0;
+                }
+                continue;
+            }
+            if run > 0 {
+                out.push(OP_RUN | (run - 1) as u8);
+                run = 0;
+            }
+
+            let pos = index_position(px);
+            if index[pos] == px {
+                out.push(OP_INDEX | pos as u8);
+            } else if a == prev[3] {
+                let dr = r.wrapping_sub(prev[0]) as i8;
+                let dg = g.wrapping_sub(prev[1]) as i8;
+                let db = b.wrapping_sub(prev[2]) as i8;
+                let (dr_dg, db_dg) = (dr.wrapping_sub(dg), db.wrapping_sub(dg));
+                if (-2..2).contains(&dr) && (-2..2).contains(&dg) && (-2..2).contains(&db) {
+                    out.push(OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8);
+                } else if (-32..32).contains(&dg) && (-8..8).contains(&dr_dg) && (-8..8).contains(&db_dg) {
+                    out.push(OP_LUMA | (dg + 32) as u8);
+                    out.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    out.extend(&[OP_RGB, r, g, b]);
+                }
+            } else {
+                out.extend(&[OP_RGBA, r, g, b, a]);
+            }
+            index[pos] = px;
+            prev = px;
+        }
+        if run > 0 {
+            out.push(OP_RUN | (run - 1) as u8);
+        }
+        out.extend(&END_MARKER);
+        out
+    }
+
+    /// Decodes a QOI file into a new Canvas
+    ///
+    /// Images with 3 channels are decoded as opaque.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: contents of the QOI file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(4, 4);
+    /// canvas.buffer_mut()[5] = 0x80FF8000;
+    ///
+    /// let decoded = Canvas::from_qoi(&canvas.to_qoi()).unwrap();
+    /// assert_eq!(decoded.buffer(), canvas.buffer());
+    /// ```
+    pub fn from_qoi(src: &[u8]) -> Result<Canvas, ImageError> {
+        if src.len() < HEADER_SIZE || &src[0..4] != MAGIC {
+            return Err(ImageError::new("not a QOI file"));
+        }
+        let width = u32::from_be_bytes([src[4], src[5], src[6], src[7]]) as usize;
+        let height = u32::from_be_bytes([src[8], src[9], src[10], src[11]]) as usize;
+        if src[12] != 3 && src[12] != 4 {
+            return Err(ImageError::new("invalid QOI channel count"));
+        }
+        if src[13] > 1 {
+            return Err(ImageError::new("invalid QOI colour space"));
+        }
+
+        // Each byte of data can describe at most one run of pixels, so reject larger images before
+        // allocating them
+        let count = width.checked_mul(height).filter(|n| *n <= MAX_PIXELS);
+        let count = count.ok_or_else(|| ImageError::new("QOI image is too large"))?;
+        if count > (src.len() - HEADER_SIZE) * MAX_RUN {
+            return Err(ImageError::new("QOI pixel data is truncated"));
+        }
+
+        let truncated = || ImageError::new("QOI pixel data is truncated");
+        let mut canvas = Canvas::new(width, height);
+        let mut index = [[0u8; 4]; 64];
+        let mut px = [0u8, 0, 0, 255];
+        let mut pos = HEADER_SIZE;
+        let mut i = 0;
+        while i < count {
+            let tag = *src.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            let mut run = 1;
+            match tag {
+                OP_RGB | OP_RGBA => {
+                    let len = if tag == OP_RGB { 3 } else { 4 };
+                    let data = src.get(pos..pos + len).ok_or_else(truncated)?;
+                    px[..len].copy_from_slice(data);
+                    pos += len;
+                }
+                _ => match tag & OP_MASK {
+                    OP_INDEX => px = index[usize::from(tag)],
+                    OP_DIFF => {
+                        px[0] = px[0].wrapping_add((tag >> 4) & 3).wrapping_sub(2);
+                        px[1] = px[1].wrapping_add((tag >> 2) & 3).wrapping_sub(2);
+                        px[2] = px[2].wrapping_add(tag & 3).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let next = *src.get(pos).ok_or_else(truncated)?;
+                        pos += 1;
+                        let dg = (tag & 0x3F).wrapping_sub(32);
+                        px[0] = px[0].wrapping_add(dg).wrapping_add(next >> 4).wrapping_sub(8);
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2].wrapping_add(dg).wrapping_add(next & 0x0F).wrapping_sub(8);
+                    }
+                    _ => run = usize::from(tag & 0x3F) + 1,
+                },
+            }
+            index[index_position(px)] = px;
+
+            let [r, g, b, a] = px;
+            let argb = u32::from_be_bytes([a, r, g, b]);
+            let end = (i + run).min(count);
+            canvas.buffer_mut()[i..end].iter_mut().for_each(|dst| *dst = argb);
+            i = end;
+        }
+        Ok(canvas)
+    }
+}
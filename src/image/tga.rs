@@ -0,0 +1,230 @@
+//! Truevision TGA encoding and decoding
+//!
+//! Colour-mapped, truecolour and greyscale images can be decoded, both uncompressed and RLE
+//! compressed, with 15, 16, 24 or 32-bit colours (8 or 16-bit for greyscale) and any of the four
+//! pixel origins.  Alpha is taken from the attribute bits of 16 and 32-bit colours when the image
+//! descriptor declares them, otherwise pixels are opaque.
+//!
+//! Canvases are encoded as 32-bit truecolour images with 8 bits of alpha and a top-left origin,
+//! optionally RLE compressed.
+
+use wasm_bindgen::prelude::*;
+
+use super::ImageError;
+use crate::canvas::Canvas;
+
+/// Size of the fixed TGA header
+const HEADER_SIZE: usize = 18;
+
+/// Image types
+const TYPE_COLOUR_MAPPED: u8 = 1;
+const TYPE_TRUECOLOUR: u8 = 2;
+const TYPE_GREY: u8 = 3;
+
+/// Added to an image type when its pixels are RLE compressed
+const TYPE_RLE: u8 = 8;
+
+/// Image descriptor bits
+const DESC_ALPHA_BITS: u8 = 0x0F;
+const DESC_RIGHT_TO_LEFT: u8 = 0x10;
+const DESC_TOP_TO_BOTTOM: u8 = 0x20;
+
+/// Longest run of pixels in a single RLE packet
+const MAX_PACKET: usize = 128;
+
+/// Converts a little-endian 15, 16, 24 or 32-bit colour to ARGB
+fn colour(src: &[u8], bits: u8, alpha: bool) -> u32 {
+    match bits {
+        15 | 16 => {
+            let v = u32::from(u16::from_le_bytes([src[0], src[1]]));
+            let scale = |c: u32| (c & 0x1F) * 255 / 31;
+            let a = if alpha && bits == 16 && v & 0x8000 == 0 { 0 } else { 255 };
+            (a << 24) | (scale(v >> 10) << 16) | (scale(v >> 5) << 8) | scale(v)
+        }
+        24 => 0xFF00_0000 | (u32::from(src[2]) << 16) | (u32::from(src[1]) << 8) | u32::from(src[0]),
+        _ => {
+            let a = if alpha { u32::from(src[3]) } else { 255 };
+            (a << 24) | (u32::from(src[2]) << 16) | (u32::from(src[1]) << 8) | u32::from(src[0])
+        }
+    }
+}
+
+/// Reads `count` pixels of `size` bytes, expanding RLE packets if `rle` is set
+fn read_pixels(src: &[u8], count: usize, size: usize, rle: bool) -> Result<Vec<&[u8]>, ImageError> {
+    let truncated = || ImageError::new("TGA pixel data is truncated");
+    if !rle {
+        let data = count.checked_mul(size).and_then(|len| src.get(..len)).ok_or_else(truncated)?;
+        return Ok(data.chunks(size).collect());
+    }
+
+    let mut pixels = vec![];
+    let mut pos = 0;
+    while pixels.len() < count {
+        let packet = *src.get(pos).ok_or_else(truncated)?;
+        let len = usize::from(packet & 0x7F) + 1;
+        pos += 1;
+        if packet & 0x80 != 0 {
+            let px = src.get(pos..pos + size).ok_or_else(truncated)?;
+            pixels.extend(std::iter::repeat_n(px, len));
+            pos += size;
+        } else {
+            let data = src.get(pos..pos + len * size).ok_or_else(truncated)?;
+            pixels.extend(data.chunks(size));
+            pos += len * size;
+        }
+    }
+    pixels.truncate(count);
+    Ok(pixels)
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Decodes a TGA file into a new Canvas
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: contents of the TGA file
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(3, 2);
+    /// canvas.buffer_mut()[4] = 0x80102030;
+    ///
+    /// let decoded = Canvas::from_tga(&canvas.to_tga(true).unwrap()).unwrap();
+    /// assert_eq!(decoded.buffer(), canvas.buffer());
+    /// ```
+    pub fn from_tga(src: &[u8]) -> Result<Canvas, ImageError> {
+        if src.len() < HEADER_SIZE {
+            return Err(ImageError::new("truncated TGA header"));
+        }
+        let id_len = usize::from(src[0]);
+        let map_type = src[1];
+        let image_type = src[2] & !TYPE_RLE;
+        let rle = src[2] & TYPE_RLE != 0;
+        let map_first = usize::from(u16::from_le_bytes([src[3], src[4]]));
+        let map_len = usize::from(u16::from_le_bytes([src[5], src[6]]));
+        let map_bits = src[7];
+        let width = usize::from(u16::from_le_bytes([src[12], src[13]]));
+        let height = usize::from(u16::from_le_bytes([src[14], src[15]]));
+        let bits = src[16];
+        let descriptor = src[17];
+        let alpha = descriptor & DESC_ALPHA_BITS != 0;
+
+        let valid = match image_type {
+            TYPE_COLOUR_MAPPED => map_type == 1 && (bits == 8 || bits == 16),
+            TYPE_TRUECOLOUR => [15, 16, 24, 32].contains(&bits),
+            TYPE_GREY => bits == 8 || bits == 16,
+            _ => return Err(ImageError::new("unsupported TGA image type")),
+        };
+        if !valid || map_type > 1 {
+            return Err(ImageError::new("unsupported TGA pixel depth for its image type"));
+        }
+        if map_type == 1 && ![15, 16, 24, 32].contains(&map_bits) {
+            return Err(ImageError::new("unsupported TGA colour map entry size"));
+        }
+
+        // The image ID and colour map precede the pixel data
+        let map_size = if map_type == 1 { map_len * usize::from(map_bits).div_ceil(8) } else { 0 };
+        let map_start = HEADER_SIZE + id_len;
+        let map_data = src
+            .get(map_start..map_start + map_size)
+            .ok_or_else(|| ImageError::new("truncated TGA colour map"))?;
+        let palette = map_data
+            .chunks(usize::from(map_bits).div_ceil(8).max(1))
+            .map(|entry| colour(entry, map_bits, alpha))
+            .collect::<Vec<u32>>();
+
+        let size = usize::from(bits).div_ceil(8);
+        let pixels = read_pixels(&src[map_start + map_size..], width * height, size, rle)?;
+        let mut canvas = Canvas::new(width, height);
+        for (i, px) in pixels.into_iter().enumerate() {
+            let argb = match image_type {
+                TYPE_COLOUR_MAPPED => {
+                    let idx = px.iter().rev().fold(0, |idx, b| (idx << 8) | usize::from(*b));
+                    *idx.checked_sub(map_first)
+                        .and_then(|idx| palette.get(idx))
+                        .ok_or_else(|| ImageError::new("TGA colour map index out of range"))?
+                }
+                TYPE_GREY => {
+                    let a = if size == 2 && alpha { u32::from(px[1]) } else { 255 };
+                    (a << 24) | (u32::from(px[0]) * 0x0001_0101)
+                }
+                _ => colour(px, bits, alpha),
+            };
+
+            // Rows are stored bottom to top unless the descriptor says otherwise
+            let (x, row) = (i % width, i / width);
+            let x = if descriptor & DESC_RIGHT_TO_LEFT != 0 { width - 1 - x } else { x };
+            let y = if descriptor & DESC_TOP_TO_BOTTOM != 0 { row } else { height - 1 - row };
+            let idx = canvas.buffer_index(x, y);
+            canvas.buffer_mut()[idx] = argb;
+        }
+        Ok(canvas)
+    }
+
+    /// Encodes the Canvas as a 32-bit TGA file with alpha.  TGA dimensions are 16-bit, so an error
+    /// is returned if the Canvas is more than 65535 pixels wide or high.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `rle`: if `true` the pixels are RLE compressed, with packets never crossing rows
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    ///
+    /// let tga = Canvas::new(16, 16).to_tga(true).unwrap();
+    /// assert_eq!(tga[2], 10);
+    /// ```
+    pub fn to_tga(&self, rle: bool) -> Result<Vec<u8>, ImageError> {
+        if self.width() > usize::from(u16::MAX) || self.height() > usize::from(u16::MAX) {
+            return Err(ImageError::new("TGA images must be at most 65535 pixels wide and high"));
+        }
+        let mut out = vec![0; HEADER_SIZE];
+        out[2] = if rle { TYPE_TRUECOLOUR + TYPE_RLE } else { TYPE_TRUECOLOUR };
+        out[12..14].copy_from_slice(&(self.width() as u16).to_le_bytes());
+        out[14..16].copy_from_slice(&(self.height() as u16).to_le_bytes());
+        out[16] = 32;
+        out[17] = 8 | DESC_TOP_TO_BOTTOM;
+
+        let bgra = |px: u32| {
+            let [a, r, g, b] = px.to_be_bytes();
+            [b, g, r, a]
+        };
+        for row in self.buffer().chunks(self.width().max(1)).take(self.height()) {
+            if !rle {
+                row.iter().for_each(|px| out.extend(&bgra(*px)));
+                continue;
+            }
+
+            // Runs of 2 or more identical pixels become run packets, everything else raw packets
+            let mut x = 0;
+            while x < row.len() {
+                let run = row[x..].iter().take(MAX_PACKET).take_while(|px| **px == row[x]).count();
+                if run >= 2 {
+                    out.push(0x80 | (run - 1) as u8);
+                    out.extend(&bgra(row[x]));
+                    x += run;
+                    continue;
+                }
+                let mut len = 1;
+                while x + len < row.len() && len < MAX_PACKET && row.get(x + len + 1) != Some(&row[x + len]) {
+                    len += 1;
+                }
+                out.push((len - 1) as u8);
+                row[x..x + len].iter().for_each(|px| out.extend(&bgra(*px)));
+                x += len;
+            }
+        }
+
+        // TGA 2.0 footer with no extension or developer areas
+        out.extend(&[0; 8]);
+        out.extend(b"TRUEVISION-XFILE.\0");
+        Ok(out)
+    }
+}
@@ -0,0 +1,181 @@
+//! Streaming of Canvas frames to uncompressed video formats
+//!
+//! Frames are written to any [`Write`] implementation as they are rendered, so long animations
+//! don't need to be held in memory.  [`Y4mWriter`] produces YUV4MPEG2 files and [`RawRgbaWriter`]
+//! produces headerless RGBA frames, both of which standard tools can turn into compressed video,
+//! e.g.:
+//!
+//! ```text
+//! ffmpeg -i out.y4m out.mp4
+//! ffmpeg -f rawvideo -pix_fmt rgba -s 320x240 -r 30 -i out.rgba out.mp4
+//! ```
+//!
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`Y4mWriter`]: ./struct.Y4mWriter.html
+//! [`RawRgbaWriter`]: ./struct.RawRgbaWriter.html
+
+use std::io::{self, Write};
+
+use crate::canvas::Canvas;
+
+/// A destination for a sequence of frames of the same size
+pub trait FrameSink {
+
+    /// Writes a frame, which must be the size the sink was created with
+    fn write_frame(&mut self, frame: &Canvas) -> io::Result<()>;
+}
+
+/// Returns an error if a frame is not the expected size
+fn check_size(frame: &Canvas, width: usize, height: usize) -> io::Result<()> {
+    if frame.width() != width || frame.height() != height {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame size does not match the output size"));
+    }
+    Ok(())
+}
+
+/// Converts a Canvas to planar YUV 4:2:0 using the BT.601 limited range ("TV") matrix
+///
+/// Returns the full resolution Y plane followed by the U and V planes, each of which has one
+/// sample per 2x2 block of pixels (rounding up for odd sizes) averaged from the block's RGB
+/// values.  Alpha is ignored.
+pub fn rgb_to_yuv420(canvas: &Canvas) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let (width, height) = (canvas.width(), canvas.height());
+    let rgb = |x: usize, y: usize| {
+        let [_, r, g, b] = canvas.buffer()[canvas.buffer_index(x, y)].to_be_bytes();
+        [i32::from(r), i32::from(g), i32::from(b)]
+    };
+
+    let y_plane = canvas
+        .buffer()
+        .iter()
+        .map(|px| {
+            let [_, r, g, b] = px.to_be_bytes();
+            let (r, g, b) = (i32::from(r), i32::from(g), i32::from(b));
+            (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8
+        })
+        .collect();
+
+    let (cw, ch) = (width.div_ceil(2), height.div_ceil(2));
+    let mut u_plane = Vec::with_capacity(cw * ch);
+    let mut v_plane = Vec::with_capacity(cw * ch);
+    for cy in 0..ch {
+        for cx in 0..cw {
+            // Average the block, repeating the last row or column of odd sized canvases
+            let mut sum = [0; 3];
+            for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let px = rgb((cx * 2 + dx).min(width - 1), (cy * 2 + dy).min(height - 1));
+                sum.iter_mut().zip(&px).for_each(|(s, c)| *s += c);
+            }
+            let [r, g, b] = sum.map(|s| (s + 2) / 4);
+            u_plane.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+            v_plane.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+        }
+    }
+    (y_plane, u_plane, v_plane)
+}
+
+/// Writes frames to a YUV4MPEG2 (Y4M) stream with 4:2:0 chroma subsampling
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+    width: usize,
+    height: usize,
+}
+
+impl<W: Write> Y4mWriter<W> {
+
+    /// Creates a Y4mWriter, writing the stream header
+    ///
+    /// # Arguments:
+    ///
+    ///   - `writer`: destination of the stream
+    ///   - `width`: width of each frame
+    ///   - `height`: height of each frame
+    ///   - `fps_num`: numerator of the frame rate
+    ///   - `fps_den`: denominator of the frame rate
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::image::video::{FrameSink, Y4mWriter};
+    ///
+    /// let mut y4m = Y4mWriter::new(vec![], 64, 48, 30, 1).unwrap();
+    /// let frame = Canvas::new(64, 48);
+    /// for _ in 0..10 {
+    ///     y4m.write_frame(&frame).unwrap();
+    /// }
+    /// let data = y4m.into_inner();
+    /// assert!(data.starts_with(b"YUV4MPEG2 W64 H48 F30:1"));
+    /// ```
+    pub fn new(mut writer: W, width: usize, height: usize, fps_num: u32, fps_den: u32) -> io::Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Y4M frames must not be empty"));
+        }
+        writeln!(writer, "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg", width, height, fps_num, fps_den)?;
+        Ok(Self { writer, width, height })
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> FrameSink for Y4mWriter<W> {
+    fn write_frame(&mut self, frame: &Canvas) -> io::Result<()> {
+        check_size(frame, self.width, self.height)?;
+        let (y, u, v) = rgb_to_yuv420(frame);
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&y)?;
+        self.writer.write_all(&u)?;
+        self.writer.write_all(&v)
+    }
+}
+
+/// Writes frames as consecutive, headerless 8-bit RGBA pixel data
+pub struct RawRgbaWriter<W: Write> {
+    writer: W,
+    width: usize,
+    height: usize,
+}
+
+impl<W: Write> RawRgbaWriter<W> {
+
+    /// Creates a RawRgbaWriter for frames of a given size
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::image::video::{FrameSink, RawRgbaWriter};
+    ///
+    /// let mut raw = RawRgbaWriter::new(vec![], 2, 1);
+    /// let mut frame = Canvas::new(2, 1);
+    /// frame.buffer_mut()[1] = 0x80102030;
+    /// raw.write_frame(&frame).unwrap();
+    /// assert_eq!(raw.into_inner(), vec![0, 0, 0, 0, 0x10, 0x20, 0x30, 0x80]);
+    /// ```
+    pub fn new(writer: W, width: usize, height: usize) -> Self {
+        Self { writer, width, height }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> FrameSink for RawRgbaWriter<W> {
+    fn write_frame(&mut self, frame: &Canvas) -> io::Result<()> {
+        check_size(frame, self.width, self.height)?;
+        let rgba = frame
+            .buffer()
+            .iter()
+            .flat_map(|px| {
+                let [a, r, g, b] = px.to_be_bytes();
+                [r, g, b, a]
+            })
+            .collect::<Vec<u8>>();
+        self.writer.write_all(&rgba)
+    }
+}
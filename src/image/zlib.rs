@@ -0,0 +1,458 @@
+//! zlib (RFC 1950) streams containing DEFLATE (RFC 1951) compressed data
+
+use super::ImageError;
+
+/// Size of the LZ77 sliding window
+const WINDOW_SIZE: usize = 32768;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Number of bits used to hash the first bytes of a match
+const HASH_BITS: usize = 15;
+
+/// Maximum number of earlier positions compared when searching for a match
+const MAX_CHAIN: usize = 64;
+
+/// Largest amount of data in a single stored block
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Base lengths of length codes 257 to 285 and their number of extra bits
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of distance codes 0 to 29 and their number of extra bits
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Writes values to a byte stream starting from the least significant bit
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { out: vec![], bits: 0, count: 0 }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which is stored starting from its most significant bit
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn flush(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Returns the fixed Huffman code and length of a literal/length symbol
+fn fixed_lit_code(sym: u32) -> (u32, u32) {
+    match sym {
+        0..=143 => (0x30 + sym, 8),
+        144..=255 => (0x190 + sym - 144, 9),
+        256..=279 => (sym - 256, 7),
+        _ => (0xC0 + sym - 280, 8),
+    }
+}
+
+/// Returns the index of the largest table entry no greater than `value`
+fn code_index(table: &[u16], value: usize) -> usize {
+    table.iter().rposition(|b| usize::from(*b) <= value).unwrap_or(0)
+}
+
+/// Compresses data into a single DEFLATE block using LZ77 and the fixed Huffman codes
+fn deflate_fixed(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.write(1, 1);
+    w.write(1, 2);
+
+    let hash = |i: usize| {
+        let v = (u32::from(data[i]) << 16) | (u32::from(data[i + 1]) << 8) | u32::from(data[i + 2]);
+        (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    };
+
+    // Most recent position of each hash, and the previous position with the same hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        // Follow the hash chain to find the longest earlier match
+        let (mut best_len, mut best_dist) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - i);
+            let mut cand = head[hash(i)];
+            let mut chain = 0;
+            while cand != usize::MAX && cand < i && i - cand <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[cand..cand + max_len]
+                    .iter()
+                    .zip(&data[i..i + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - cand;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[cand % WINDOW_SIZE];
+                if next >= cand {
+                    break;
+                }
+                cand = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            let li = code_index(&LENGTH_BASE, best_len);
+            let (code, len) = fixed_lit_code(257 + li as u32);
+            w.write_code(code, len);
+            w.write((best_len - usize::from(LENGTH_BASE[li])) as u32, u32::from(LENGTH_EXTRA[li]));
+            let di = code_index(&DIST_BASE, best_dist);
+            w.write_code(di as u32, 5);
+            w.write((best_dist - usize::from(DIST_BASE[di])) as u32, u32::from(DIST_EXTRA[di]));
+            for j in i..i + best_len {
+                insert(j, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            let (code, len) = fixed_lit_code(u32::from(data[i]));
+            w.write_code(code, len);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+
+    let (code, len) = fixed_lit_code(256);
+    w.write_code(code, len);
+    w.flush()
+}
+
+/// Stores data in uncompressed DEFLATE blocks
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        return vec![1, 0, 0, 0xFF, 0xFF];
+    }
+    while let Some(chunk) = chunks.next() {
+        out.push(if chunks.peek().is_none() { 1 } else { 0 });
+        let len = chunk.len() as u16;
+        out.extend(&len.to_le_bytes());
+        out.extend(&(!len).to_le_bytes());
+        out.extend(chunk);
+    }
+    out
+}
+
+/// Returns the Adler-32 checksum of data
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    // Sums are reduced every 5552 bytes, the most which can be added without overflow
+    for chunk in data.chunks(5552) {
+        for x in chunk {
+            a += u32::from(*x);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Compresses data into a zlib stream, falling back to uncompressed blocks if compression does
+/// not reduce the size
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x9C];
+    let compressed = deflate_fixed(data);
+    let stored_len = data.len() + 5 * data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    if compressed.len() < stored_len {
+        out.extend(compressed);
+    } else {
+        out.extend(deflate_stored(data));
+    }
+    out.extend(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Order in which code length code lengths are stored in a dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Reads values from a byte stream starting from the least significant bit
+struct BitReader<'a> {
+    src: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(src: &'a [u8]) -> Self {
+        Self { src, pos: 0, bits: 0, count: 0 }
+    }
+
+    fn read(&mut self, count: u32) -> Result<u32, ImageError> {
+        while self.count < count {
+            let byte = *self.src.get(self.pos).ok_or_else(|| ImageError::new("truncated compressed data"))?;
+            self.bits |= u32::from(byte) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << count) - 1) as u32;
+        self.bits >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    /// Discards bits up to the next byte boundary
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length and the symbols in
+/// code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ImageError> {
+        let mut counts = [0u16; 16];
+        for len in lengths {
+            counts[usize::from(*len)] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject over-subscribed codes, which can't be decoded unambiguously
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = (left << 1) - i32::from(*count);
+            if left < 0 {
+                return Err(ImageError::new("invalid Huffman code lengths"));
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (sym, len) in lengths.iter().enumerate() {
+            if *len > 0 {
+                symbols[usize::from(offsets[usize::from(*len)])] = sym as u16;
+                offsets[usize::from(*len)] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16, ImageError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= r.read(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(ImageError::new("invalid Huffman code in compressed data"))
+    }
+}
+
+/// Returns the fixed literal/length and distance codes
+fn fixed_codes() -> Result<(Huffman, Huffman), ImageError> {
+    let mut lengths = [0u8; 288];
+    for (sym, len) in lengths.iter_mut().enumerate() {
+        *len = match sym {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// Reads the code lengths of a dynamic block and returns its literal/length and distance codes
+fn dynamic_codes(r: &mut BitReader) -> Result<(Huffman, Huffman), ImageError> {
+    let nlen = r.read(5)? as usize + 257;
+    let ndist = r.read(5)? as usize + 1;
+    let ncode = r.read(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(ImageError::new("too many codes in dynamic Huffman block"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for idx in CODE_LENGTH_ORDER.iter().take(ncode) {
+        code_lengths[*idx] = r.read(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![];
+    while lengths.len() < nlen + ndist {
+        let (value, repeat) = match code_length_code.decode(r)? {
+            sym @ 0..=15 => (sym as u8, 1),
+            16 => {
+                let last = *lengths.last().ok_or_else(|| ImageError::new("repeated code length with no previous length"))?;
+                (last, 3 + r.read(2)?)
+            }
+            17 => (0, 3 + r.read(3)?),
+            _ => (0, 11 + r.read(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > nlen + ndist {
+        return Err(ImageError::new("code lengths overrun in dynamic Huffman block"));
+    }
+    if lengths[256] == 0 {
+        return Err(ImageError::new("dynamic Huffman block has no end-of-block code"));
+    }
+    Ok((Huffman::new(&lengths[..nlen])?, Huffman::new(&lengths[nlen..])?))
+}
+
+/// Returns an error if decompressed data would grow past `max_size` bytes
+fn check_size(size: usize, max_size: usize) -> Result<(), ImageError> {
+    if size > max_size {
+        return Err(ImageError::new("decompressed data is larger than expected"));
+    }
+    Ok(())
+}
+
+/// Decodes the symbols of a compressed block until its end-of-block code, without letting the
+/// output grow past `max_size` bytes
+fn inflate_block(
+    r: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+    max_size: usize,
+) -> Result<(), ImageError> {
+    loop {
+        let sym = usize::from(lit.decode(r)?);
+        if sym < 256 {
+            check_size(out.len() + 1, max_size)?;
+            out.push(sym as u8);
+            continue;
+        }
+        if sym == 256 {
+            return Ok(());
+        }
+        let li = sym - 257;
+        if li >= LENGTH_BASE.len() {
+            return Err(ImageError::new("invalid length code in compressed data"));
+        }
+        let len = usize::from(LENGTH_BASE[li]) + r.read(u32::from(LENGTH_EXTRA[li]))? as usize;
+        let di = usize::from(dist.decode(r)?);
+        if di >= DIST_BASE.len() {
+            return Err(ImageError::new("invalid distance code in compressed data"));
+        }
+        let distance = usize::from(DIST_BASE[di]) + r.read(u32::from(DIST_EXTRA[di]))? as usize;
+        if distance > out.len() {
+            return Err(ImageError::new("distance refers to data before the start of the stream"));
+        }
+        check_size(out.len() + len, max_size)?;
+
+        // Copy byte by byte as the source may overlap the bytes being written
+        let start = out.len() - distance;
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+}
+
+/// Decompresses a zlib stream, verifying its header and Adler-32 checksum
+///
+/// Decompression stops with an error as soon as the output would be larger than `max_size` bytes,
+/// so that small malicious streams can't expand to exhaust memory.
+pub fn decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, ImageError> {
+    if data.len() < 6 {
+        return Err(ImageError::new("truncated zlib stream"));
+    }
+    if data[0] & 0x0F != 8 || (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 != 0 {
+        return Err(ImageError::new("invalid zlib header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(ImageError::new("zlib preset dictionaries are not supported"));
+    }
+
+    let mut r = BitReader::new(&data[2..]);
+    let mut out = vec![];
+    loop {
+        let last = r.read(1)? == 1;
+        match r.read(2)? {
+            0 => {
+                r.align();
+                let header = r.src.get(r.pos..r.pos + 4).ok_or_else(|| ImageError::new("truncated stored block"))?;
+                let len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                if len != usize::from(!u16::from_le_bytes([header[2], header[3]])) {
+                    return Err(ImageError::new("stored block length does not match its complement"));
+                }
+                r.pos += 4;
+                check_size(out.len() + len, max_size)?;
+                let block = r.src.get(r.pos..r.pos + len).ok_or_else(|| ImageError::new("truncated stored block"))?;
+                out.extend(block);
+                r.pos += len;
+            }
+            1 => {
+                let (lit, dist) = fixed_codes()?;
+                inflate_block(&mut r, &mut out, &lit, &dist, max_size)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut r)?;
+                inflate_block(&mut r, &mut out, &lit, &dist, max_size)?;
+            }
+            _ => return Err(ImageError::new("invalid DEFLATE block type")),
+        }
+        if last {
+            break;
+        }
+    }
+
+    r.align();
+    let checksum = r.src.get(r.pos..r.pos + 4).ok_or_else(|| ImageError::new("truncated zlib checksum"))?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err(ImageError::new("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
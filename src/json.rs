@@ -0,0 +1,227 @@
+//! Minimal JSON reader used for loading asset descriptions
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Maximum nesting depth of arrays and objects, which bounds the parser's recursion
+const MAX_DEPTH: usize = 128;
+
+/// A parsed JSON value.  Object members are kept in document order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+
+    /// Returns the value of an object member, or `None` if this is not an object or the member
+    /// does not exist
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, JsonValue)>> {
+        match self {
+            JsonValue::Object(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("expected '{}' but found end of input", expected)),
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in literal.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(format!("invalid literal, expected '{}'", literal));
+            }
+        }
+        Ok(value)
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape '{}'", hex))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let mut code = self.parse_hex4()?;
+
+                        // Characters outside the Basic Multilingual Plane are escaped as a UTF-16
+                        // surrogate pair, which is combined if the low surrogate follows
+                        if (0xD800..0xDC00).contains(&code) && self.chars.peek() == Some(&'\\') {
+                            let mut ahead = self.chars.clone();
+                            ahead.next();
+                            if ahead.next() == Some('u') {
+                                self.chars = ahead;
+                                let low = self.parse_hex4()?;
+                                if (0xDC00..0xE000).contains(&low) {
+                                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                } else {
+                                    out.push('\u{fffd}');
+                                    code = low;
+                                }
+                            }
+                        }
+                        out.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                    },
+                    _ => return Err(String::from("invalid escape sequence")),
+                },
+                Some(c) => out.push(c),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') {
+                break;
+            }
+            s.push(c);
+            self.chars.next();
+        }
+        s.parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("invalid number '{}'", s))
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        if depth > MAX_DEPTH && matches!(self.chars.peek(), Some('{') | Some('[')) {
+            return Err(format!("arrays and objects nested more than {} deep", MAX_DEPTH));
+        }
+        match self.chars.peek() {
+            Some('{') => {
+                self.chars.next();
+                let mut members = vec![];
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Ok(JsonValue::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.parse_string()?;
+                    self.expect(':')?;
+                    members.push((key, self.parse_value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(JsonValue::Object(members)),
+                        _ => return Err(String::from("expected ',' or '}' in object")),
+                    }
+                }
+            },
+            Some('[') => {
+                self.chars.next();
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Ok(JsonValue::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(JsonValue::Array(items)),
+                        _ => return Err(String::from("expected ',' or ']' in array")),
+                    }
+                }
+            },
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some(_) => self.parse_number(),
+            None => Err(String::from("unexpected end of input")),
+        }
+    }
+}
+
+/// Parses a JSON document, returning a description of the problem if it is malformed
+pub fn parse(src: &str) -> Result<JsonValue, String> {
+    let mut parser = Parser { chars: src.chars().peekable() };
+    let value = parser.parse_value(1)?;
+    parser.skip_whitespace();
+    if parser.chars.next().is_some() {
+        return Err(String::from("unexpected trailing characters"));
+    }
+    Ok(value)
+}
@@ -1,6 +1,16 @@
 pub mod canvas;
 pub mod drawing;
+pub mod edit;
+pub mod filter;
+pub mod font;
+pub mod image;
+pub mod lighting;
+pub mod mesh;
+pub mod sprite;
+pub mod tilemap;
+pub mod transform;
 pub mod types;
+mod json;
 mod utils;
 
 use wasm_bindgen::prelude::*;
@@ -0,0 +1,182 @@
+//! Simple lighting model with directional and point lights
+//!
+//! Light is accumulated from an ambient term, a Lambert diffuse term and an optional Blinn-Phong
+//! specular term.  The ambient and diffuse light is multiplied into the surface colour, while the
+//! specular light is added afterwards so that highlights take the colour of the light rather than
+//! the surface.  The resulting colour is multiplied into the fill or texture colour when drawing
+//! (see [`ShadedVertex`]).
+//!
+//! [`ShadedVertex`]: ../types/struct.ShadedVertex.html
+
+use wasm_bindgen::prelude::*;
+
+use crate::types::ARGBColour;
+
+/// A single light source.  All colours have components in the range [0,1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+
+    /// Light shining from infinitely far away in a single `direction`
+    Directional {
+        direction: [f64; 3],
+        colour: [f64; 3],
+    },
+
+    /// Light shining in all directions from a `position`.  Intensity falls off by
+    /// `1 / (1 + attenuation * distance^2)`.
+    Point {
+        position: [f64; 3],
+        colour: [f64; 3],
+        attenuation: f64,
+    },
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+/// A set of lights and material parameters used to calculate lit colours
+pub struct Lighting {
+    ambient: [f64; 3],
+    lights: Vec<Light>,
+    eye: [f64; 3],
+    specular: f64,
+    shininess: f64,
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn normalise(a: [f64; 3]) -> [f64; 3] {
+    let len = dot(a, a).sqrt();
+    if len > 0.0 { [a[0] / len, a[1] / len, a[2] / len] } else { a }
+}
+
+/// Returns the unit normal of a triangle with counter-clockwise winding
+pub fn face_normal(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> [f64; 3] {
+    let e1 = sub(b, a);
+    let e2 = sub(c, a);
+    normalise([
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ])
+}
+
+#[wasm_bindgen]
+impl Lighting {
+
+    /// Creates a new Lighting model with a given ambient colour and no lights.  Specular
+    /// highlights are disabled by default.
+    pub fn new(ambient_r: f64, ambient_g: f64, ambient_b: f64) -> Self {
+        Self {
+            ambient: [ambient_r, ambient_g, ambient_b],
+            lights: vec![],
+            eye: [0.0, 0.0, 0.0],
+            specular: 0.0,
+            shininess: 1.0,
+        }
+    }
+
+    /// Adds a directional light shining in the direction (dx,dy,dz)
+    pub fn add_directional_light(&mut self, dx: f64, dy: f64, dz: f64, r: f64, g: f64, b: f64) {
+        self.lights.push(Light::Directional {
+            direction: normalise([dx, dy, dz]),
+            colour: [r, g, b],
+        });
+    }
+
+    /// Adds a point light at position (x,y,z)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_point_light(&mut self, x: f64, y: f64, z: f64, r: f64, g: f64, b: f64, attenuation: f64) {
+        self.lights.push(Light::Point {
+            position: [x, y, z],
+            colour: [r, g, b],
+            attenuation,
+        });
+    }
+
+    /// Enables Blinn-Phong specular highlights as seen from an eye position (x,y,z).  A
+    /// `strength` of 0 disables specular highlights.
+    pub fn set_specular(&mut self, strength: f64, shininess: f64, x: f64, y: f64, z: f64) {
+        self.specular = strength;
+        self.shininess = shininess;
+        self.eye = [x, y, z];
+    }
+
+    /// Calculates the lit colour of a surface point (px,py,pz) with normal (nx,ny,nz) and a base
+    /// colour, which is multiplied by the ambient and diffuse light before specular light is added.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::lighting::Lighting;
+    /// use rust_wasm_graphics_lib::types::ARGBColour;
+    ///
+    /// let mut lighting = Lighting::new(0.0, 0.0, 0.0);
+    /// lighting.add_directional_light(0.0, 0.0, -1.0, 1.0, 1.0, 1.0);
+    ///
+    /// // Surface facing the light is fully lit
+    /// let col = lighting.light_colour(&ARGBColour::new(255, 200, 100, 50), 0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    /// assert_eq!((col.r, col.g, col.b), (200, 100, 50));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn light_colour(&self, col: &ARGBColour, px: f64, py: f64, pz: f64, nx: f64, ny: f64, nz: f64) -> ARGBColour {
+        let (diffuse, specular) = self.shade([px, py, pz], [nx, ny, nz]);
+        let light = |c: u8, i: usize| {
+            let lit = f64::from(c) * diffuse[i].clamp(0.0, 1.0) + 255.0 * specular[i];
+            (lit.clamp(0.0, 255.0) + 0.5).floor() as u8
+        };
+        ARGBColour::new(col.a, light(col.r, 0), light(col.g, 1), light(col.b, 2))
+    }
+}
+
+impl Lighting {
+
+    /// Returns all lights
+    pub fn lights(&self) -> &Vec<Light> {
+        &self.lights
+    }
+
+    /// Calculates the RGB light colours reaching a surface point with a given normal, returning
+    /// the ambient and diffuse light, which is multiplied into the surface colour, and the
+    /// specular light, which is added to it.  Components are not clamped.
+    pub fn shade(&self, position: [f64; 3], normal: [f64; 3]) -> ([f64; 3], [f64; 3]) {
+        let n = normalise(normal);
+        let view = normalise(sub(self.eye, position));
+        let mut diffuse = self.ambient;
+        let mut specular = [0.0; 3];
+        for light in &self.lights {
+            let (to_light, colour, intensity) = match *light {
+                Light::Directional { direction, colour } => {
+                    ([-direction[0], -direction[1], -direction[2]], colour, 1.0)
+                },
+                Light::Point { position: lp, colour, attenuation } => {
+                    let d = sub(lp, position);
+                    (normalise(d), colour, 1.0 / (1.0 + attenuation * dot(d, d)))
+                },
+            };
+            let n_dot_l = dot(n, to_light);
+            if n_dot_l <= 0.0 {
+                continue;
+            }
+            let mut highlight = 0.0;
+            if self.specular > 0.0 {
+                let half = normalise([
+                    to_light[0] + view[0],
+                    to_light[1] + view[1],
+                    to_light[2] + view[2],
+                ]);
+                highlight = self.specular * dot(n, half).max(0.0).powf(self.shininess);
+            }
+            for i in 0..3 {
+                diffuse[i] += colour[i] * intensity * n_dot_l;
+                specular[i] += colour[i] * intensity * highlight;
+            }
+        }
+        (diffuse, specular)
+    }
+}
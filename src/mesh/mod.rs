@@ -0,0 +1,264 @@
+//! Triangle mesh data which can be loaded from model files and converted into vertices ready for
+//! drawing with the [`clip`] triangle functions
+//!
+//! [`clip`]: ../drawing/clip/index.html
+
+pub mod obj;
+
+use wasm_bindgen::prelude::*;
+
+use crate::lighting::{face_normal, Lighting};
+use crate::types::{ClipVertex, ShadingMode};
+
+/// A reference to the attributes of a single mesh vertex.  Each field is an index into the
+/// corresponding attribute list of the [`Mesh`].
+///
+/// [`Mesh`]: ./struct.Mesh.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VertexRef {
+    pub position: usize,
+    pub tex_coord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+/// A single triangle of a [`Mesh`] with an optional index into the mesh's material list
+///
+/// [`Mesh`]: ./struct.Mesh.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshTriangle {
+    pub vertices: [VertexRef; 3],
+    pub material: Option<usize>,
+}
+
+/// A surface material
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    /// Material name
+    pub name: String,
+
+    /// Diffuse RGB colour with components in the range [0,1]
+    pub diffuse: [f64; 3],
+
+    /// Opacity in the range [0,1]
+    pub alpha: f64,
+
+    /// File name of the diffuse texture map, if any
+    pub diffuse_texture: Option<String>,
+}
+
+impl Material {
+
+    /// Creates a new opaque white Material with no texture
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: [1.0, 1.0, 1.0],
+            alpha: 1.0,
+            diffuse_texture: None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+/// An indexed triangle mesh with positions, texture co-ordinates, normals and materials
+pub struct Mesh {
+    positions: Vec<[f64; 3]>,
+    tex_coords: Vec<[f64; 2]>,
+    normals: Vec<[f64; 3]>,
+    triangles: Vec<MeshTriangle>,
+    materials: Vec<Material>,
+}
+
+#[wasm_bindgen]
+impl Mesh {
+
+    /// Creates a new empty Mesh
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of triangles in the Mesh
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Converts all triangles into a list of [`ClipVertex`] vertices (three per triangle) ready for
+    /// drawing with [`textured_triangles_clipped()`].
+    ///
+    /// Positions are transformed by a 4x4 model-view-projection matrix given as 16 values in
+    /// column-major order (as used by WebGL).  Texture co-ordinates are flipped vertically, as V
+    /// points up in model files but down in a [`Canvas`], and vertex colours are taken from each
+    /// triangle's material.
+    ///
+    /// [`Canvas`]: ../canvas/struct.Canvas.html
+    /// [`ClipVertex`]: ../types/struct.ClipVertex.html
+    /// [`textured_triangles_clipped()`]: ../drawing/clip/fn.textured_triangles_clipped.html
+    ///
+    /// # Arguments:
+    ///
+    ///   - `mvp`: column-major 4x4 transformation matrix
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::mesh::Mesh;
+    ///
+    /// let mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    /// let identity = [
+    ///     1.0, 0.0, 0.0, 0.0,
+    ///     0.0, 1.0, 0.0, 0.0,
+    ///     0.0, 0.0, 1.0, 0.0,
+    ///     0.0, 0.0, 0.0, 1.0,
+    /// ];
+    /// assert_eq!(mesh.to_clip_vertices(&identity).len(), 3);
+    /// ```
+    pub fn to_clip_vertices(&self, mvp: &[f64]) -> Vec<ClipVertex> {
+        let m = |i: usize| mvp.get(i).copied().unwrap_or(0.0);
+        self.triangles
+            .iter()
+            .flat_map(|tri| {
+                let material = tri.material.and_then(|i| self.materials.get(i));
+                tri.vertices.iter().map(move |vr| {
+                    let [x, y, z] = self.positions[vr.position];
+                    let [u, v] = vr.tex_coord.map(|i| self.tex_coords[i]).unwrap_or([0.0, 0.0]);
+                    let mut cv = ClipVertex::new(
+                        m(0) * x + m(4) * y + m(8)  * z + m(12),
+                        m(1) * x + m(5) * y + m(9)  * z + m(13),
+                        m(2) * x + m(6) * y + m(10) * z + m(14),
+                        m(3) * x + m(7) * y + m(11) * z + m(15),
+                        u,
+                        1.0 - v,
+                    );
+                    if let Some(mat) = material {
+                        cv.r = mat.diffuse[0];
+                        cv.g = mat.diffuse[1];
+                        cv.b = mat.diffuse[2];
+                        cv.a = mat.alpha;
+                    }
+                    cv
+                })
+            })
+            .collect()
+    }
+
+    /// Converts all triangles into a list of lit [`ClipVertex`] vertices (three per triangle) ready
+    /// for drawing with [`shaded_triangles_clipped()`] or [`shaded_textured_triangles_clipped()`].
+    ///
+    /// Vertices are converted as in [`to_clip_vertices()`] and each vertex colour is then
+    /// multiplied by the light reaching it.  Lighting is calculated in model space, so light
+    /// positions and directions should be given in the same space as the Mesh's positions.  With
+    /// `ShadingMode::Gouraud`, vertex normals are used when available and the face normal
+    /// otherwise.
+    ///
+    /// [`ClipVertex`]: ../types/struct.ClipVertex.html
+    /// [`shaded_textured_triangles_clipped()`]: ../drawing/clip/fn.shaded_textured_triangles_clipped.html
+    /// [`shaded_triangles_clipped()`]: ../drawing/clip/fn.shaded_triangles_clipped.html
+    /// [`to_clip_vertices()`]: #method.to_clip_vertices
+    ///
+    /// # Arguments:
+    ///
+    ///   - `mvp`: column-major 4x4 transformation matrix
+    ///   - `lighting`: lights used to calculate vertex colours
+    ///   - `shading`: whether to light each face once or each vertex separately
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::lighting::Lighting;
+    /// use rust_wasm_graphics_lib::mesh::Mesh;
+    /// use rust_wasm_graphics_lib::types::ShadingMode;
+    ///
+    /// let mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    /// let identity = [
+    ///     1.0, 0.0, 0.0, 0.0,
+    ///     0.0, 1.0, 0.0, 0.0,
+    ///     0.0, 0.0, 1.0, 0.0,
+    ///     0.0, 0.0, 0.0, 1.0,
+    /// ];
+    ///
+    /// // Light shining at the triangle's front face at 60 degrees
+    /// let mut lighting = Lighting::new(0.0, 0.0, 0.0);
+    /// lighting.add_directional_light(0.0, -(3f64.sqrt()), -1.0, 1.0, 1.0, 1.0);
+    ///
+    /// let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Flat);
+    /// assert!((verts[0].r - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn to_lit_clip_vertices(&self, mvp: &[f64], lighting: &Lighting, shading: ShadingMode) -> Vec<ClipVertex> {
+        let mut verts = self.to_clip_vertices(mvp);
+        self.triangles
+            .iter()
+            .zip(verts.chunks_mut(3))
+            .for_each(|(tri, out)| {
+                let p = [
+                    self.positions[tri.vertices[0].position],
+                    self.positions[tri.vertices[1].position],
+                    self.positions[tri.vertices[2].position],
+                ];
+                let face_n = face_normal(p[0], p[1], p[2]);
+                let light = match shading {
+                    ShadingMode::Flat => {
+                        let centroid = [
+                            (p[0][0] + p[1][0] + p[2][0]) / 3.0,
+                            (p[0][1] + p[1][1] + p[2][1]) / 3.0,
+                            (p[0][2] + p[1][2] + p[2][2]) / 3.0,
+                        ];
+                        let l = lighting.shade(centroid, face_n);
+                        [l, l, l]
+                    },
+                    ShadingMode::Gouraud => {
+                        let shade_vertex = |i: usize| {
+                            let n = tri.vertices[i].normal.map(|n| self.normals[n]).unwrap_or(face_n);
+                            lighting.shade(p[i], n)
+                        };
+                        [shade_vertex(0), shade_vertex(1), shade_vertex(2)]
+                    },
+                };
+                out.iter_mut().zip(light.iter()).for_each(|(v, (diffuse, specular))| {
+                    v.r = (v.r * diffuse[0] + specular[0]).clamp(0.0, 1.0);
+                    v.g = (v.g * diffuse[1] + specular[1]).clamp(0.0, 1.0);
+                    v.b = (v.b * diffuse[2] + specular[2]).clamp(0.0, 1.0);
+                });
+            });
+        verts
+    }
+}
+
+impl Mesh {
+
+    /// Returns all vertex positions
+    pub fn positions(&self) -> &Vec<[f64; 3]> {
+        &self.positions
+    }
+
+    /// Returns all vertex texture co-ordinates
+    pub fn tex_coords(&self) -> &Vec<[f64; 2]> {
+        &self.tex_coords
+    }
+
+    /// Returns all vertex normals
+    pub fn normals(&self) -> &Vec<[f64; 3]> {
+        &self.normals
+    }
+
+    /// Returns all triangles
+    pub fn triangles(&self) -> &Vec<MeshTriangle> {
+        &self.triangles
+    }
+
+    /// Returns all materials referenced by the Mesh's triangles
+    pub fn materials(&self) -> &Vec<Material> {
+        &self.materials
+    }
+
+    /// Returns the index of a named material, adding a new default material if it does not exist
+    fn material_index(&mut self, name: &str) -> usize {
+        match self.materials.iter().position(|m| m.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.materials.push(Material::new(name));
+                self.materials.len() - 1
+            },
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Wavefront OBJ and MTL loading
+//!
+//! Supports vertex positions (`v`), texture co-ordinates (`vt`), normals (`vn`) and faces (`f`)
+//! with positive or negative (relative) indices.  Polygon faces are triangulated as a fan around
+//! their first vertex.  Materials are selected with `usemtl` and loaded separately from MTL files
+//! with [`Mesh::load_mtl()`], as only the diffuse colour (`Kd`), opacity (`d` or `Tr`) and diffuse
+//! texture (`map_Kd`) are used.  All other statements are ignored.
+//!
+//! [`Mesh::load_mtl()`]: ../struct.Mesh.html#method.load_mtl
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use super::{Material, Mesh, MeshTriangle, VertexRef};
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// An error encountered while parsing an OBJ or MTL file
+pub struct ObjError {
+    line: usize,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl ObjError {
+
+    /// Returns the 1-based line number on which the error occurred
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns a description of the error
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl ObjError {
+    fn new(line: usize, message: &str) -> Self {
+        Self { line, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Parses between `min` and `max` floating point values from a statement's arguments
+fn parse_floats(args: &[&str], min: usize, max: usize, line: usize) -> Result<Vec<f64>, ObjError> {
+    if args.len() < min || args.len() > max {
+        return Err(ObjError::new(line, &format!("expected {} to {} values", min, max)));
+    }
+    args.iter()
+        .map(|x| x.parse::<f64>().map_err(|_| ObjError::new(line, &format!("invalid number '{}'", x))))
+        .collect()
+}
+
+/// Resolves a 1-based (or negative, relative) OBJ index into a 0-based index into a list of
+/// length `len`
+fn resolve_index(idx: &str, len: usize, line: usize) -> Result<usize, ObjError> {
+    let i = idx
+        .parse::<isize>()
+        .map_err(|_| ObjError::new(line, &format!("invalid index '{}'", idx)))?;
+    let resolved = if i < 0 { len as isize + i } else { i - 1 };
+    if i == 0 || resolved < 0 || resolved >= len as isize {
+        return Err(ObjError::new(line, &format!("index {} out of range", i)));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parses a single face vertex of the form `v`, `v/vt`, `v//vn` or `v/vt/vn`
+fn parse_vertex_ref(s: &str, mesh: &Mesh, line: usize) -> Result<VertexRef, ObjError> {
+    let mut parts = s.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), mesh.positions.len(), line)?;
+    let tex_coord = match parts.next() {
+        Some(x) if !x.is_empty() => Some(resolve_index(x, mesh.tex_coords.len(), line)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(x) if !x.is_empty() => Some(resolve_index(x, mesh.normals.len(), line)?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(ObjError::new(line, &format!("invalid face vertex '{}'", s)));
+    }
+    Ok(VertexRef { position, tex_coord, normal })
+}
+
+/// Splits a line into a statement keyword and its arguments, ignoring comments
+fn tokenise(line: &str) -> Option<(&str, Vec<&str>)> {
+    let line = line.split('#').next().unwrap_or("");
+    let mut tokens = line.split_whitespace();
+    tokens.next().map(|kw| (kw, tokens.collect()))
+}
+
+/// Returns the file names of all MTL libraries referenced by `mtllib` statements in OBJ source
+/// text
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::mesh::obj::mtl_libraries;
+///
+/// assert_eq!(mtl_libraries("mtllib a.mtl b.mtl\nv 0 0 0\n"), vec!["a.mtl", "b.mtl"]);
+/// ```
+pub fn mtl_libraries(src: &str) -> Vec<String> {
+    src.lines()
+        .filter_map(tokenise)
+        .filter(|(kw, _)| *kw == "mtllib")
+        .flat_map(|(_, args)| args.into_iter().map(|x| x.to_string()))
+        .collect()
+}
+
+#[wasm_bindgen]
+impl Mesh {
+
+    /// Parses Wavefront OBJ source text into a new Mesh.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: OBJ file contents
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::mesh::Mesh;
+    ///
+    /// // A quad, which will be split into two triangles
+    /// let mesh = Mesh::from_obj("
+    ///     v 0 0 0
+    ///     v 1 0 0
+    ///     v 1 1 0
+    ///     v 0 1 0
+    ///     vt 0 0
+    ///     vt 1 1
+    ///     f -4/1 -3/1 -2/2 -1/2
+    /// ").unwrap();
+    /// assert_eq!(mesh.triangle_count(), 2);
+    /// ```
+    pub fn from_obj(src: &str) -> Result<Mesh, ObjError> {
+        let mut mesh = Mesh::new();
+        let mut material = None;
+        for (line_idx, line) in src.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let (kw, args) = match tokenise(line) {
+                Some(x) => x,
+                None => continue,
+            };
+            match kw {
+                "v" => {
+                    let v = parse_floats(&args, 3, 4, line_no)?;
+                    mesh.positions.push([v[0], v[1], v[2]]);
+                },
+                "vt" => {
+                    let v = parse_floats(&args, 1, 3, line_no)?;
+                    mesh.tex_coords.push([v[0], v.get(1).copied().unwrap_or(0.0)]);
+                },
+                "vn" => {
+                    let v = parse_floats(&args, 3, 3, line_no)?;
+                    mesh.normals.push([v[0], v[1], v[2]]);
+                },
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(ObjError::new(line_no, "face has fewer than 3 vertices"));
+                    }
+                    let verts = args
+                        .iter()
+                        .map(|x| parse_vertex_ref(x, &mesh, line_no))
+                        .collect::<Result<Vec<VertexRef>, ObjError>>()?;
+
+                    // Triangulate the polygon as a fan around the first vertex
+                    for (curr, next) in verts.iter().skip(1).zip(verts.iter().skip(2)) {
+                        mesh.triangles.push(MeshTriangle {
+                            vertices: [verts[0], *curr, *next],
+                            material,
+                        });
+                    }
+                },
+                "usemtl" => {
+                    let name = args.first().ok_or_else(|| ObjError::new(line_no, "missing material name"))?;
+                    material = Some(mesh.material_index(name));
+                },
+                _ => {},
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Parses MTL source text and applies all materials defined within it to the Mesh.
+    ///
+    /// Materials which are not already referenced by the Mesh are added to its material list.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `src`: MTL file contents
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::mesh::Mesh;
+    ///
+    /// let mut mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n").unwrap();
+    /// mesh.load_mtl("newmtl red\nKd 1 0 0\nmap_Kd red.png\n").unwrap();
+    ///
+    /// let mat = &mesh.materials()[0];
+    /// assert_eq!(mat.diffuse, [1.0, 0.0, 0.0]);
+    /// assert_eq!(mat.diffuse_texture, Some(String::from("red.png")));
+    /// ```
+    pub fn load_mtl(&mut self, src: &str) -> Result<(), ObjError> {
+        let mut current: Option<usize> = None;
+        for (line_idx, line) in src.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let (kw, args) = match tokenise(line) {
+                Some(x) => x,
+                None => continue,
+            };
+            if kw == "newmtl" {
+                let name = args.first().ok_or_else(|| ObjError::new(line_no, "missing material name"))?;
+                let idx = self.material_index(name);
+                self.materials[idx] = Material::new(name);
+                current = Some(idx);
+                continue;
+            }
+            if !["Kd", "d", "Tr", "map_Kd"].contains(&kw) {
+                continue;
+            }
+            let mat = match current {
+                Some(i) => &mut self.materials[i],
+                None => return Err(ObjError::new(line_no, &format!("'{}' before 'newmtl'", kw))),
+            };
+            match kw {
+                "Kd" => {
+                    let v = parse_floats(&args, 3, 3, line_no)?;
+                    mat.diffuse = [v[0], v[1], v[2]];
+                },
+                "d" => mat.alpha = parse_floats(&args, 1, 1, line_no)?[0],
+                "Tr" => mat.alpha = 1.0 - parse_floats(&args, 1, 1, line_no)?[0],
+                _ => {
+
+                    // Texture options may precede the file name, which is always the last argument
+                    let name = args.last().ok_or_else(|| ObjError::new(line_no, "missing texture name"))?;
+                    mat.diffuse_texture = Some(name.to_string());
+                },
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+//! Sprite atlases: a single [`Canvas`] containing many named frames and animations
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::json::{self, JsonValue};
+
+/// Frame duration in milliseconds used when an animation description doesn't specify one
+const DEFAULT_FRAME_DURATION: f64 = 100.0;
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+/// An error encountered while loading a sprite atlas description
+pub struct AtlasError {
+    message: String,
+}
+
+#[wasm_bindgen]
+impl AtlasError {
+
+    /// Returns a description of the error
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl AtlasError {
+    fn new(message: &str) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// A named rectangle within a [`SpriteAtlas`].
+///
+/// Frames which were trimmed when packing are placed at (`offset_x`,`offset_y`) within the
+/// original, untrimmed sprite.  The pivot point is relative to the top-left corner of the
+/// untrimmed sprite.
+///
+/// [`SpriteAtlas`]: ./struct.SpriteAtlas.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteFrame {
+    pub name: String,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub offset_x: isize,
+    pub offset_y: isize,
+    pub pivot_x: f64,
+    pub pivot_y: f64,
+}
+
+/// Returns the frame shown at a given time in milliseconds from a sequence of frames and their
+/// durations.  If `looped` is set the sequence repeats, otherwise the last frame is held.
+///
+/// # Example:
+///
+/// ```
+/// use rust_wasm_graphics_lib::sprite::animation_frame;
+///
+/// let frames = [('a', 100.0), ('b', 50.0)];
+/// assert_eq!(animation_frame(&frames, 120.0, true), Some('b'));
+/// assert_eq!(animation_frame(&frames, 160.0, true), Some('a'));
+/// assert_eq!(animation_frame(&frames, 160.0, false), Some('b'));
+/// ```
+pub fn animation_frame<T: Copy>(frames: &[(T, f64)], time: f64, looped: bool) -> Option<T> {
+    let total: f64 = frames.iter().map(|f| f.1).sum();
+    if total <= 0.0 {
+        return frames.first().map(|f| f.0);
+    }
+    let mut t = if looped { time.rem_euclid(total) } else { time.max(0.0) };
+    for (frame, duration) in frames {
+        if t < *duration {
+            return Some(*frame);
+        }
+        t -= duration;
+    }
+    frames.last().map(|f| f.0)
+}
+
+/// A named sequence of frames, each shown for a duration in milliseconds
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteAnimation {
+    pub name: String,
+
+    /// Frame indices into the atlas's frame list with durations in milliseconds
+    pub frames: Vec<(usize, f64)>,
+}
+
+impl SpriteAnimation {
+
+    /// Returns the total duration of the animation in milliseconds
+    pub fn duration(&self) -> f64 {
+        self.frames.iter().map(|f| f.1).sum()
+    }
+
+    /// Returns the frame index shown at a given time in milliseconds.  If `looped` is set the
+    /// animation repeats, otherwise the last frame is held.
+    pub fn frame_at(&self, time: f64, looped: bool) -> Option<usize> {
+        animation_frame(&self.frames, time, looped)
+    }
+}
+
+#[wasm_bindgen]
+/// A [`Canvas`] containing many sprites, with a table of named frames and animations
+///
+/// [`Canvas`]: ../canvas/struct.Canvas.html
+pub struct SpriteAtlas {
+    canvas: Canvas,
+    frames: Vec<SpriteFrame>,
+    animations: Vec<SpriteAnimation>,
+}
+
+#[wasm_bindgen]
+impl SpriteAtlas {
+
+    /// Creates a new SpriteAtlas from a Canvas with no frames or animations
+    pub fn new(canvas: Canvas) -> Self {
+        Self {
+            canvas,
+            frames: vec![],
+            animations: vec![],
+        }
+    }
+
+    /// Creates a new SpriteAtlas from a Canvas and a JSON frame table.
+    ///
+    /// The JSON format follows TexturePacker's "hash" and "array" exports: `frames` is either an
+    /// object keyed by frame name or an array of objects with a `filename`.  Each frame has a
+    /// `frame` rectangle (`x`, `y`, `w`, `h`) and optionally a normalised `pivot` (`x`, `y`),
+    /// `spriteSourceSize` and `sourceSize` for trimmed frames, and a `duration` in milliseconds.
+    /// Rotated frames are not supported.
+    ///
+    /// An optional `animations` object maps animation names to arrays of frames, where each frame
+    /// is either a frame name (using the frame's `duration`, or 100ms) or an object with `frame`
+    /// and `duration` members.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::sprite::SpriteAtlas;
+    ///
+    /// let atlas = SpriteAtlas::from_json(Canvas::new(32, 16), r#"{
+    ///     "frames": {
+    ///         "walk0.png": { "frame": { "x": 0,  "y": 0, "w": 16, "h": 16 }, "pivot": { "x": 0.5, "y": 1 } },
+    ///         "walk1.png": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "duration": 150 }
+    ///     },
+    ///     "animations": {
+    ///         "walk": ["walk0.png", "walk1.png"]
+    ///     }
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(atlas.frame("walk0.png").unwrap().pivot_x, 8.0);
+    /// assert_eq!(atlas.animation_duration("walk"), 250.0);
+    /// ```
+    pub fn from_json(canvas: Canvas, src: &str) -> Result<SpriteAtlas, AtlasError> {
+        let root = json::parse(src).map_err(|e| AtlasError::new(&format!("invalid JSON: {}", e)))?;
+        let mut atlas = SpriteAtlas::new(canvas);
+        let mut durations: Vec<Option<f64>> = vec![];
+
+        let frames: Vec<(String, &JsonValue)> = match root.get("frames") {
+            Some(JsonValue::Object(members)) => members.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Some(JsonValue::Array(items)) => items
+                .iter()
+                .map(|v| {
+                    v.get("filename")
+                        .and_then(|n| n.as_str())
+                        .map(|n| (n.to_string(), v))
+                        .ok_or_else(|| AtlasError::new("frame is missing 'filename'"))
+                })
+                .collect::<Result<Vec<_>, AtlasError>>()?,
+            _ => return Err(AtlasError::new("missing 'frames' object or array")),
+        };
+
+        for (name, desc) in frames {
+            let err = |field: &str| AtlasError::new(&format!("frame '{}': missing or invalid '{}'", name, field));
+            let num = |v: &JsonValue, key: &str| v.get(key).and_then(|x| x.as_f64());
+            if desc.get("rotated").and_then(|x| x.as_bool()) == Some(true) {
+                return Err(AtlasError::new(&format!("frame '{}': rotated frames are not supported", name)));
+            }
+            let rect = desc.get("frame").ok_or_else(|| err("frame"))?;
+            let x = num(rect, "x").ok_or_else(|| err("frame.x"))?;
+            let y = num(rect, "y").ok_or_else(|| err("frame.y"))?;
+            let w = num(rect, "w").ok_or_else(|| err("frame.w"))?;
+            let h = num(rect, "h").ok_or_else(|| err("frame.h"))?;
+            if x < 0.0 || y < 0.0 || w < 0.0 || h < 0.0 {
+                return Err(err("frame"));
+            }
+
+            // Trimmed frames are offset within their original size
+            let (offset_x, offset_y) = match desc.get("spriteSourceSize") {
+                Some(sss) => (num(sss, "x").unwrap_or(0.0), num(sss, "y").unwrap_or(0.0)),
+                None => (0.0, 0.0),
+            };
+            let (source_w, source_h) = match desc.get("sourceSize") {
+                Some(ss) => (num(ss, "w").unwrap_or(w), num(ss, "h").unwrap_or(h)),
+                None => (w, h),
+            };
+            let (pivot_x, pivot_y) = match desc.get("pivot") {
+                Some(p) => (num(p, "x").unwrap_or(0.0) * source_w, num(p, "y").unwrap_or(0.0) * source_h),
+                None => (0.0, 0.0),
+            };
+
+            atlas.insert_frame(SpriteFrame {
+                name: name.clone(),
+                x: x as usize,
+                y: y as usize,
+                width: w as usize,
+                height: h as usize,
+                offset_x: offset_x as isize,
+                offset_y: offset_y as isize,
+                pivot_x,
+                pivot_y,
+            });
+            let idx = atlas.frame_index(&name).unwrap_or(0);
+            durations.resize(atlas.frames.len(), None);
+            durations[idx] = num(desc, "duration");
+        }
+
+        if let Some(anims) = root.get("animations") {
+            let anims = anims.as_object().ok_or_else(|| AtlasError::new("'animations' must be an object"))?;
+            for (anim_name, frames) in anims {
+                let frames = frames
+                    .as_array()
+                    .ok_or_else(|| AtlasError::new(&format!("animation '{}' must be an array", anim_name)))?;
+                for f in frames {
+                    let (frame_name, duration) = match f {
+                        JsonValue::String(s) => (s.as_str(), None),
+                        _ => (
+                            f.get("frame").and_then(|x| x.as_str()).unwrap_or(""),
+                            f.get("duration").and_then(|x| x.as_f64()),
+                        ),
+                    };
+                    let idx = atlas.frame_index(frame_name).ok_or_else(|| {
+                        AtlasError::new(&format!("animation '{}': unknown frame '{}'", anim_name, frame_name))
+                    })?;
+                    let duration = duration.or(durations[idx]).unwrap_or(DEFAULT_FRAME_DURATION);
+                    atlas.add_animation_frame(anim_name, frame_name, duration);
+                }
+            }
+        }
+
+        Ok(atlas)
+    }
+
+    /// Adds a named frame, replacing any existing frame with the same name
+    ///
+    /// # Arguments:
+    ///
+    ///   - `name`: frame name
+    ///   - `x`: X co-ordinate of the top-left corner of the frame within the atlas
+    ///   - `y`: Y co-ordinate of the top-left corner of the frame within the atlas
+    ///   - `width`: frame width
+    ///   - `height`: frame height
+    ///   - `pivot_x`: X co-ordinate of the pivot point relative to the frame's top-left corner
+    ///   - `pivot_y`: Y co-ordinate of the pivot point relative to the frame's top-left corner
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_frame(
+        &mut self,
+        name: &str,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        pivot_x: f64,
+        pivot_y: f64,
+    ) {
+        self.insert_frame(SpriteFrame {
+            name: name.to_string(),
+            x,
+            y,
+            width,
+            height,
+            offset_x: 0,
+            offset_y: 0,
+            pivot_x,
+            pivot_y,
+        });
+    }
+
+    /// Appends a frame to a named animation, creating the animation if it does not exist.
+    /// Returns `false` if no frame called `frame_name` exists.
+    pub fn add_animation_frame(&mut self, name: &str, frame_name: &str, duration: f64) -> bool {
+        let frame_idx = match self.frame_index(frame_name) {
+            Some(i) => i,
+            None => return false,
+        };
+        match self.animations.iter_mut().find(|a| a.name == name) {
+            Some(anim) => anim.frames.push((frame_idx, duration)),
+            None => self.animations.push(SpriteAnimation {
+                name: name.to_string(),
+                frames: vec![(frame_idx, duration)],
+            }),
+        }
+        true
+    }
+
+    /// Returns the number of frames in the atlas
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns the total duration of a named animation in milliseconds, or 0 if it does not exist
+    pub fn animation_duration(&self, name: &str) -> f64 {
+        self.animation(name).map(|a| a.duration()).unwrap_or(0.0)
+    }
+
+    /// Draws a named frame to a Canvas with its pivot point at (x,y).  Returns `false` if the
+    /// frame does not exist.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::sprite::SpriteAtlas;
+    ///
+    /// let mut atlas = SpriteAtlas::new(Canvas::new(32, 16));
+    /// atlas.add_frame("idle", 0, 0, 16, 16, 8.0, 16.0);
+    ///
+    /// // Draw with the frame's bottom-centre at (64, 64)
+    /// let mut dst = Canvas::new(128, 128);
+    /// assert!(atlas.draw_frame(&mut dst, "idle", 64, 64, false));
+    /// ```
+    pub fn draw_frame(&self, dst: &mut Canvas, name: &str, x: isize, y: isize, flip_h: bool) -> bool {
+        match self.frame_index(name) {
+            Some(idx) => {
+                self.draw_frame_index(dst, idx, x, y, flip_h);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Draws the frame of a named animation shown at a given time in milliseconds, with its pivot
+    /// point at (x,y).  Returns `false` if the animation does not exist or has no frames.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::sprite::SpriteAtlas;
+    ///
+    /// let mut atlas = SpriteAtlas::new(Canvas::new(32, 16));
+    /// atlas.add_frame("walk0", 0, 0, 16, 16, 8.0, 16.0);
+    /// atlas.add_frame("walk1", 16, 0, 16, 16, 8.0, 16.0);
+    /// atlas.add_animation_frame("walk", "walk0", 100.0);
+    /// atlas.add_animation_frame("walk", "walk1", 150.0);
+    ///
+    /// let mut dst = Canvas::new(128, 128);
+    /// assert!(atlas.draw_animation(&mut dst, "walk", 120.0, 64, 64, true, false));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_animation(
+        &self,
+        dst: &mut Canvas,
+        name: &str,
+        time: f64,
+        x: isize,
+        y: isize,
+        looped: bool,
+        flip_h: bool,
+    ) -> bool {
+        match self.animation(name).and_then(|a| a.frame_at(time, looped)) {
+            Some(idx) => {
+                self.draw_frame_index(dst, idx, x, y, flip_h);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl SpriteAtlas {
+
+    /// Returns the atlas Canvas
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Returns a named frame
+    pub fn frame(&self, name: &str) -> Option<&SpriteFrame> {
+        self.frame_index(name).map(|i| &self.frames[i])
+    }
+
+    /// Returns all frames
+    pub fn frames(&self) -> &Vec<SpriteFrame> {
+        &self.frames
+    }
+
+    /// Returns a named animation
+    pub fn animation(&self, name: &str) -> Option<&SpriteAnimation> {
+        self.animations.iter().find(|a| a.name == name)
+    }
+
+    fn frame_index(&self, name: &str) -> Option<usize> {
+        self.frames.iter().position(|f| f.name == name)
+    }
+
+    fn insert_frame(&mut self, frame: SpriteFrame) {
+        match self.frame_index(&frame.name) {
+            Some(idx) => self.frames[idx] = frame,
+            None => self.frames.push(frame),
+        }
+    }
+
+    /// Draws a frame by index with its pivot point at (x,y)
+    fn draw_frame_index(&self, dst: &mut Canvas, idx: usize, x: isize, y: isize, flip_h: bool) {
+        let f = &self.frames[idx];
+
+        // When flipped, the trimmed offset is mirrored around the pivot point
+        let offset_x = if flip_h {
+            (2.0 * f.pivot_x) as isize - f.offset_x - f.width as isize
+        } else {
+            f.offset_x
+        };
+        let dx = x - (f.pivot_x + 0.5).floor() as isize + offset_x;
+        let dy = y - (f.pivot_y + 0.5).floor() as isize + f.offset_y;
+        dst.draw_canvas_region(&self.canvas, f.x, f.y, f.width, f.height, dx, dy, flip_h, false);
+    }
+}
@@ -0,0 +1,335 @@
+//! Layered tile maps drawn from a tileset [`Canvas`]
+//!
+//! Cells store tile values using the same encoding as [Tiled](https://www.mapeditor.org/) layer
+//! data: 0 is an empty cell, other values are 1-based indices into the tileset (numbered left to
+//! right, top to bottom) with [`TILE_FLIP_H`], [`TILE_FLIP_V`] and [`TILE_FLIP_D`] set for flipped
+//! and rotated tiles.  The diagonal flip swaps a tile's X and Y axes and is applied before the
+//! other flips, so e.g. a tile rotated 90 degrees clockwise has `TILE_FLIP_D | TILE_FLIP_H` set.
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+//! [`TILE_FLIP_H`]: ./constant.TILE_FLIP_H.html
+//! [`TILE_FLIP_V`]: ./constant.TILE_FLIP_V.html
+//! [`TILE_FLIP_D`]: ./constant.TILE_FLIP_D.html
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::{BlitRegion, Canvas};
+use crate::sprite::animation_frame;
+
+/// Tile value flag set when a tile is flipped horizontally
+pub const TILE_FLIP_H: u32 = 0x8000_0000;
+
+/// Tile value flag set when a tile is flipped vertically
+pub const TILE_FLIP_V: u32 = 0x4000_0000;
+
+/// Tile value flag set when a tile is flipped diagonally (its X and Y axes are swapped)
+pub const TILE_FLIP_D: u32 = 0x2000_0000;
+
+/// Mask of the tileset index part of a tile value
+const TILE_INDEX_MASK: u32 = !(TILE_FLIP_H | TILE_FLIP_V | TILE_FLIP_D);
+
+#[wasm_bindgen]
+/// A grid of tiles in one or more layers, drawn from a tileset [`Canvas`] of fixed size tiles
+///
+/// [`Canvas`]: ../canvas/struct.Canvas.html
+pub struct TileMap {
+    tileset: Canvas,
+    tile_width: usize,
+    tile_height: usize,
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u32>>,
+
+    /// Animated tiles: a tileset index followed by the indices and durations (ms) it cycles through
+    animations: Vec<(u32, Vec<(u32, f64)>)>,
+}
+
+#[wasm_bindgen]
+impl TileMap {
+
+    /// Creates a new TileMap with a single empty layer
+    ///
+    /// # Arguments:
+    ///
+    ///   - `tileset`: Canvas containing all tiles in a grid
+    ///   - `tile_width`: width of each tile in pixels
+    ///   - `tile_height`: height of each tile in pixels
+    ///   - `width`: map width in tiles
+    ///   - `height`: map height in tiles
+    pub fn new(tileset: Canvas, tile_width: usize, tile_height: usize, width: usize, height: usize) -> Self {
+        Self {
+            tileset,
+            tile_width: tile_width.max(1),
+            tile_height: tile_height.max(1),
+            width,
+            height,
+            layers: vec![vec![0; width * height]],
+            animations: vec![],
+        }
+    }
+
+    /// Returns the map width in tiles
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the map height in tiles
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the width of each tile in pixels
+    pub fn tile_width(&self) -> usize {
+        self.tile_width
+    }
+
+    /// Returns the height of each tile in pixels
+    pub fn tile_height(&self) -> usize {
+        self.tile_height
+    }
+
+    /// Returns the number of layers
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Adds a new empty layer on top of all existing layers and returns its index
+    pub fn add_layer(&mut self) -> usize {
+        self.layers.push(vec![0; self.width * self.height]);
+        self.layers.len() - 1
+    }
+
+    /// Sets a single cell to a 0-based tileset index.  Returns `false` if the layer or cell does
+    /// not exist, or `tile` is too large to be encoded.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `layer`: layer index
+    ///   - `x`: cell X co-ordinate
+    ///   - `y`: cell Y co-ordinate
+    ///   - `tile`: 0-based tileset index
+    ///   - `flip_h`: flip the tile horizontally
+    ///   - `flip_v`: flip the tile vertically
+    ///   - `flip_d`: flip the tile diagonally, swapping its X and Y axes before any other flip
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_tile(
+        &mut self,
+        layer: usize,
+        x: usize,
+        y: usize,
+        tile: u32,
+        flip_h: bool,
+        flip_v: bool,
+        flip_d: bool,
+    ) -> bool {
+        let mut value = match tile.checked_add(1) {
+            Some(v) if v & TILE_INDEX_MASK == v => v,
+            _ => return false,
+        };
+        if flip_h {
+            value |= TILE_FLIP_H;
+        }
+        if flip_v {
+            value |= TILE_FLIP_V;
+        }
+        if flip_d {
+            value |= TILE_FLIP_D;
+        }
+        self.set_tile_value(layer, x, y, value)
+    }
+
+    /// Empties a single cell.  Returns `false` if the layer or cell does not exist.
+    pub fn clear_tile(&mut self, layer: usize, x: usize, y: usize) -> bool {
+        self.set_tile_value(layer, x, y, 0)
+    }
+
+    /// Returns the encoded tile value of a cell, or 0 if the layer or cell does not exist
+    pub fn tile_value(&self, layer: usize, x: usize, y: usize) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.layers.get(layer).map(|l| l[y * self.width + x]).unwrap_or(0)
+    }
+
+    /// Replaces all cells of a layer with encoded tile values in row-major order (e.g. layer data
+    /// exported from Tiled).  Returns `false` if the layer does not exist or `data` is the wrong
+    /// length.
+    pub fn load_layer(&mut self, layer: usize, data: Vec<u32>) -> bool {
+        if data.len() != self.width * self.height {
+            return false;
+        }
+        match self.layers.get_mut(layer) {
+            Some(l) => {
+                *l = data;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Appends a frame to the animation of a tile.  Wherever `tile` appears in the map, the
+    /// current animation frame is drawn instead.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `tile`: 0-based tileset index of the animated tile
+    ///   - `frame_tile`: 0-based tileset index to draw for this frame
+    ///   - `duration`: frame duration in milliseconds
+    pub fn add_tile_animation_frame(&mut self, tile: u32, frame_tile: u32, duration: f64) {
+        match self.animations.iter_mut().find(|a| a.0 == tile) {
+            Some(anim) => anim.1.push((frame_tile, duration)),
+            None => self.animations.push((tile, vec![(frame_tile, duration)])),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+
+    /// Draws all layers of a TileMap in order, with the camera's top-left corner at
+    /// (camera_x,camera_y) in map pixel co-ordinates.  Only tiles visible on this Canvas are drawn.
+    ///
+    /// # Arguments:
+    ///
+    ///   - `map`: TileMap to draw
+    ///   - `camera_x`: X co-ordinate of the map pixel drawn at the left edge of this Canvas
+    ///   - `camera_y`: Y co-ordinate of the map pixel drawn at the top edge of this Canvas
+    ///   - `time`: time in milliseconds used to select frames of animated tiles
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::canvas::Canvas;
+    /// use rust_wasm_graphics_lib::tilemap::TileMap;
+    ///
+    /// // Tileset of four 16x16 tiles and a 100x100 tile map
+    /// let mut map = TileMap::new(Canvas::new(64, 16), 16, 16, 100, 100);
+    /// map.set_tile(0, 10, 10, 2, false, false, false);
+    ///
+    /// // Tile 3 is a water tile animating through tiles 3 and 1
+    /// map.add_tile_animation_frame(3, 3, 250.0);
+    /// map.add_tile_animation_frame(3, 1, 250.0);
+    ///
+    /// let mut dst = Canvas::new(320, 240);
+    /// dst.draw_tilemap(&map, 100, 120, 0.0);
+    /// ```
+    pub fn draw_tilemap(&mut self, map: &TileMap, camera_x: isize, camera_y: isize, time: f64) {
+        let frames = map.animation_frames(time);
+        for layer in 0..map.layers.len() {
+            map.draw_layer_with_frames(self, layer, camera_x, camera_y, &frames);
+        }
+    }
+
+    /// Draws a single layer of a TileMap, allowing other drawing (e.g. sprites) between layers.
+    /// See [`draw_tilemap()`].
+    ///
+    /// [`draw_tilemap()`]: #method.draw_tilemap
+    pub fn draw_tilemap_layer(&mut self, map: &TileMap, layer: usize, camera_x: isize, camera_y: isize, time: f64) {
+        if layer < map.layers.len() {
+            let frames = map.animation_frames(time);
+            map.draw_layer_with_frames(self, layer, camera_x, camera_y, &frames);
+        }
+    }
+}
+
+impl TileMap {
+
+    /// Returns the tileset Canvas
+    pub fn tileset(&self) -> &Canvas {
+        &self.tileset
+    }
+
+    /// Returns the cells of a layer as encoded tile values in row-major order
+    pub fn layer(&self, layer: usize) -> Option<&Vec<u32>> {
+        self.layers.get(layer)
+    }
+
+    fn set_tile_value(&mut self, layer: usize, x: usize, y: usize, value: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        match self.layers.get_mut(layer) {
+            Some(l) => {
+                l[y * self.width + x] = value;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the number of tiles in each row of the tileset
+    fn tileset_columns(&self) -> usize {
+        (self.tileset.width() / self.tile_width).max(1)
+    }
+
+    /// Returns the current frame of each animated tile at a given time
+    fn animation_frames(&self, time: f64) -> Vec<(u32, u32)> {
+        self.animations
+            .iter()
+            .filter_map(|(tile, frames)| animation_frame(frames, time, true).map(|f| (*tile, f)))
+            .collect()
+    }
+
+    fn draw_layer_with_frames(
+        &self,
+        dst: &mut Canvas,
+        layer: usize,
+        camera_x: isize,
+        camera_y: isize,
+        frames: &[(u32, u32)],
+    ) {
+        let cells = &self.layers[layer];
+        let (tw, th) = (self.tile_width as isize, self.tile_height as isize);
+
+        // Range of cells overlapping the destination Canvas
+        let range = |camera: isize, tile: isize, dst_size: usize, map_size: usize| {
+            let start = camera.div_euclid(tile).max(0) as usize;
+            let end = (camera + dst_size as isize + tile - 1).div_euclid(tile).max(0) as usize;
+            (start, end.min(map_size))
+        };
+        let (x_start, x_end) = range(camera_x, tw, dst.width(), self.width);
+        let (y_start, y_end) = range(camera_y, th, dst.height(), self.height);
+
+        let columns = self.tileset_columns();
+        for ty in y_start..y_end {
+            for tx in x_start..x_end {
+                let value = cells[ty * self.width + tx];
+                if value & TILE_INDEX_MASK == 0 {
+                    continue;
+                }
+                let mut tile = (value & TILE_INDEX_MASK) - 1;
+                if let Some(f) = frames.iter().find(|f| f.0 == tile) {
+                    tile = f.1;
+                }
+                let sx = (tile as usize % columns) * self.tile_width;
+                let sy = (tile as usize / columns) * self.tile_height;
+                let (x, y) = (tx as isize * tw - camera_x, ty as isize * th - camera_y);
+                let (flip_h, flip_v) = (value & TILE_FLIP_H != 0, value & TILE_FLIP_V != 0);
+                if value & TILE_FLIP_D == 0 {
+                    dst.draw_canvas_region(&self.tileset, sx, sy, self.tile_width, self.tile_height, x, y, flip_h, flip_v);
+                } else if let Some(region) =
+                    BlitRegion::transposed(dst, &self.tileset, sx, sy, self.tile_width, self.tile_height, x, y)
+                {
+                    self.draw_tile_diagonal(dst, &region, x, y, flip_h, flip_v);
+                }
+            }
+        }
+    }
+
+    /// Draws a tile region clipped by `BlitRegion::transposed()` with its X and Y axes swapped
+    /// before flipping it, with its top-left corner at (x,y).  Fully transparent pixels are not
+    /// drawn.
+    fn draw_tile_diagonal(&self, dst: &mut Canvas, region: &BlitRegion, x: isize, y: isize, flip_h: bool, flip_v: bool) {
+        for oy in region.off_y.0..region.off_y.1 {
+            for ox in region.off_x.0..region.off_x.1 {
+                let src_row = if flip_h { region.height - 1 - ox } else { ox };
+                let src_col = if flip_v { region.width - 1 - oy } else { oy };
+                let px = self.tileset.buffer()[self.tileset.buffer_index(region.src_x + src_col, region.src_y + src_row)];
+                if px >> 24 > 0 {
+                    let idx = dst.buffer_index((x + ox as isize) as usize, (y + oy as isize) as usize);
+                    dst.buffer_mut()[idx] = px;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! 2D affine transformations applied to vector drawing functions
+//!
+//! Each [`Canvas`] holds a current [`Transform2D`] which maps the co-ordinates passed to
+//! [`line()`], [`rect()`], [`fill_rect()`], [`polygon()`], [`fill_polygon()`] and
+//! [`fill_triangle()`] to pixel co-ordinates.  The current transform can be saved and restored
+//! with [`Canvas::save()`] and [`Canvas::restore()`].
+//!
+//! [`Canvas`]: ../canvas/struct.Canvas.html
+//! [`Canvas::restore()`]: ../canvas/struct.Canvas.html#method.restore
+//! [`Canvas::save()`]: ../canvas/struct.Canvas.html#method.save
+//! [`Transform2D`]: ./struct.Transform2D.html
+//! [`fill_polygon()`]: ../drawing/shape/fn.fill_polygon.html
+//! [`fill_rect()`]: ../drawing/rect/fn.fill_rect.html
+//! [`fill_triangle()`]: ../drawing/shape/fn.fill_triangle.html
+//! [`line()`]: ../drawing/lines/fn.line.html
+//! [`polygon()`]: ../drawing/shape/fn.polygon.html
+//! [`rect()`]: ../drawing/rect/fn.rect.html
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A 2D affine transformation stored as a 3x2 matrix.
+///
+/// A point (x,y) is transformed to (a*x + c*y + e, b*x + d*y + f), matching the HTML canvas
+/// `setTransform()` parameter order.
+pub struct Transform2D {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[wasm_bindgen]
+impl Transform2D {
+
+    /// Creates a new Transform2D from its six matrix components
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Creates a new identity Transform2D which leaves all points unchanged
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// Creates a new Transform2D which translates points by (tx,ty)
+    pub fn translation(tx: f64, ty: f64) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    /// Creates a new Transform2D which rotates points clockwise on the Canvas by `angle` radians
+    /// around the origin
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Creates a new Transform2D which scales points by (sx,sy) relative to the origin
+    pub fn scaling(sx: f64, sy: f64) -> Self {
+        Self::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    /// Creates a new Transform2D which skews points by `ax` radians along the X axis and `ay`
+    /// radians along the Y axis
+    pub fn skewing(ax: f64, ay: f64) -> Self {
+        Self::new(1.0, ay.tan(), ax.tan(), 1.0, 0.0, 0.0)
+    }
+
+    /// Returns the composition of this Transform2D with `other`, where `other` is applied to
+    /// points first.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::transform::Transform2D;
+    ///
+    /// // Scale, then translate
+    /// let t = Transform2D::translation(10.0, 0.0).multiply(&Transform2D::scaling(2.0, 2.0));
+    /// assert_eq!(t.apply(1.0, 1.0), (12.0, 2.0));
+    /// ```
+    pub fn multiply(&self, other: &Transform2D) -> Self {
+        Self::new(
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        )
+    }
+
+    /// Returns this Transform2D with a translation applied to points first
+    pub fn translate(&self, tx: f64, ty: f64) -> Self {
+        self.multiply(&Self::translation(tx, ty))
+    }
+
+    /// Returns this Transform2D with a rotation applied to points first
+    pub fn rotate(&self, angle: f64) -> Self {
+        self.multiply(&Self::rotation(angle))
+    }
+
+    /// Returns this Transform2D with a scale applied to points first
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        self.multiply(&Self::scaling(sx, sy))
+    }
+
+    /// Returns this Transform2D with a skew applied to points first
+    pub fn skew(&self, ax: f64, ay: f64) -> Self {
+        self.multiply(&Self::skewing(ax, ay))
+    }
+
+    /// Returns the inverse of this Transform2D, or `None` if it cannot be inverted (e.g. when
+    /// scaled by zero)
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rust_wasm_graphics_lib::transform::Transform2D;
+    ///
+    /// let t = Transform2D::translation(5.0, 3.0).scale(2.0, 4.0);
+    /// let inv = t.invert().unwrap();
+    /// assert_eq!(inv.apply(7.0, 7.0), (1.0, 1.0));
+    /// assert!(Transform2D::scaling(0.0, 1.0).invert().is_none());
+    /// ```
+    pub fn invert(&self) -> Option<Transform2D> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        Some(Self::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    /// Returns `true` if this Transform2D leaves all points unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    /// Returns `true` if this Transform2D maps axis-aligned rectangles to axis-aligned rectangles
+    /// (i.e. it contains no rotation or skew)
+    pub fn is_axis_aligned(&self) -> bool {
+        self.b == 0.0 && self.c == 0.0
+    }
+
+    /// Returns the transformed X co-ordinate of a point (x,y)
+    pub fn transform_x(&self, x: f64, y: f64) -> f64 {
+        self.a * x + self.c * y + self.e
+    }
+
+    /// Returns the transformed Y co-ordinate of a point (x,y)
+    pub fn transform_y(&self, x: f64, y: f64) -> f64 {
+        self.b * x + self.d * y + self.f
+    }
+}
+
+impl Transform2D {
+
+    /// Transforms a point (x,y)
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.transform_x(x, y), self.transform_y(x, y))
+    }
+
+    /// Transforms a point with integer co-ordinates, rounding the result to the nearest pixel
+    pub fn apply_pixel(&self, x: isize, y: isize) -> (isize, isize) {
+        let (tx, ty) = self.apply(x as f64, y as f64);
+
+        // Round to the nearest pixel with floor() rather than round(), as in Canvas::sample()
+        ((tx + 0.5).floor() as isize, (ty + 0.5).floor() as isize)
+    }
+}
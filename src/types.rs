@@ -54,3 +54,247 @@ pub enum UVWrapMode {
     Clamp,
     Wrap,
 }
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// A single vertex in homogeneous clip space containing an (X,Y,Z,W) co-ordinate, a (U,V)
+/// co-ordinate and an ARGB colour with components in the range [0,1].
+///
+/// A vertex is inside the view volume when `-w <= x <= w`, `-w <= y <= w` and `-w <= z <= w`.
+pub struct ClipVertex {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+    pub u: f64,
+    pub v: f64,
+    pub a: f64,
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+#[wasm_bindgen]
+impl ClipVertex {
+
+    /// Creates a new opaque white ClipVertex from (X,Y,Z,W) and (U,V) co-ordinates
+    pub fn new(x: f64, y: f64, z: f64, w: f64, u: f64, v: f64) -> Self {
+        Self { x, y, z, w, u, v, a: 1.0, r: 1.0, g: 1.0, b: 1.0 }
+    }
+
+    /// Returns a copy of this ClipVertex with its colour set from an ARGBColour
+    pub fn with_colour(&self, col: &ARGBColour) -> Self {
+        Self {
+            a: f64::from(col.a) / 255.0,
+            r: f64::from(col.r) / 255.0,
+            g: f64::from(col.g) / 255.0,
+            b: f64::from(col.b) / 255.0,
+            ..*self
+        }
+    }
+
+    /// Returns the vertex colour as an ARGBColour
+    pub fn colour(&self) -> ARGBColour {
+        let to_u8 = |x: f64| (x.clamp(0.0, 1.0) * 255.0 + 0.5).floor() as u8;
+        ARGBColour::new(to_u8(self.a), to_u8(self.r), to_u8(self.g), to_u8(self.b))
+    }
+}
+
+impl ClipVertex {
+
+    /// Linearly interpolates all vertex attributes between `self` (`t` = 0) and `other` (`t` = 1)
+    pub fn lerp(&self, other: &ClipVertex, t: f64) -> ClipVertex {
+        let l = |a: f64, b: f64| a + (b - a) * t;
+        ClipVertex {
+            x: l(self.x, other.x),
+            y: l(self.y, other.y),
+            z: l(self.z, other.z),
+            w: l(self.w, other.w),
+            u: l(self.u, other.u),
+            v: l(self.v, other.v),
+            a: l(self.a, other.a),
+            r: l(self.r, other.r),
+            g: l(self.g, other.g),
+            b: l(self.b, other.b),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Set of clip-space planes used when clipping triangles.
+///
+///   - `NearFar`: clip against the near and far planes only
+///   - `All`: clip against all six planes of the view volume (near, far, left, right, top and
+///     bottom)
+pub enum ClipPlanes {
+    NearFar,
+    All,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Which triangles to discard based on their facing in screen space.
+///
+///   - `None`: draw all triangles
+///   - `Back`: discard back-facing triangles
+///   - `Front`: discard front-facing triangles
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Screen-space vertex winding order of front-facing triangles.
+///
+///   - `Cw`: triangles whose vertices appear clockwise on the Canvas are front-facing
+///   - `Ccw`: triangles whose vertices appear counter-clockwise on the Canvas are front-facing
+pub enum FrontFace {
+    Cw,
+    Ccw,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+/// A single vertex containing an (X,Y) co-ordinate, a (U,V) co-ordinate and an ARGB colour with
+/// components in the range [0,1] which is multiplied into the fill or texture colour
+pub struct ShadedVertex {
+    pub x: isize,
+    pub y: isize,
+    pub u: f64,
+    pub v: f64,
+    pub a: f64,
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+#[wasm_bindgen]
+impl ShadedVertex {
+
+    /// Creates a new ShadedVertex from (X,Y) and (U,V) co-ordinates and ARGB colour components
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(x: isize, y: isize, u: f64, v: f64, a: f64, r: f64, g: f64, b: f64) -> Self {
+        Self { x, y, u, v, a, r, g, b }
+    }
+}
+
+impl From<&ShadedVertex> for UVVertex {
+    fn from(x: &ShadedVertex) -> UVVertex {
+        UVVertex::new(x.x, x.y, x.u, x.v)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// How lighting is evaluated across a triangle.
+///
+///   - `Flat`: lighting is evaluated once per face using the face normal
+///   - `Gouraud`: lighting is evaluated per vertex and interpolated across the face
+pub enum ShadingMode {
+    Flat,
+    Gouraud,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Filter used when sampling pixels at non-integer positions.
+///
+///   - `Nearest`: use the nearest pixel
+///   - `Bilinear`: linearly interpolate between the four nearest pixels
+pub enum SampleFilter {
+    Nearest,
+    Bilinear,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Horizontal alignment of each line of text relative to the X co-ordinate it is drawn at.
+///
+///   - `Left`: lines start at X
+///   - `Center`: lines are centred on X
+///   - `Right`: lines end at X
+///   - `Justify`: wrapped lines are stretched to fill the layout width by widening spaces, except
+///     for the last line of each paragraph which starts at X
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Netpbm variant written by [`Canvas::to_netpbm()`]
+///
+///   - `PlainPpm`: P3 colour image with decimal ASCII samples
+///   - `Ppm`: P6 colour image with binary samples
+///   - `PlainPgm`: P2 greyscale image with decimal ASCII samples
+///   - `Pgm`: P5 greyscale image with binary samples
+///   - `Pam`: P7 colour image with an alpha channel (`RGB_ALPHA`)
+///
+/// All variants except `Pam` discard alpha.
+///
+/// [`Canvas::to_netpbm()`]: ../canvas/struct.Canvas.html#method.to_netpbm
+pub enum NetpbmFormat {
+    PlainPpm,
+    Ppm,
+    PlainPgm,
+    Pgm,
+    Pam,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Filter used when resampling a Canvas to a new size.
+///
+///   - `Nearest`: use the nearest source pixel
+///   - `Bilinear`: linearly interpolate between neighbouring source pixels
+///   - `Bicubic`: Catmull-Rom cubic interpolation, which is sharper than bilinear
+///   - `Lanczos`: 3-lobed Lanczos windowed sinc, the sharpest but slowest filter
+///
+/// When shrinking, all filters except `Nearest` average every source pixel covered by each
+/// destination pixel.
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Position of the existing content within a Canvas when its size is changed.
+///
+///   - `TopLeft`, `Top`, `TopRight`: content is kept against the top edge
+///   - `Left`, `Center`, `Right`: content is centred vertically
+///   - `BottomLeft`, `Bottom`, `BottomRight`: content is kept against the bottom edge
+///
+/// Horizontally, content is kept against the left edge, centred or kept against the right edge
+/// respectively.
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq)]
+/// Treatment of pixels beyond the edges of a Canvas when filtering.
+///
+///   - `Clamp`: the nearest edge pixel is repeated
+///   - `Wrap`: pixels are taken from the opposite edge, for tiling images
+///   - `Transparent`: pixels are fully transparent, so content fades out towards the edges
+pub enum EdgeMode {
+    Clamp,
+    Wrap,
+    Transparent,
+}
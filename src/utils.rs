@@ -8,6 +8,7 @@ cfg_if! {
     // For more details see
     // https://github.com/rustwasm/console_error_panic_hook#readme
     if #[cfg(feature = "console_error_panic_hook")] {
+        #[allow(unused_imports)]
         pub use console_error_panic_hook::set_once as set_panic_hook;
     } else {
         #[inline]
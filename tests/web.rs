@@ -10,7 +10,35 @@ wasm_bindgen_test_configure!(run_in_browser);
 extern crate rust_wasm_graphics_lib;
 use crate::rust_wasm_graphics_lib::canvas::{self, Canvas};
 use crate::rust_wasm_graphics_lib::drawing;
-use crate::rust_wasm_graphics_lib::types::{self, ARGBColour, UVWrapMode};
+use crate::rust_wasm_graphics_lib::filter::convolve::Kernel;
+use crate::rust_wasm_graphics_lib::font::{self, BitmapFont};
+use crate::rust_wasm_graphics_lib::font::ttf::{GlyphCache, TrueTypeFont};
+use crate::rust_wasm_graphics_lib::image::gif::GifEncoder;
+use crate::rust_wasm_graphics_lib::image::ImageError;
+use crate::rust_wasm_graphics_lib::image::png;
+use crate::rust_wasm_graphics_lib::image::video::{FrameSink, RawRgbaWriter, Y4mWriter};
+use crate::rust_wasm_graphics_lib::lighting::Lighting;
+use crate::rust_wasm_graphics_lib::mesh::Mesh;
+use crate::rust_wasm_graphics_lib::sprite::SpriteAtlas;
+use crate::rust_wasm_graphics_lib::tilemap::{self, TileMap};
+use crate::rust_wasm_graphics_lib::transform::Transform2D;
+use crate::rust_wasm_graphics_lib::types::{
+    self,
+    Anchor,
+    ARGBColour,
+    ClipPlanes,
+    ClipVertex,
+    CullMode,
+    EdgeMode,
+    FrontFace,
+    NetpbmFormat,
+    ResizeFilter,
+    SampleFilter,
+    ShadedVertex,
+    ShadingMode,
+    TextAlign,
+    UVWrapMode,
+};
 
 fn assert_no_pixels_with_colour(canv: &Canvas, col: &ARGBColour) {
     let col_u32: u32 = col.into();
@@ -44,6 +72,22 @@ fn assert_pixels_without_colour(canv: &Canvas, col: &ARGBColour, coords: &Vec<(u
     );
 }
 
+fn hashed_pixel(i: usize) -> u32 {
+    (i as u32).wrapping_mul(0x9E37_79B9)
+}
+
+fn hashed_canvas(width: usize, height: usize) -> Canvas {
+    let mut canv = Canvas::new(width, height);
+    for (i, px) in canv.buffer_mut().iter_mut().enumerate() {
+        *px = hashed_pixel(i);
+    }
+    canv
+}
+
+fn decode_error(decode: fn(&[u8]) -> Result<Canvas, ImageError>, data: &[u8]) -> String {
+    decode(data).err().unwrap().message()
+}
+
 
 #[wasm_bindgen_test]
 fn argbcolour_into_u32() {
@@ -124,6 +168,150 @@ fn canvas_draw_canvas() {
         ],
     );
     assert_pixels_with_colour(&dst, &col, &vec![(1, 1), (2, 2)]);
+
+    // Should produce: -
+    //  +---+
+    //  |#  |
+    //  | # |
+    //  |   |
+    //  +---+
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas(&src, -1, -1);
+    assert_pixels_with_colour(&dst, &col, &vec![(0, 0), (1, 1)]);
+    assert_pixels_without_colour(
+        &dst,
+        &col,
+        &vec![
+            /*   */ (1, 0), (2, 0),
+            (0, 1), /*   */ (2, 1),
+            (0, 2), (1, 2), (2, 2),
+        ],
+    );
+
+    // Should produce: -
+    //  +---+
+    //  |  #|
+    //  |   |
+    //  |  #|
+    //  +---+
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas(&src, 2, 0);
+    assert_pixels_with_colour(&dst, &col, &vec![(2, 0), (2, 2)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (2, 1), (0, 2), (1, 2)]);
+
+    // Entirely off-canvas on each side
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas(&src, -3, 0);
+    dst.draw_canvas(&src, 3, 0);
+    dst.draw_canvas(&src, 0, -3);
+    dst.draw_canvas(&src, 0, 3);
+    assert_no_pixels_with_colour(&dst, &col);
+}
+
+#[wasm_bindgen_test]
+fn canvas_draw_canvas_region() {
+    let col = ARGBColour::new(255, 255, 0, 0);
+    let col_u32 = u32::from(&col);
+
+    // Source atlas with two 2x2 frames: -
+    //  +----+
+    //  |#  #|
+    //  |   #|
+    //  +----+
+    let mut src = Canvas::new(4, 2);
+    src.buffer_mut()[0] = col_u32;
+    src.buffer_mut()[3] = col_u32;
+    src.buffer_mut()[7] = col_u32;
+
+    // Second frame only
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas_region(&src, 2, 0, 2, 2, 1, 1, false, false);
+    assert_pixels_with_colour(&dst, &col, &vec![(2, 1), (2, 2)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (1, 1), (1, 2)]);
+
+    // Second frame flipped horizontally
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas_region(&src, 2, 0, 2, 2, 1, 1, true, false);
+    assert_pixels_with_colour(&dst, &col, &vec![(1, 1), (1, 2)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(2, 1), (2, 2)]);
+
+    // First frame flipped vertically
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas_region(&src, 0, 0, 2, 2, 0, 0, false, true);
+    assert_pixels_with_colour(&dst, &col, &vec![(0, 1)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (1, 0), (1, 1)]);
+
+    // Whole atlas partly off the top-left corner, flipped both ways
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas_region(&src, 0, 0, 4, 2, -1, -1, true, true);
+    assert_pixels_with_colour(&dst, &col, &vec![(2, 0)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (1, 0), (0, 1), (1, 1), (2, 1)]);
+
+    // Regions entirely off either Canvas are ignored
+    let mut dst = Canvas::new(3, 3);
+    dst.draw_canvas_region(&src, 4, 0, 2, 2, 0, 0, false, false);
+    dst.draw_canvas_region(&src, 0, 0, 2, 2, 3, 0, false, false);
+    dst.draw_canvas_region(&src, 0, 0, 2, 2, 0, -2, false, false);
+    assert_no_pixels_with_colour(&dst, &col);
+}
+
+#[wasm_bindgen_test]
+fn canvas_draw_canvas_transformed() {
+    let col = ARGBColour::new(255, 255, 0, 0);
+    let col_u32 = u32::from(&col);
+
+    // Source: -
+    //  +--+
+    //  |# |
+    //  |  |
+    //  +--+
+    let mut src = Canvas::new(2, 2);
+    src.buffer_mut()[0] = col_u32;
+
+    // Identity transform matches draw_canvas()
+    let mut dst = Canvas::new(4, 4);
+    dst.draw_canvas_transformed(&src, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, SampleFilter::Nearest);
+    assert_pixels_with_colour(&dst, &col, &vec![(1, 1)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (2, 1), (1, 2), (2, 2)]);
+
+    // Scaled by 2
+    let mut dst = Canvas::new(4, 4);
+    dst.draw_canvas_transformed(&src, 0.0, 0.0, 2.0, 2.0, 0.0, 0.0, 0.0, SampleFilter::Nearest);
+    assert_pixels_with_colour(&dst, &col, &vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(2, 0), (0, 2), (2, 2), (3, 3)]);
+
+    // Rotated 90 degrees clockwise around the centre of the source: top-left moves to top-right
+    let mut dst = Canvas::new(4, 4);
+    dst.draw_canvas_transformed(&src, 2.0, 2.0, 1.0, 1.0, std::f64::consts::PI / 2.0, 1.0, 1.0, SampleFilter::Nearest);
+    assert_pixels_with_colour(&dst, &col, &vec![(2, 1)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(1, 1), (1, 2), (2, 2)]);
+
+    // Partly off-canvas and zero scale don't panic
+    let mut dst = Canvas::new(4, 4);
+    dst.draw_canvas_transformed(&src, -1.0, -1.0, 4.0, 4.0, 0.3, 0.0, 0.0, SampleFilter::Bilinear);
+    dst.draw_canvas_transformed(&src, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, SampleFilter::Bilinear);
+
+    // Bilinear filtering blends neighbouring pixels
+    let mut src = Canvas::new(2, 1);
+    src.buffer_mut()[0] = u32::from(&ARGBColour::new(255, 0, 0, 0));
+    src.buffer_mut()[1] = u32::from(&ARGBColour::new(255, 200, 0, 0));
+    assert_eq!(src.sample_bilinear(0.5, 0.0), u32::from(&ARGBColour::new(255, 100, 0, 0)));
+
+    // Transparent pixels don't bleed their colour into filtered samples
+    src.buffer_mut()[0] = u32::from(&ARGBColour::new(0, 0, 0, 255));
+    assert_eq!(src.sample_bilinear(0.5, 0.0), u32::from(&ARGBColour::new(128, 200, 0, 0)));
+
+    // Partially transparent samples are blended over the destination
+    let mut dst = Canvas::new(2, 1);
+    dst.buffer_mut().fill(0xFF0000FF);
+    let mut src = Canvas::new(2, 1);
+    src.buffer_mut().fill(0x80FF0000);
+    dst.draw_canvas_transformed(&src, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, SampleFilter::Bilinear);
+    assert_eq!(dst.buffer()[0], 0xFF80007F);
+
+    // Empty Canvases sample as transparent
+    assert_eq!(Canvas::new(0, 2).sample_bilinear(0.0, 0.0), 0);
+    assert_eq!(Canvas::new(2, 0).sample_bilinear(1.0, 1.0), 0);
 }
 
 #[wasm_bindgen_test]
@@ -194,6 +382,95 @@ fn canvas_sample() {
 }
 
 
+#[wasm_bindgen_test]
+fn canvas_transform() {
+    let col = ARGBColour::new(255, 255, 0, 0);
+
+    // Translated rectangle
+    let mut canv = Canvas::new(4, 4);
+    canv.translate(2.0, 1.0);
+    drawing::rect::fill_rect(&mut canv, &col, 0, 0, 1, 1);
+    assert_pixels_with_colour(&canv, &col, &vec![(2, 1), (3, 1), (2, 2), (3, 2)]);
+    assert_pixels_without_colour(&canv, &col, &vec![(0, 0), (1, 1), (2, 3), (3, 0)]);
+
+    // Scaled line
+    let mut canv = Canvas::new(4, 4);
+    canv.scale(3.0, 1.0);
+    drawing::lines::line(&mut canv, &col, 0, 2, 1, 2);
+    assert_pixels_with_colour(&canv, &col, &vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+
+    // Rotation by 90 degrees around the centre of the Canvas, with save/restore
+    let mut canv = Canvas::new(5, 5);
+    canv.save();
+    canv.translate(2.0, 2.0);
+    canv.rotate(std::f64::consts::PI / 2.0);
+    drawing::lines::line(&mut canv, &col, 0, 0, 2, 0);
+    canv.restore();
+    assert!(canv.transform().is_identity());
+    assert_pixels_with_colour(&canv, &col, &vec![(2, 2), (2, 3), (2, 4)]);
+    assert_pixels_without_colour(&canv, &col, &vec![(3, 2), (4, 2)]);
+
+    // Rotated filled rectangle becomes a diamond
+    let mut canv = Canvas::new(7, 7);
+    canv.translate(3.0, 3.0);
+    canv.rotate(std::f64::consts::PI / 4.0);
+    drawing::rect::fill_rect(&mut canv, &col, -2, -2, 2, 2);
+    assert_pixels_with_colour(&canv, &col, &vec![(3, 0), (0, 3), (3, 3), (6, 3), (3, 6)]);
+    assert_pixels_without_colour(&canv, &col, &vec![(0, 0), (6, 0), (0, 6), (6, 6)]);
+
+    // Polygons and triangles are transformed
+    let mut canv = Canvas::new(4, 4);
+    canv.set_transform(&Transform2D::translation(1.0, 1.0));
+    drawing::shape::fill_triangle(&mut canv, &col, 0, 0, 2, 2, 0, 2);
+    assert_pixels_with_colour(&canv, &col, &vec![(1, 1), (1, 3), (3, 3)]);
+    assert_pixels_without_colour(&canv, &col, &vec![(0, 0), (0, 3)]);
+    canv.clear(&ARGBColour::new(0, 0, 0, 0));
+    drawing::shape::polygon(&mut canv, &col, true, vec![0, 0, 2, 0, 2, 2]);
+    assert_pixels_with_colour(&canv, &col, &vec![(1, 1), (3, 1), (3, 3), (2, 2)]);
+    canv.reset_transform();
+    assert!(canv.transform().is_identity());
+
+    // Inversion
+    let t = Transform2D::identity().translate(3.0, -2.0).rotate(0.3).scale(2.0, 0.5).skew(0.1, 0.2);
+    let (x, y) = t.apply(5.0, 7.0);
+    let (ix, iy) = t.invert().unwrap().apply(x, y);
+    assert!((ix - 5.0).abs() < 1e-9 && (iy - 7.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn drawing_clip_triangle() {
+    let a = ClipVertex::new(-0.5, -0.5, 0.5, 1.0, 0.0, 0.0);
+    let b = ClipVertex::new( 0.5, -0.5, 0.5, 1.0, 1.0, 0.0);
+
+    // Triangle entirely inside the view volume is unchanged
+    let c = ClipVertex::new(0.0, 0.5, 0.5, 1.0, 0.5, 1.0);
+    assert_eq!(drawing::clip::clip_triangle(&a, &b, &c, ClipPlanes::All).len(), 3);
+
+    // Triangle entirely behind the camera is removed
+    let behind = |v: &ClipVertex| ClipVertex::new(v.x, v.y, -2.0, -1.0, v.u, v.v);
+    assert_eq!(
+        drawing::clip::clip_triangle(&behind(&a), &behind(&b), &behind(&c), ClipPlanes::NearFar).len(),
+        0,
+    );
+
+    // Triangle crossing the near plane is split into a quad with interpolated U,V and colour
+    let c = ClipVertex::new(0.0, 1.5, -3.0, -1.0, 0.5, 1.0).with_colour(&ARGBColour::new(255, 0, 0, 0));
+    let poly = drawing::clip::clip_triangle(&a, &b, &c, ClipPlanes::NearFar);
+    assert_eq!(poly.len(), 4);
+    assert!(poly.iter().all(|v| v.z + v.w >= -1e-9 && v.w > 0.0));
+    assert!(poly.iter().all(|v| v.v >= 0.0 && v.v < 1.0));
+    assert!(poly.iter().any(|v| v.r < 1.0 && v.r > 0.0));
+
+    // Clipped pieces can be drawn without panicking
+    let mut dst = Canvas::new(16, 16);
+    let mut src = Canvas::new(2, 2);
+    let col = ARGBColour::new(255, 255, 0, 0);
+    src.clear(&col);
+    drawing::clip::textured_triangle_clipped(&mut dst, &src, &a, &b, &c, UVWrapMode::Clamp, ClipPlanes::All);
+    assert_pixels_with_colour(&dst, &col, &vec![(2, 7), (14, 7), (7, 9), (5, 11)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(0, 0), (15, 0), (7, 6), (7, 12)]);
+}
+
 #[wasm_bindgen_test]
 fn drawing_fill_rect() {
     let mut canv = Canvas::new(4, 6);
@@ -577,3 +854,1263 @@ fn drawing_v_line() {
     // Assert all filled locations are set to "col"
     assert_pixels_with_colour(&canv, &col, &eq_idx);
 }
+
+#[wasm_bindgen_test]
+fn drawing_textured_triangles_cull() {
+    let mut src = Canvas::new(2, 2);
+    let col = ARGBColour::new(255, 255, 0, 0);
+    src.clear(&col);
+
+    // Top-left triangle is clockwise on the Canvas, bottom-right triangle is counter-clockwise
+    let verts = vec![
+        0.0, 0.0, 0.0, 0.0,  7.0, 0.0, 1.0, 0.0,  0.0, 7.0, 0.0, 1.0,
+        7.0, 1.0, 1.0, 0.0,  1.0, 7.0, 0.0, 1.0,  7.0, 7.0, 1.0, 1.0,
+    ];
+
+    // Cull nothing
+    let mut dst = Canvas::new(8, 8);
+    drawing::shape::textured_triangles(&mut dst, &src, verts.clone(), UVWrapMode::Clamp, CullMode::None, FrontFace::Cw);
+    assert_pixels_with_colour(&dst, &col, &vec![(1, 1), (6, 6)]);
+
+    // Cull back faces with clockwise front faces: only the top-left triangle is drawn
+    let mut dst = Canvas::new(8, 8);
+    drawing::shape::textured_triangles(&mut dst, &src, verts.clone(), UVWrapMode::Clamp, CullMode::Back, FrontFace::Cw);
+    assert_pixels_with_colour(&dst, &col, &vec![(1, 1)]);
+    assert_pixels_without_colour(&dst, &col, &vec![(6, 6)]);
+
+    // Cull front faces with clockwise front faces: only the bottom-right triangle is drawn
+    let mut dst = Canvas::new(8, 8);
+    drawing::shape::textured_triangles(&mut dst, &src, verts.clone(), UVWrapMode::Clamp, CullMode::Front, FrontFace::Cw);
+    assert_pixels_without_colour(&dst, &col, &vec![(1, 1)]);
+    assert_pixels_with_colour(&dst, &col, &vec![(6, 6)]);
+
+    // Cull back faces with counter-clockwise front faces: only the bottom-right triangle is drawn
+    let mut dst = Canvas::new(8, 8);
+    drawing::shape::textured_triangles(&mut dst, &src, verts, UVWrapMode::Clamp, CullMode::Back, FrontFace::Ccw);
+    assert_pixels_without_colour(&dst, &col, &vec![(1, 1)]);
+    assert_pixels_with_colour(&dst, &col, &vec![(6, 6)]);
+}
+
+#[wasm_bindgen_test]
+fn mesh_from_obj() {
+    let src = "
+        # A textured quad and a triangle using negative indices
+        mtllib quad.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        usemtl red
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        usemtl blue
+        f -4//-1 -3//-1 -2//-1
+    ";
+    let mut mesh = Mesh::from_obj(src).unwrap();
+    assert_eq!(mesh.positions().len(), 4);
+    assert_eq!(mesh.tex_coords().len(), 4);
+    assert_eq!(mesh.normals().len(), 1);
+    assert_eq!(mesh.triangle_count(), 3);
+
+    let tris = mesh.triangles();
+    assert_eq!(tris[0].vertices.iter().map(|v| v.position).collect::<Vec<usize>>(), vec![0, 1, 2]);
+    assert_eq!(tris[1].vertices.iter().map(|v| v.position).collect::<Vec<usize>>(), vec![0, 2, 3]);
+    assert_eq!(tris[2].vertices.iter().map(|v| v.position).collect::<Vec<usize>>(), vec![0, 1, 2]);
+    assert_eq!(tris[1].vertices[2].tex_coord, Some(3));
+    assert_eq!(tris[2].vertices[0].tex_coord, None);
+    assert_eq!(tris[2].vertices[0].normal, Some(0));
+    assert_eq!(tris[0].material, Some(0));
+    assert_eq!(tris[2].material, Some(1));
+
+    mesh.load_mtl("newmtl red\nKd 1 0 0\nmap_Kd -s 1 1 1 red.png\nnewmtl blue\nKd 0 0 1\nd 0.5\n").unwrap();
+    assert_eq!(mesh.materials()[0].diffuse_texture, Some(String::from("red.png")));
+    assert_eq!(mesh.materials()[1].diffuse, [0.0, 0.0, 1.0]);
+    assert_eq!(mesh.materials()[1].alpha, 0.5);
+
+    // Vertices are ready to draw: V is flipped and colours come from materials
+    let identity = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    let verts = mesh.to_clip_vertices(&identity);
+    assert_eq!(verts.len(), 9);
+    assert_eq!((verts[2].x, verts[2].y, verts[2].w), (1.0, 1.0, 1.0));
+    assert_eq!((verts[2].u, verts[2].v), (1.0, 0.0));
+    assert_eq!((verts[0].r, verts[0].b), (1.0, 0.0));
+    assert_eq!((verts[8].b, verts[8].a), (1.0, 0.5));
+
+    // Errors report the offending line
+    let err = Mesh::from_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap_err();
+    assert_eq!(err.line(), 3);
+    assert!(Mesh::from_obj("v 0 0 x\n").is_err());
+    assert!(Mesh::from_obj("v 0 0 0\nf 1 1\n").is_err());
+}
+
+#[wasm_bindgen_test]
+fn drawing_shaded_triangle() {
+    let mut canv = Canvas::new(4, 4);
+    let col = ARGBColour::new(255, 200, 100, 50);
+
+    // Top vertex at full brightness, bottom vertices at zero brightness
+    drawing::shape::shaded_triangle(
+        &mut canv,
+        &col,
+        &ShadedVertex::new(0, 0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0),
+        &ShadedVertex::new(3, 3, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
+        &ShadedVertex::new(0, 3, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
+    );
+    assert_pixels_with_colour(&canv, &col, &vec![(0, 0)]);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(255, 0, 0, 0), &vec![(0, 3), (1, 3), (3, 3)]);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(0, 0, 0, 0), &vec![(1, 0), (3, 0), (3, 2)]);
+
+    // Halfway down, colour is interpolated to half brightness
+    let mid = canv.buffer()[canv.buffer_index(0, 2)];
+    assert_eq!(mid, u32::from(&ARGBColour::new(255, 67, 33, 17)));
+
+    // Textured version multiplies sampled pixels
+    let mut src = Canvas::new(2, 2);
+    src.clear(&col);
+    let mut canv = Canvas::new(4, 4);
+    drawing::shape::shaded_textured_triangle(
+        &mut canv,
+        &src,
+        &ShadedVertex::new(0, 0, 0.0, 0.0, 1.0, 0.5, 0.5, 0.5),
+        &ShadedVertex::new(3, 3, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5),
+        &ShadedVertex::new(0, 3, 0.0, 1.0, 1.0, 0.5, 0.5, 0.5),
+        UVWrapMode::Clamp,
+    );
+    assert_pixels_with_colour(&canv, &ARGBColour::new(255, 100, 50, 25), &vec![(0, 0), (0, 3), (3, 3)]);
+}
+
+#[wasm_bindgen_test]
+fn lighting_mesh() {
+    let mesh = Mesh::from_obj("
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vn 0 0 1
+        vn 0 0 1
+        vn 0 0 -1
+        f 1//1 2//2 3//3
+    ").unwrap();
+    let identity = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+    // Ambient only
+    let lighting = Lighting::new(0.25, 0.5, 0.75);
+    let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Flat);
+    assert!(verts.iter().all(|v| (v.r, v.g, v.b) == (0.25, 0.5, 0.75)));
+
+    // Directional light from the front: flat shading uses the face normal
+    let mut lighting = Lighting::new(0.0, 0.0, 0.0);
+    lighting.add_directional_light(0.0, 0.0, -1.0, 1.0, 1.0, 1.0);
+    let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Flat);
+    assert!(verts.iter().all(|v| v.r == 1.0));
+
+    // Gouraud shading uses vertex normals: third vertex faces away from the light
+    let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Gouraud);
+    assert_eq!((verts[0].r, verts[1].r, verts[2].r), (1.0, 1.0, 0.0));
+
+    // Point light with attenuation
+    let mut lighting = Lighting::new(0.0, 0.0, 0.0);
+    lighting.add_point_light(0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0);
+    let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Gouraud);
+    assert!((verts[0].r - 0.5).abs() < 1e-9);
+
+    // Specular highlights are added after the surface colour, so they take the light's colour
+    let mut lighting = Lighting::new(0.0, 0.0, 0.0);
+    lighting.add_directional_light(0.0, 0.0, -1.0, 0.5, 0.5, 0.5);
+    let red = ARGBColour::new(255, 200, 0, 0);
+    let plain = lighting.light_colour(&red, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    lighting.set_specular(1.0, 16.0, 0.0, 0.0, 10.0);
+    let shiny = lighting.light_colour(&red, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    assert_eq!((plain.r, plain.g, plain.b), (100, 0, 0));
+    assert_eq!((shiny.r, shiny.g, shiny.b), (228, 128, 128));
+    let verts = mesh.to_lit_clip_vertices(&identity, &lighting, ShadingMode::Flat);
+    assert!(verts.iter().all(|v| v.r > 0.5 && v.r == v.g));
+}
+
+#[wasm_bindgen_test]
+fn sprite_atlas() {
+    let red = ARGBColour::new(255, 255, 0, 0);
+    let blue = ARGBColour::new(255, 0, 0, 255);
+
+    // Atlas containing a 2x2 red frame followed by a 2x1 blue frame trimmed from a 2x2 sprite
+    let mut atlas_canv = Canvas::new(4, 2);
+    drawing::rect::fill_rect(&mut atlas_canv, &red, 0, 0, 1, 1);
+    drawing::rect::fill_rect(&mut atlas_canv, &blue, 2, 0, 3, 0);
+    let mut atlas = SpriteAtlas::from_json(atlas_canv, r#"{
+        "frames": [
+            { "filename": "a", "frame": { "x": 0, "y": 0, "w": 2, "h": 2 }, "pivot": { "x": 0.5, "y": 1.0 } },
+            {
+                "filename": "b",
+                "frame": { "x": 2, "y": 0, "w": 2, "h": 1 },
+                "spriteSourceSize": { "x": 0, "y": 1, "w": 2, "h": 1 },
+                "sourceSize": { "w": 2, "h": 2 },
+                "pivot": { "x": 0.5, "y": 1.0 },
+                "duration": 50
+            }
+        ],
+        "animations": {
+            "blink": ["a", { "frame": "b", "duration": 200 }],
+            "short": ["b"]
+        }
+    }"#).unwrap();
+    assert_eq!(atlas.frame_count(), 2);
+    assert_eq!(atlas.animation_duration("blink"), 300.0);
+    assert_eq!(atlas.animation_duration("short"), 50.0);
+
+    // Frame drawn relative to its pivot
+    let mut canv = Canvas::new(8, 8);
+    assert!(atlas.draw_frame(&mut canv, "a", 4, 4, false));
+    assert_pixels_with_colour(&canv, &red, &vec![(3, 2), (4, 2), (3, 3), (4, 3)]);
+    assert!(!atlas.draw_frame(&mut canv, "missing", 4, 4, false));
+
+    // Trimmed frame is drawn at its offset within the original sprite
+    let mut canv = Canvas::new(8, 8);
+    assert!(atlas.draw_frame(&mut canv, "b", 4, 4, false));
+    assert_pixels_with_colour(&canv, &blue, &vec![(3, 3), (4, 3)]);
+    assert_pixels_without_colour(&canv, &blue, &vec![(3, 2), (4, 2)]);
+
+    // Animation frame selected by time, looping or holding the last frame
+    let mut canv = Canvas::new(8, 8);
+    atlas.draw_animation(&mut canv, "blink", 150.0, 4, 4, true, false);
+    assert_pixels_with_colour(&canv, &blue, &vec![(3, 3)]);
+    let mut canv = Canvas::new(8, 8);
+    atlas.draw_animation(&mut canv, "blink", 350.0, 4, 4, true, false);
+    assert_pixels_with_colour(&canv, &red, &vec![(3, 2)]);
+    let mut canv = Canvas::new(8, 8);
+    atlas.draw_animation(&mut canv, "blink", 350.0, 4, 4, false, false);
+    assert_pixels_with_colour(&canv, &blue, &vec![(3, 3)]);
+    assert!(!atlas.draw_animation(&mut canv, "missing", 0.0, 4, 4, true, false));
+
+    // Rotated frames and unknown animation frames are rejected
+    assert!(SpriteAtlas::from_json(Canvas::new(1, 1), r#"{ "frames": { "a": { "frame": { "x": 0, "y": 0, "w": 1, "h": 1 }, "rotated": true } } }"#).is_err());
+    assert!(SpriteAtlas::from_json(Canvas::new(1, 1), r#"{ "frames": {}, "animations": { "x": ["a"] } }"#).is_err());
+    assert!(SpriteAtlas::from_json(Canvas::new(1, 1), "{ frames").is_err());
+
+    // Escaped surrogate pairs are combined into one character
+    let atlas = SpriteAtlas::from_json(Canvas::new(1, 1), r#"{ "frames": { "\uD83D\uDE00": { "frame": { "x": 0, "y": 0, "w": 1, "h": 1 } } } }"#).unwrap();
+    assert!(atlas.draw_frame(&mut Canvas::new(1, 1), "\u{1F600}", 0, 0, false));
+
+    // Deeply nested documents are rejected rather than overflowing the stack
+    let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    assert!(SpriteAtlas::from_json(Canvas::new(1, 1), &deep).is_err());
+}
+
+#[wasm_bindgen_test]
+fn tilemap_draw() {
+    let red = ARGBColour::new(255, 255, 0, 0);
+    let green = ARGBColour::new(255, 0, 255, 0);
+    let blue = ARGBColour::new(255, 0, 0, 255);
+
+    // Tileset of three 2x2 tiles: red, green with a blue top-left pixel, and blue
+    let mut tileset = Canvas::new(6, 2);
+    drawing::rect::fill_rect(&mut tileset, &red, 0, 0, 1, 1);
+    drawing::rect::fill_rect(&mut tileset, &green, 2, 0, 3, 1);
+    drawing::rect::fill_rect(&mut tileset, &blue, 2, 0, 2, 0);
+    drawing::rect::fill_rect(&mut tileset, &blue, 4, 0, 5, 1);
+
+    let mut map = TileMap::new(tileset, 2, 2, 4, 4);
+    assert!(map.set_tile(0, 0, 0, 0, false, false, false));
+    assert!(map.set_tile(0, 1, 0, 1, true, false, false));
+    assert!(!map.set_tile(0, 4, 0, 0, false, false, false));
+    assert!(!map.set_tile(1, 0, 0, 0, false, false, false));
+    assert!(!map.set_tile(0, 0, 0, u32::MAX, false, false, false));
+    assert!(!map.set_tile(0, 0, 0, tilemap::TILE_FLIP_D, false, false, false));
+    assert_eq!(map.tile_value(0, 1, 0), 2 | tilemap::TILE_FLIP_H);
+    assert!(map.set_tile(0, 2, 0, 1, false, true, true));
+    assert_eq!(map.tile_value(0, 2, 0), 2 | tilemap::TILE_FLIP_V | tilemap::TILE_FLIP_D);
+    assert!(map.clear_tile(0, 2, 0));
+
+    // Upper layer drawn over the lower one
+    assert_eq!(map.add_layer(), 1);
+    assert!(map.load_layer(1, vec![0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+    assert!(!map.load_layer(1, vec![0; 3]));
+    map.set_tile(0, 0, 1, 0, false, false, false);
+
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_tilemap(&map, 0, 0, 0.0);
+    assert_pixels_with_colour(&canv, &red, &vec![(0, 0), (1, 1)]);
+    assert_pixels_with_colour(&canv, &blue, &vec![(3, 0), (0, 2), (1, 3)]);
+    assert_pixels_with_colour(&canv, &green, &vec![(2, 0), (2, 1), (3, 1)]);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(0, 0, 0, 0), &vec![(4, 0), (7, 7)]);
+
+    // Single layer only
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_tilemap_layer(&map, 0, 0, 0, 0.0);
+    assert_pixels_with_colour(&canv, &red, &vec![(0, 2)]);
+
+    // Camera offset, including partially visible tiles
+    let mut canv = Canvas::new(2, 2);
+    canv.draw_tilemap(&map, 1, 1, 0.0);
+    assert_pixels_with_colour(&canv, &red, &vec![(0, 0)]);
+    assert_pixels_with_colour(&canv, &green, &vec![(1, 0)]);
+    assert_pixels_with_colour(&canv, &blue, &vec![(0, 1)]);
+    let mut canv = Canvas::new(2, 2);
+    canv.draw_tilemap(&map, -1, -1, 0.0);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(0, 0, 0, 0), &vec![(0, 0), (1, 0), (0, 1)]);
+    assert_pixels_with_colour(&canv, &red, &vec![(1, 1)]);
+
+    // Animated tile: red tile alternates with the blue tile
+    map.add_tile_animation_frame(0, 0, 100.0);
+    map.add_tile_animation_frame(0, 2, 100.0);
+    let mut canv = Canvas::new(2, 2);
+    canv.draw_tilemap(&map, 0, 0, 150.0);
+    assert_all_pixels_have_colour(&canv, &blue);
+    canv.draw_tilemap(&map, 0, 0, 250.0);
+    assert_all_pixels_have_colour(&canv, &red);
+
+    // Diagonally flipped tiles are transposed, then flipped: the blue corner of the green tile
+    // stays top-left, and moves top-right and bottom-left when rotated 90 degrees either way
+    let mut map = TileMap::new(map.tileset().crop(0, 0, 6, 2), 2, 2, 3, 1);
+    let d = tilemap::TILE_FLIP_D;
+    assert!(map.load_layer(0, vec![2 | d, 2 | d | tilemap::TILE_FLIP_H, 2 | d | tilemap::TILE_FLIP_V]));
+    let mut canv = Canvas::new(6, 2);
+    canv.draw_tilemap(&map, 0, 0, 0.0);
+    assert_pixels_with_colour(&canv, &blue, &vec![(0, 0), (3, 0), (4, 1)]);
+    assert_pixels_with_colour(&canv, &green, &vec![(1, 0), (0, 1), (1, 1), (2, 0), (2, 1), (3, 1), (4, 0), (5, 0), (5, 1)]);
+    let mut canv = Canvas::new(6, 2);
+    canv.draw_tilemap(&map, -5, 1, 0.0);
+    assert_pixels_with_colour(&canv, &green, &vec![(5, 0)]);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(0, 0, 0, 0), &vec![(4, 0), (5, 1)]);
+}
+
+#[wasm_bindgen_test]
+fn font_draw_text() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let red = ARGBColour::new(255, 255, 0, 0);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+
+    // Fixed font of 2x2 glyphs: 'A' is solid, 'B' has only its top-left pixel set
+    let mut atlas = Canvas::new(4, 2);
+    drawing::rect::fill_rect(&mut atlas, &white, 0, 0, 1, 1);
+    drawing::rect::fill_rect(&mut atlas, &white, 2, 0, 2, 0);
+    let mut font = BitmapFont::fixed(atlas, 2, 2, 'A' as u32);
+    assert_eq!(font.glyph_count(), 2);
+
+    // Character codes past u32::MAX are skipped rather than overflowing
+    assert_eq!(BitmapFont::fixed(Canvas::new(4, 2), 2, 2, u32::MAX).glyph_count(), 0);
+
+    // Tinted, with transparent glyph pixels skipped
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_text(&font, "AB", 0, 0, &red, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &red, &vec![(0, 0), (1, 1), (2, 0)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(3, 0), (2, 1), (4, 0)]);
+
+    // Unknown characters are skipped when there is no '?' glyph
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_text(&font, "xA", 0, 0, &white, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &white, &vec![(0, 0)]);
+
+    // Right and centre alignment, multiple lines with extra line spacing
+    font.set_line_spacing(1);
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_text(&font, "A\nAA", 8, 0, &white, TextAlign::Right);
+    assert_pixels_with_colour(&canv, &white, &vec![(6, 0), (7, 1), (4, 3), (7, 4)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(5, 0), (7, 2), (3, 3)]);
+    let mut canv = Canvas::new(8, 8);
+    canv.draw_text(&font, "AA", 4, 0, &white, TextAlign::Center);
+    assert_pixels_with_colour(&canv, &white, &vec![(2, 0), (5, 0)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(1, 0), (6, 0)]);
+
+    // Variable-width glyphs with offsets, clipped at the Canvas edges
+    let mut atlas = Canvas::new(4, 2);
+    drawing::rect::fill_rect(&mut atlas, &white, 0, 0, 0, 1);
+    let mut font = BitmapFont::new(atlas, 4, 3);
+    font.add_glyph('i', 0, 0, 1, 2, 1, 1, 3);
+    let mut canv = Canvas::new(4, 4);
+    canv.draw_text(&font, "ii", -1, -2, &white, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &white, &vec![(0, 0), (3, 0)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(0, 1), (1, 0), (2, 0)]);
+}
+
+#[wasm_bindgen_test]
+fn font_load_bmfont() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+    let atlas = || {
+        let mut atlas = Canvas::new(4, 4);
+        drawing::rect::fill_rect(&mut atlas, &white, 0, 0, 3, 3);
+        atlas
+    };
+
+    // Text format
+    let text = BitmapFont::from_bmfont(atlas(), b"info face=\"Test Font\" size=4\n\
+        common lineHeight=5 base=4 scaleW=4 scaleH=4 pages=1\n\
+        page id=0 file=\"test font.png\"\n\
+        char id=65 x=0 y=0 width=2 height=2 xoffset=0 yoffset=1 xadvance=3 page=0\n\
+        char id=66 x=2 y=0 width=2 height=2 xoffset=1 yoffset=0 xadvance=3 page=0\n\
+        kerning first=65 second=66 amount=-1\n").unwrap();
+    assert_eq!((text.line_height(), text.base(), text.glyph_count()), (5, 4, 2));
+    assert_eq!(text.kerning('A', 'B'), -1);
+    assert_eq!(text.kerning('B', 'A'), 0);
+    assert_eq!(font::bmfont::bmfont_pages(b"page id=0 file=\"test font.png\"").unwrap(), vec!["test font.png"]);
+    let pages = font::bmfont::bmfont_pages(b"page id=99999999999999 file=b.png\npage id=2 file=a.png\npage id=2 file=c.png");
+    assert_eq!(pages.unwrap(), vec!["c.png", "b.png"]);
+
+    // Binary format with the same contents
+    let mut bin = b"BMF\x03".to_vec();
+    bin.extend(&[2, 4, 0, 0, 0, 5, 0, 4, 0]);
+    bin.extend(&[3, 14, 0, 0, 0]);
+    bin.extend(b"test font.png\0");
+    bin.extend(&[4, 40, 0, 0, 0]);
+    bin.extend(&[65, 0, 0, 0, 0, 0, 0, 0, 2, 0, 2, 0, 0, 0, 1, 0, 3, 0, 0, 15]);
+    bin.extend(&[66, 0, 0, 0, 2, 0, 0, 0, 2, 0, 2, 0, 1, 0, 0, 0, 3, 0, 0, 15]);
+    bin.extend(&[5, 10, 0, 0, 0, 65, 0, 0, 0, 66, 0, 0, 0, 0xFF, 0xFF]);
+    let binary = BitmapFont::from_bmfont(atlas(), &bin).unwrap();
+    assert_eq!(binary.glyphs(), text.glyphs());
+    assert_eq!(binary.kerning('A', 'B'), -1);
+    assert_eq!(font::bmfont::bmfont_pages(&bin).unwrap(), vec!["test font.png"]);
+
+    // Kerning moves 'B' left by one pixel
+    let mut canv = Canvas::new(8, 4);
+    canv.draw_text(&binary, "AB", 0, 0, &white, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &white, &vec![(0, 1), (1, 2), (3, 0), (4, 1)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(0, 0), (5, 0)]);
+
+    // Errors
+    assert!(BitmapFont::from_bmfont(atlas(), b"char id=65 x=0 y=0 width=2 height=2 page=1").is_err());
+    assert!(BitmapFont::from_bmfont(atlas(), b"char id=A").is_err());
+    assert!(BitmapFont::from_bmfont(atlas(), b"BMF\x03\x04\x10\x00").is_err());
+    let huge = BitmapFont::from_bmfont(atlas(), b"BMF\x03\x04\xFF\xFF\xFF\xFF");
+    assert_eq!(huge.err().unwrap().message(), "truncated block 4");
+}
+
+#[wasm_bindgen_test]
+fn font_load_psf() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+
+    // PSF1 with a Unicode table mapping glyph 1 to both 'A' and 'a'
+    let mut psf1 = vec![0x36, 0x04, 0x02, 2];
+    psf1.extend(vec![0; 256 * 2]);
+    psf1[4 + 2] = 0b1000_0000;
+    psf1[4 + 3] = 0b0100_0000;
+    for i in 0..256u16 {
+        let chars: Vec<u16> = if i == 1 { vec![0x41, 0x61, 0xFFFE, 0x41, 0x42] } else { vec![0x100 + i] };
+        for c in chars.iter().chain(&[0xFFFF]) {
+            psf1.extend(&c.to_le_bytes());
+        }
+    }
+    let font = BitmapFont::from_psf(&psf1).unwrap();
+    assert_eq!(font.line_height(), 2);
+    assert_eq!(font.glyph('A'), font.glyph('a').map(|g| font::Glyph { ch: 'A', ..*g }).as_ref());
+    assert!(font.glyph('B').is_none());
+    let mut canv = Canvas::new(16, 2);
+    canv.draw_text(&font, "aA", 0, 0, &white, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &white, &vec![(0, 0), (1, 1), (8, 0), (9, 1)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(1, 0), (0, 1), (2, 0)]);
+
+    // PSF2 with 10-pixel wide glyphs and no Unicode table
+    let mut psf2 = vec![0x72, 0xB5, 0x4A, 0x86];
+    for x in &[0u32, 32, 0, 2, 4, 2, 10] {
+        psf2.extend(&x.to_le_bytes());
+    }
+    psf2.extend(&[0, 0, 0, 0, 0x00, 0x40, 0x80, 0x00]);
+    let font = BitmapFont::from_psf(&psf2).unwrap();
+    assert_eq!(font.glyph_count(), 2);
+    let mut canv = Canvas::new(10, 2);
+    canv.draw_text(&font, "\u{1}", 0, 0, &white, TextAlign::Left);
+    assert_pixels_with_colour(&canv, &white, &vec![(9, 0), (0, 1)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(8, 0), (1, 1)]);
+
+    // Errors
+    assert!(BitmapFont::from_psf(&[0x36, 0x04, 0x00, 8, 0]).is_err());
+    assert!(BitmapFont::from_psf(&psf2[..36]).is_err());
+    assert!(BitmapFont::from_psf(b"not a font").is_err());
+}
+
+/// Builds a minimal TrueType font with units per em of 1000 containing a square 'A', an 'O' made
+/// entirely of off-curve points and a composite 'B' containing a scaled and offset 'A'
+fn build_test_ttf() -> Vec<u8> {
+    let be16 = |v: &mut Vec<u8>, x: i32| v.extend(&(x as u16).to_be_bytes());
+
+    let mut head = vec![0; 54];
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    let mut maxp = vec![0, 0, 0x50, 0];
+    be16(&mut maxp, 4);
+    let mut hhea = vec![0; 36];
+    hhea[4..6].copy_from_slice(&800i16.to_be_bytes());
+    hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+    hhea[34..36].copy_from_slice(&4u16.to_be_bytes());
+    let mut hmtx = vec![];
+    for _ in 0..4 {
+        be16(&mut hmtx, 1000);
+        be16(&mut hmtx, 0);
+    }
+
+    // Format 4 cmap with one segment per character
+    let segments = [(65, 1), (66, 3), (79, 2), (0xFFFF, 0)];
+    let mut cmap = vec![];
+    for x in &[0, 1, 3, 1, 0, 12, 4, 0, 0] {
+        be16(&mut cmap, *x);
+    }
+    be16(&mut cmap, segments.len() as i32 * 2);
+    for _ in 0..3 {
+        be16(&mut cmap, 0);
+    }
+    segments.iter().for_each(|s| be16(&mut cmap, s.0));
+    be16(&mut cmap, 0);
+    segments.iter().for_each(|s| be16(&mut cmap, s.0));
+    segments.iter().for_each(|s| be16(&mut cmap, if s.0 == 0xFFFF { 1 } else { s.1 - s.0 }));
+    segments.iter().for_each(|_| be16(&mut cmap, 0));
+
+    let square = |on_curve: u8| {
+        let mut g = vec![];
+        for x in &[1, 0, 0, 800, 800, 3, 0] {
+            be16(&mut g, *x);
+        }
+        g.extend(&[on_curve; 4]);
+        for x in &[0, 0, 800, 0, 0, 800, 0, -800] {
+            be16(&mut g, *x);
+        }
+        g
+    };
+    let glyph_a = square(1);
+    let glyph_o = square(0);
+    let mut glyph_b = vec![];
+    for x in &[-1, 0, 0, 800, 800, 0x000B, 1, 200, 0, 0x2000] {
+        be16(&mut glyph_b, *x);
+    }
+    let mut glyf = vec![];
+    let mut loca = vec![0, 0, 0, 0];
+    for g in &[glyph_a, glyph_o, glyph_b] {
+        glyf.extend(g);
+        be16(&mut loca, glyf.len() as i32 / 2);
+    }
+
+    let tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"cmap", cmap),
+        (b"glyf", glyf),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", hmtx),
+        (b"loca", loca),
+        (b"maxp", maxp),
+    ];
+    let mut font = vec![0, 1, 0, 0];
+    be16(&mut font, tables.len() as i32);
+    font.extend(&[0; 6]);
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in &tables {
+        font.extend(*tag);
+        font.extend(&[0; 4]);
+        font.extend(&(offset as u32).to_be_bytes());
+        font.extend(&(data.len() as u32).to_be_bytes());
+        offset += data.len();
+    }
+    for (_, data) in tables {
+        font.extend(data);
+    }
+    font
+}
+
+#[wasm_bindgen_test]
+fn font_truetype() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+
+    let data = build_test_ttf();
+    let ttf = TrueTypeFont::from_bytes(&data).unwrap();
+    assert_eq!((ttf.units_per_em(), ttf.glyph_count(), ttf.ascender(), ttf.descender()), (1000, 4, 800, -200));
+    assert_eq!((ttf.glyph_index('A'), ttf.glyph_index('O'), ttf.glyph_index('B')), (1, 2, 3));
+    assert_eq!(ttf.glyph_index('Z'), 0);
+    assert_eq!(ttf.advance_width(1), 1000);
+
+    // 10 pixels per em: glyphs are 8x8 pixels with the baseline 8 pixels down
+    let mut cache = GlyphCache::new(ttf, 10.0);
+    assert_eq!((cache.font().line_height(), cache.font().base()), (10, 8));
+    let mut canv = Canvas::new(30, 10);
+    cache.draw_text(&mut canv, "AOB", 0, 0, &white, TextAlign::Left);
+    assert_eq!(cache.font().glyph_count(), 3);
+
+    // Square glyph with edges on pixel boundaries
+    assert_pixels_with_colour(&canv, &white, &vec![(0, 0), (7, 0), (0, 7), (7, 7)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(8, 0), (0, 8)]);
+
+    // Curved glyph has solid centre, empty corners and anti-aliased edges
+    assert_pixels_with_colour(&canv, &white, &vec![(14, 4), (13, 3)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(10, 0), (17, 7)]);
+    assert!((10..18).any(|x| {
+        let a = canv.buffer()[canv.buffer_index(x, 1)] >> 24;
+        a > 0 && a < 255
+    }));
+
+    // Composite glyph scaled by 0.5 and offset by 200 units
+    assert_pixels_with_colour(&canv, &white, &vec![(22, 4), (25, 7)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(21, 4), (22, 3), (26, 7)]);
+
+    // Cached glyphs are reused
+    cache.prepare("AAA");
+    assert_eq!(cache.font().glyph_count(), 3);
+
+    // Errors
+    let mut cff = data.clone();
+    cff[28..32].copy_from_slice(b"CFF ");
+    assert_eq!(TrueTypeFont::from_bytes(&cff).err().unwrap().message(), "CFF outlines are not supported");
+    assert!(TrueTypeFont::from_bytes(b"not a font").is_err());
+    assert!(TrueTypeFont::from_bytes(&data[..100]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn drawing_fill_path() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+    let rect_path = |x1: f64, y1: f64, x2: f64, y2: f64| {
+        let mut p = drawing::path::Path::new();
+        p.move_to(x1, y1);
+        p.line_to(x2, y1);
+        p.line_to(x2, y2);
+        p.line_to(x1, y2);
+        p.close();
+        p
+    };
+
+    // Edges on pixel boundaries are not anti-aliased
+    let mut canv = Canvas::new(8, 8);
+    drawing::path::fill_path(&mut canv, &white, &rect_path(2.0, 2.0, 6.0, 6.0));
+    assert_pixels_with_colour(&canv, &white, &vec![(2, 2), (5, 5)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(1, 2), (6, 5), (2, 6)]);
+
+    // Half-covered pixels are blended with the existing contents
+    let mut canv = Canvas::new(8, 8);
+    canv.clear(&ARGBColour::new(255, 0, 0, 0));
+    drawing::path::fill_path(&mut canv, &white, &rect_path(2.5, 0.0, 8.0, 8.0));
+    assert_pixels_with_colour(&canv, &ARGBColour::new(255, 128, 128, 128), &vec![(2, 0), (2, 7)]);
+    assert_pixels_with_colour(&canv, &white, &vec![(3, 0)]);
+
+    // Overlapping contours with the same winding are filled once (non-zero rule)
+    let mut path = rect_path(0.0, 0.0, 4.0, 4.0);
+    path.move_to(2.0, 2.0);
+    path.quad_to(4.0, 2.0, 6.0, 2.0);
+    path.line_to(6.0, 6.0);
+    path.line_to(2.0, 6.0);
+    let mut canv = Canvas::new(8, 8);
+    drawing::path::fill_path(&mut canv, &ARGBColour::new(128, 255, 255, 255), &path);
+    assert_pixels_with_colour(&canv, &ARGBColour::new(128, 255, 255, 255), &vec![(0, 0), (3, 3), (5, 5)]);
+
+    // Canvas transform is applied to the path
+    let mut canv = Canvas::new(8, 8);
+    canv.translate(4.0, 4.0);
+    drawing::path::fill_path(&mut canv, &white, &rect_path(0.0, 0.0, 2.0, 2.0));
+    assert_pixels_with_colour(&canv, &white, &vec![(4, 4), (5, 5)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(0, 0), (6, 6)]);
+}
+
+#[wasm_bindgen_test]
+fn font_layout_text() {
+    let white = ARGBColour::new(255, 255, 255, 255);
+    let empty = ARGBColour::new(0, 0, 0, 0);
+
+    // Fixed font of solid 2x2 glyphs for all printable ASCII characters, with a blank space
+    let mut atlas = Canvas::new(2 * 95, 2);
+    drawing::rect::fill_rect(&mut atlas, &white, 2, 0, 2 * 95 - 1, 1);
+    let mut font = BitmapFont::fixed(atlas, 2, 2, 32);
+    font.set_line_spacing(1);
+
+    // Measurement
+    let metrics = font.measure_text("ab\nabcd\n");
+    assert_eq!((metrics.width, metrics.height, metrics.line_count), (8, 8, 3));
+    assert_eq!((metrics.line_advance, metrics.base), (3, 2));
+    assert_eq!(font.measure_text("").width, 0);
+
+    // Word wrap, explicit newlines and breaking of long words
+    let layout = font.layout_text("aa bb cc\nddddddd", 10, 100, TextAlign::Left, false);
+    let lines: Vec<String> = (0..layout.line_count()).map(|i| layout.line_text(i)).collect();
+    assert_eq!(lines, vec!["aa bb", "cc", "ddddd", "dd"]);
+    assert_eq!((layout.height(), layout.truncated()), (11, false));
+
+    // Truncation with an ellipsis
+    let layout = font.layout_text("aa bb cc dd ee", 10, 5, TextAlign::Left, true);
+    assert_eq!(layout.line_count(), 2);
+    assert_eq!(layout.line_text(1), "cc...");
+    assert!(layout.truncated());
+    let layout = font.layout_text("aa bb cc dd ee", 10, 5, TextAlign::Left, false);
+    assert_eq!(layout.line_text(1), "cc dd");
+    assert!(font.layout_text("aa", 10, 5, TextAlign::Left, true).line_text(0) == "aa");
+
+    // Alignment
+    let layout = font.layout_text("a\nbb", 10, 100, TextAlign::Right, false);
+    assert_eq!(layout.lines()[0].glyphs, vec![('a', 8)]);
+    assert_eq!(layout.lines()[1].glyphs, vec![('b', 6), ('b', 8)]);
+    let layout = font.layout_text("a\nbb", 10, 100, TextAlign::Center, false);
+    assert_eq!(layout.lines()[0].glyphs, vec![('a', 4)]);
+    let layout = font.layout_text("a b", 20, 100, TextAlign::Right, false);
+    assert_eq!(layout.lines()[0].glyphs, vec![('a', 14), (' ', 16), ('b', 18)]);
+    let layout = font.layout_text("a b", 20, 100, TextAlign::Center, false);
+    assert_eq!(layout.lines()[0].glyphs, vec![('a', 7), (' ', 9), ('b', 11)]);
+
+    // Justified lines fill the width, except the last line of each paragraph
+    let layout = font.layout_text("a b c d", 8, 100, TextAlign::Justify, false);
+    assert_eq!(layout.line_text(0), "a b");
+    assert_eq!(layout.lines()[0].glyphs.last(), Some(&('b', 6)));
+    assert_eq!(layout.lines()[1].glyphs.last(), Some(&('d', 4)));
+
+    let mut canv = Canvas::new(12, 8);
+    canv.draw_text_layout(&font, &layout, 2, 1, &white);
+    assert_pixels_with_colour(&canv, &white, &vec![(2, 1), (8, 1), (9, 2), (2, 4), (6, 4)]);
+    assert_pixels_with_colour(&canv, &empty, &vec![(4, 1), (10, 1), (8, 4), (2, 3)]);
+}
+
+#[wasm_bindgen_test]
+fn image_to_png() {
+    let mut canv = Canvas::new(20, 10);
+    canv.clear(&ARGBColour::new(128, 255, 0, 64));
+    canv.buffer_mut()[0] = 0x01020304;
+    let data = canv.to_png().unwrap();
+    assert_eq!(&data[0..8], &png::SIGNATURE);
+
+    // Walk the chunks, checking lengths and CRCs
+    let mut pos = 8;
+    let mut types = vec![];
+    while pos < data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let body = &data[pos + 4..pos + 8 + len];
+        let crc = &data[pos + 8 + len..pos + 12 + len];
+        assert_eq!(crc, &png::crc32(body).to_be_bytes());
+        types.push(String::from_utf8(body[..4].to_vec()).unwrap());
+        if &body[..4] == b"IHDR" {
+            assert_eq!(&body[4..17], &[0, 0, 0, 20, 0, 0, 0, 10, 8, 6, 0, 0, 0]);
+        }
+        pos += 12 + len;
+    }
+    assert_eq!(types, vec!["IHDR", "IDAT", "IEND"]);
+    assert_eq!(png::crc32(b"IEND"), 0xAE42_6082);
+
+    // Empty canvases can't be encoded
+    assert_eq!(Canvas::new(0, 0).to_png().err().unwrap().message(), "PNG images must not be empty");
+    assert!(Canvas::new(4, 0).to_png().is_err());
+}
+
+#[wasm_bindgen_test]
+fn image_from_png() {
+    fn chunk(out: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+        out.extend(&(data.len() as u32).to_be_bytes());
+        let body = [chunk_type, data].concat();
+        out.extend(&body);
+        out.extend(&png::crc32(&body).to_be_bytes());
+    }
+
+    // Round trip through the encoder keeps every pixel, including alpha
+    let canv = hashed_canvas(13, 7);
+    let decoded = Canvas::from_png(&canv.to_png().unwrap()).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (13, 7));
+    assert_eq!(decoded.buffer(), canv.buffer());
+
+    // Builds a PNG file with the given header fields and ancillary chunks, storing the scanlines
+    // uncompressed
+    fn png_file(width: u8, height: u8, header: [u8; 5], chunks: &[(&[u8], &[u8])], rows: &[u8]) -> Vec<u8> {
+        let mut zlib = vec![0x78, 0x01, 1, rows.len() as u8, 0, !(rows.len() as u8), 0xFF];
+        zlib.extend(rows);
+        let (a, b) = rows.iter().fold((1u32, 0u32), |(a, b), x| (a + u32::from(*x), b + a + u32::from(*x)));
+        zlib.extend(&((b << 16) | a).to_be_bytes());
+
+        let mut data = png::SIGNATURE.to_vec();
+        chunk(&mut data, b"IHDR", &[&[0, 0, 0, width, 0, 0, 0, height][..], &header].concat());
+        for (chunk_type, body) in chunks {
+            chunk(&mut data, chunk_type, body);
+        }
+        chunk(&mut data, b"IDAT", &zlib);
+        chunk(&mut data, b"IEND", &[]);
+        data
+    }
+
+    // 3x2 2-bit palette image with a transparent palette entry
+    let rows = [0u8, 0b00_01_10_00, 0, 0b11_11_00_00];
+    let plte: &[u8] = &[255, 0, 0, 0, 255, 0, 0, 0, 255, 10, 20, 30];
+    let data = png_file(3, 2, [2, 3, 0, 0, 0], &[(b"PLTE", plte), (b"tRNS", &[0])], &rows);
+    let canv = Canvas::from_png(&data).unwrap();
+    assert_eq!(canv.buffer(), &vec![0x00FF0000, 0xFF00FF00, 0xFF0000FF, 0xFF0A141E, 0xFF0A141E, 0x00FF0000]);
+
+    // 8-bit greyscale image with a transparent grey level
+    let grey = png_file(3, 1, [8, 0, 0, 0, 0], &[(b"tRNS", &[0, 0x80])], &[0, 0x00, 0x80, 0xFF]);
+    assert_eq!(Canvas::from_png(&grey).unwrap().buffer(), &vec![0xFF000000, 0x00808080, 0xFFFFFFFF]);
+
+    // 16-bit RGB image with a transparent colour, which is compared at full precision
+    let trns: &[u8] = &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+    let rows = [&[0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC][..], trns, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x07]].concat();
+    let rgb = png_file(3, 1, [16, 2, 0, 0, 0], &[(b"tRNS", trns)], &rows);
+    assert_eq!(Canvas::from_png(&rgb).unwrap().buffer(), &vec![0xFF12569A, 0x00010305, 0xFF010305]);
+
+    // 3x3 Adam7 interlaced greyscale image, where some passes are empty
+    let passes = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+    let level = |x: usize, y: usize| (20 * (y * 3 + x) + 10) as u8;
+    let mut rows = vec![];
+    for (x0, y0, dx, dy) in passes {
+        for y in (y0..3).step_by(dy) {
+            let xs: Vec<usize> = (x0..3).step_by(dx).collect();
+            if !xs.is_empty() {
+                rows.push(0);
+                rows.extend(xs.iter().map(|x| level(*x, y)));
+            }
+        }
+    }
+    let interlaced = png_file(3, 3, [8, 0, 0, 0, 1], &[], &rows);
+    let canv = Canvas::from_png(&interlaced).unwrap();
+    let expected: Vec<u32> = (0..9).map(|i| 0xFF000000 | u32::from(level(i % 3, i / 3)) * 0x010101).collect();
+    assert_eq!(canv.buffer(), &expected);
+
+    // Corrupt input
+    assert_eq!(decode_error(Canvas::from_png, b"GIF89a"), "not a PNG file");
+    let mut bad_crc = data.clone();
+    bad_crc[20] ^= 1;
+    assert_eq!(decode_error(Canvas::from_png, &bad_crc), "CRC mismatch in PNG IHDR chunk");
+    assert_eq!(decode_error(Canvas::from_png, &data[..data.len() - 12]), "PNG file has no IEND chunk");
+    let mut no_plte = data.clone();
+    no_plte.splice(33..57, vec![]);
+    assert_eq!(decode_error(Canvas::from_png, &no_plte), "palette PNG has no PLTE chunk");
+
+    // Image data beyond the size given by the header is rejected, whether stored or compressed
+    let with_height = |data: &[u8], height: u8| {
+        let mut out = data.to_vec();
+        out[23] = height;
+        let crc = png::crc32(&out[12..29]);
+        out[29..33].copy_from_slice(&crc.to_be_bytes());
+        out
+    };
+    assert_eq!(decode_error(Canvas::from_png, &with_height(&data, 1)), "decompressed data is larger than expected");
+    let mut canv = Canvas::new(16, 16);
+    canv.clear(&ARGBColour::new(255, 255, 0, 0));
+    let compressed = with_height(&canv.to_png().unwrap(), 15);
+    assert_eq!(decode_error(Canvas::from_png, &compressed), "decompressed data is larger than expected");
+}
+
+#[wasm_bindgen_test]
+fn image_bmp_tga() {
+    let canv = hashed_canvas(5, 3);
+    let opaque: Vec<u32> = canv.buffer().iter().map(|px| px | 0xFF00_0000).collect();
+    assert_eq!(Canvas::from_bmp(&canv.to_bmp(true)).unwrap().buffer(), canv.buffer());
+    assert_eq!(Canvas::from_bmp(&canv.to_bmp(false)).unwrap().buffer(), &opaque);
+    assert_eq!(Canvas::from_tga(&canv.to_tga(true).unwrap()).unwrap().buffer(), canv.buffer());
+    assert_eq!(Canvas::from_tga(&canv.to_tga(false).unwrap()).unwrap().buffer(), canv.buffer());
+    let too_wide = Canvas::new(65536, 1).to_tga(false);
+    assert_eq!(too_wide.err().unwrap().message(), "TGA images must be at most 65535 pixels wide and high");
+
+    // 4x3 bottom-up RLE8 BMP with a two colour palette, ending early in the middle row
+    let bmp_header = |bpp: u16, compression: u32, width: i32, height: i32, offset: u32| {
+        let mut out = b"BM".to_vec();
+        out.extend(&[0; 8]);
+        out.extend(&offset.to_le_bytes());
+        out.extend(&40u32.to_le_bytes());
+        out.extend(&width.to_le_bytes());
+        out.extend(&height.to_le_bytes());
+        out.extend(&1u16.to_le_bytes());
+        out.extend(&bpp.to_le_bytes());
+        out.extend(&compression.to_le_bytes());
+        out.extend(&[0; 20]);
+        out
+    };
+    let mut rle = bmp_header(8, 1, 4, 3, 62);
+    rle.extend(&[0, 0, 255, 0, 255, 0, 0, 0]);
+    rle.extend(&[3, 1, 0, 0, 0, 3, 0, 1, 1, 0, 0, 1]);
+    let (red, blue) = (0xFFFF_0000, 0xFF00_00FF);
+    assert_eq!(
+        Canvas::from_bmp(&rle).unwrap().buffer(),
+        &vec![0, 0, 0, 0, red, blue, blue, 0, blue, blue, blue, 0]
+    );
+
+    // 2x2 top-down 32-bit BMP, where the unused fourth byte is ignored
+    let mut top_down = bmp_header(32, 0, 2, -2, 54);
+    top_down.extend(&[1, 2, 3, 0, 4, 5, 6, 0, 7, 8, 9, 0, 10, 11, 12, 0]);
+    assert_eq!(
+        Canvas::from_bmp(&top_down).unwrap().buffer(),
+        &vec![0xFF030201, 0xFF060504, 0xFF090807, 0xFF0C0B0A]
+    );
+
+    // 3x2 bottom-left RLE TGA with a colour map of 24-bit entries
+    let mut tga = vec![0, 1, 9, 0, 0, 2, 0, 24, 0, 0, 0, 0, 3, 0, 2, 0, 8, 0];
+    tga.extend(&[0, 0, 255, 255, 0, 0]);
+    tga.extend(&[0x82, 1, 0x02, 0, 1, 0]);
+    assert_eq!(Canvas::from_tga(&tga).unwrap().buffer(), &vec![red, blue, red, blue, blue, blue]);
+
+    assert_eq!(decode_error(Canvas::from_tga, &tga[..26]), "TGA pixel data is truncated");
+    tga[27] = 5;
+    assert_eq!(decode_error(Canvas::from_tga, &tga), "TGA colour map index out of range");
+    assert_eq!(decode_error(Canvas::from_bmp, &rle[..66]), "truncated BMP RLE data");
+}
+
+#[wasm_bindgen_test]
+fn image_netpbm() {
+    let mut canv = Canvas::new(3, 2);
+    canv.load_pixels(vec![0xFFFF0000, 0x8000FF00, 0x000000FF, 0xFFFFFFFF, 0xFF000000, 0x40808080]);
+    let opaque: Vec<u32> = canv.buffer().iter().map(|px| px | 0xFF00_0000).collect();
+
+    // PAM keeps alpha, PPM discards it
+    assert_eq!(Canvas::from_netpbm(&canv.to_netpbm(NetpbmFormat::Pam)).unwrap().buffer(), canv.buffer());
+    assert_eq!(Canvas::from_netpbm(&canv.to_netpbm(NetpbmFormat::Ppm)).unwrap().buffer(), &opaque);
+    assert_eq!(Canvas::from_netpbm(&canv.to_netpbm(NetpbmFormat::PlainPpm)).unwrap().buffer(), &opaque);
+    assert_eq!(
+        String::from_utf8(canv.to_netpbm(NetpbmFormat::PlainPpm)).unwrap(),
+        "P3\n3 2\n255\n255 0 0 0 255 0 0 0 255\n255 255 255 0 0 0 128 128 128\n"
+    );
+
+    // Greyscale uses the luma of each pixel
+    let grey = vec![0xFF4C4C4C, 0xFF969696, 0xFF1D1D1D, 0xFFFFFFFF, 0xFF000000, 0xFF808080];
+    assert_eq!(Canvas::from_netpbm(&canv.to_netpbm(NetpbmFormat::Pgm)).unwrap().buffer(), &grey);
+    assert_eq!(Canvas::from_netpbm(&canv.to_netpbm(NetpbmFormat::PlainPgm)).unwrap().buffer(), &grey);
+
+    // 16-bit samples, comments and greyscale with alpha
+    let mut p5 = b"P5 # 16-bit\n2 1\n65535\n".to_vec();
+    p5.extend(&[0xFF, 0xFF, 0x80, 0x00]);
+    assert_eq!(Canvas::from_netpbm(&p5).unwrap().buffer(), &vec![0xFFFFFFFF, 0xFF808080]);
+    let mut pam = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 2\nMAXVAL 15\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n".to_vec();
+    pam.extend(&[5, 10]);
+    assert_eq!(Canvas::from_netpbm(&pam).unwrap().buffer(), &vec![0xAA555555]);
+
+    assert_eq!(decode_error(Canvas::from_netpbm, b"P1\n1 1\n1\n"), "not a supported Netpbm file");
+    assert_eq!(decode_error(Canvas::from_netpbm, b"P3\n2 1 255\n1 2 3\n"), "invalid or missing Netpbm sample");
+    assert_eq!(decode_error(Canvas::from_netpbm, b"P2\n1 1 15\n16\n"), "Netpbm sample exceeds the maximum value");
+    assert_eq!(decode_error(Canvas::from_netpbm, &p5[..p5.len() - 1]), "Netpbm pixel data is truncated");
+}
+
+#[wasm_bindgen_test]
+fn image_qoi() {
+    // Runs, small differences, repeated colours and alpha changes all round trip exactly
+    let mut canv = Canvas::new(70, 3);
+    for (i, px) in canv.buffer_mut().iter_mut().enumerate() {
+        *px = match i {
+            0..=63 => 0xFF102030,
+            64..=99 => 0xFF102030 + (i as u32 % 4) * 0x0001_0101,
+            100..=149 => hashed_pixel(i),
+            _ => 0x80000000 | (i as u32 % 3) * 0x0020_1008,
+        };
+    }
+    let data = canv.to_qoi();
+    assert_eq!(&data[0..14], &[b'q', b'o', b'i', b'f', 0, 0, 0, 70, 0, 0, 0, 3, 4, 0]);
+    assert_eq!(&data[data.len() - 8..], &[0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(Canvas::from_qoi(&data).unwrap().buffer(), canv.buffer());
+
+    // 3-channel image: RGB, run of 2, DIFF (+1, 0, -1), LUMA (dg = 10, dr - dg = -2, db - dg = 3), INDEX
+    let mut rgb = vec![b'q', b'o', b'i', b'f', 0, 0, 0, 6, 0, 0, 0, 1, 3, 0];
+    rgb.extend(&[0xFE, 100, 100, 100, 0xC1, 0x40 | 3 << 4 | 2 << 2 | 1, 0x80 | 42, 6 << 4 | 11, 17]);
+    rgb.extend(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(
+        Canvas::from_qoi(&rgb).unwrap().buffer(),
+        &vec![0xFF646464, 0xFF646464, 0xFF646464, 0xFF656463, 0xFF6D6E70, 0xFF646464]
+    );
+
+    assert_eq!(decode_error(Canvas::from_qoi, b"qoi"), "not a QOI file");
+    assert_eq!(decode_error(Canvas::from_qoi, &rgb[..20]), "QOI pixel data is truncated");
+    rgb[12] = 5;
+    assert_eq!(decode_error(Canvas::from_qoi, &rgb), "invalid QOI channel count");
+}
+
+#[wasm_bindgen_test]
+fn image_gif_encoder() {
+    let mut gif = GifEncoder::new(4, 2, 5).unwrap();
+    let mut frame = Canvas::new(4, 2);
+    frame.load_pixels(vec![0xFFFF0000, 0xFF00FF00, 0x00000000, 0xFFFF0000, 0xFF0000FF, 0xFF0000FF, 0x40FFFFFF, 0xFF00FF00]);
+    gif.add_frame(&frame, 20).unwrap();
+    frame.clear(&ARGBColour::new(255, 1, 2, 3));
+    gif.add_frame(&frame, 7).unwrap();
+    assert_eq!(
+        gif.add_frame(&Canvas::new(2, 2), 7).err().unwrap().message(),
+        "GIF frame size does not match the animation size"
+    );
+    assert_eq!(
+        GifEncoder::new(65536, 1, 0).err().unwrap().message(),
+        "GIF images must be at most 65535 pixels wide and high"
+    );
+    assert!(GifEncoder::new(1, 65536, 0).is_err());
+    let data = gif.finish();
+
+    // Header, logical screen and looping extension
+    assert_eq!(&data[0..13], b"GIF89a\x04\x00\x02\x00\x00\x00\x00");
+    assert_eq!(&data[13..32], b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x05\x00\x00");
+
+    // First frame: three colours plus a transparent index, so a 4 entry colour table
+    assert_eq!(&data[32..40], &[0x21, 0xF9, 4, 0x09, 20, 0, 3, 0]);
+    assert_eq!(&data[40..50], &[0x2C, 0, 0, 0, 0, 4, 0, 2, 0, 0x81]);
+    assert_eq!(&data[50..62], &[0, 0, 255, 0, 255, 0, 255, 0, 0, 0, 0, 0]);
+
+    // Skip the image data sub-blocks of the first frame
+    let mut pos = 63;
+    while data[pos] != 0 {
+        pos += data[pos] as usize + 1;
+    }
+    pos += 1;
+
+    // Second frame: a single opaque colour with no transparency
+    assert_eq!(&data[pos..pos + 8], &[0x21, 0xF9, 4, 0x08, 7, 0, 0, 0]);
+    assert_eq!(data[pos + 17], 0x80);
+    assert_eq!(&data[pos + 18..pos + 24], &[1, 2, 3, 0, 0, 0]);
+    assert_eq!(data.last(), Some(&0x3B));
+}
+
+#[wasm_bindgen_test]
+fn image_video_writers() {
+    // 3x3 red frame with a white bottom-right pixel, which alone covers the last chroma block
+    let mut frame = Canvas::new(3, 3);
+    frame.clear(&ARGBColour::new(255, 255, 0, 0));
+    frame.buffer_mut()[8] = 0xFFFFFFFF;
+
+    let mut y4m = Y4mWriter::new(vec![], 3, 3, 25, 1).unwrap();
+    y4m.write_frame(&frame).unwrap();
+    y4m.write_frame(&frame).unwrap();
+    assert!(y4m.write_frame(&Canvas::new(2, 2)).is_err());
+    let data = y4m.into_inner();
+    let header = b"YUV4MPEG2 W3 H3 F25:1 Ip A1:1 C420jpeg\n";
+    assert_eq!(&data[..header.len()], &header[..]);
+
+    let frame_data = &data[header.len()..];
+    assert_eq!(frame_data.len(), 2 * (6 + 9 + 4 + 4));
+    assert_eq!(&frame_data[..6], b"FRAME\n");
+    assert_eq!(&frame_data[6..15], &[82, 82, 82, 82, 82, 82, 82, 82, 235]);
+    assert_eq!(&frame_data[15..19], &[90, 90, 90, 128]);
+    assert_eq!(&frame_data[19..23], &[240, 240, 240, 128]);
+    assert_eq!(&frame_data[..23], &frame_data[23..]);
+
+    let mut raw = RawRgbaWriter::new(vec![], 3, 3);
+    raw.write_frame(&frame).unwrap();
+    let data = raw.into_inner();
+    assert_eq!(data.len(), 36);
+    assert_eq!(&data[28..36], &[255, 0, 0, 255, 255, 255, 255, 255]);
+}
+
+#[wasm_bindgen_test]
+fn edit_crop_and_resize() {
+    let (red, green, blue, white) = (0xFFFF0000, 0xFF00FF00, 0xFF0000FF, 0xFFFFFFFF);
+    let mut canv = Canvas::new(2, 2);
+    canv.load_pixels(vec![red, green, blue, white]);
+
+    // Cropping, with areas outside the source left transparent
+    assert_eq!(canv.crop(1, 0, 1, 2).buffer(), &vec![green, white]);
+    assert_eq!(canv.crop(1, 1, 2, 2).buffer(), &vec![white, 0, 0, 0]);
+    assert_eq!(canv.crop(5, 5, 1, 1).buffer(), &vec![0]);
+
+    // Nearest neighbour doubles each pixel
+    let mut big = canv.crop(0, 0, 2, 2);
+    big.resize(4, 2, ResizeFilter::Nearest);
+    assert_eq!(big.buffer(), &vec![red, red, green, green, blue, blue, white, white]);
+
+    // Bilinear interpolates between pixel centres and clamps at the edges
+    let mut ramp = Canvas::new(2, 1);
+    ramp.load_pixels(vec![0xFF000000, 0xFFFFFFFF]);
+    ramp.resize(4, 1, ResizeFilter::Bilinear);
+    assert_eq!(ramp.buffer(), &vec![0xFF000000, 0xFF404040, 0xFFBFBFBF, 0xFFFFFFFF]);
+
+    // Shrinking averages the covered pixels
+    for filter in &[ResizeFilter::Bilinear, ResizeFilter::Bicubic, ResizeFilter::Lanczos] {
+        let mut checks = Canvas::new(8, 8);
+        for (i, px) in checks.buffer_mut().iter_mut().enumerate() {
+            *px = if (i % 8 + i / 8) % 2 == 0 { 0xFF000000 } else { 0xFFFFFFFF };
+        }
+        checks.resize(2, 2, *filter);
+        assert!(checks.buffer().iter().all(|px| (px & 0xFF) >= 0x7C && (px & 0xFF) <= 0x83));
+    }
+
+    // Colour doesn't bleed from transparent pixels
+    let mut edge = Canvas::new(2, 1);
+    edge.load_pixels(vec![red, 0]);
+    edge.resize(4, 1, ResizeFilter::Bicubic);
+    assert!(edge.buffer().iter().all(|px| px >> 24 == 0 || px & 0x00FF_FFFF == 0x00FF_0000));
+
+    // Changing the canvas size keeps the content at the anchor
+    let mut anchored = canv.crop(0, 0, 2, 2);
+    anchored.resize_canvas(4, 3, Anchor::Center);
+    assert_eq!((anchored.width(), anchored.height()), (4, 3));
+    assert_eq!(anchored.buffer(), &vec![0, red, green, 0, 0, blue, white, 0, 0, 0, 0, 0]);
+    anchored.resize_canvas(1, 1, Anchor::TopRight);
+    assert_eq!(anchored.buffer(), &vec![0]);
+    let mut shrunk = canv.crop(0, 0, 2, 2);
+    shrunk.resize_canvas(1, 1, Anchor::BottomLeft);
+    assert_eq!(shrunk.buffer(), &vec![blue]);
+}
+
+#[wasm_bindgen_test]
+fn edit_flip_rotate() {
+    // 3x2 source:  1 2 3
+    //              4 5 6
+    let source = || {
+        let mut canv = Canvas::new(3, 2);
+        canv.load_pixels(vec![1, 2, 3, 4, 5, 6]);
+        canv
+    };
+    let check = |canv: &Canvas, size: (usize, usize), pixels: Vec<u32>| {
+        assert_eq!((canv.width(), canv.height()), size);
+        assert_eq!(canv.buffer(), &pixels);
+    };
+
+    let canv = source();
+    check(&canv.flipped_horizontal(), (3, 2), vec![3, 2, 1, 6, 5, 4]);
+    check(&canv.flipped_vertical(), (3, 2), vec![4, 5, 6, 1, 2, 3]);
+    check(&canv.rotated_90(), (2, 3), vec![4, 1, 5, 2, 6, 3]);
+    check(&canv.rotated_180(), (3, 2), vec![6, 5, 4, 3, 2, 1]);
+    check(&canv.rotated_270(), (2, 3), vec![3, 6, 2, 5, 1, 4]);
+    check(&canv.transposed(), (2, 3), vec![1, 4, 2, 5, 3, 6]);
+
+    let mut canv = source();
+    canv.rotate_90();
+    check(&canv, (2, 3), vec![4, 1, 5, 2, 6, 3]);
+    canv.rotate_270();
+    check(&canv, (3, 2), vec![1, 2, 3, 4, 5, 6]);
+    canv.transpose();
+    canv.flip_horizontal();
+    check(&canv, (2, 3), vec![4, 1, 5, 2, 6, 3]);
+    canv.flip_vertical();
+    canv.rotate_180();
+    check(&canv, (2, 3), vec![1, 4, 2, 5, 3, 6]);
+
+    // Larger than a tile, so the transpose crosses tile boundaries
+    let mut big = Canvas::new(70, 45);
+    for (i, px) in big.buffer_mut().iter_mut().enumerate() {
+        *px = i as u32;
+    }
+    let transposed = big.transposed();
+    assert_eq!(transposed.buffer()[transposed.buffer_index(44, 69)], big.buffer()[big.buffer_index(69, 44)]);
+    assert_eq!(transposed.buffer()[transposed.buffer_index(33, 40)], big.buffer()[big.buffer_index(40, 33)]);
+    let mut round_trip = big.rotated_90();
+    round_trip.rotate_90();
+    round_trip.rotate_90();
+    round_trip.rotate_90();
+    assert_eq!(round_trip.buffer(), big.buffer());
+}
+
+#[wasm_bindgen_test]
+fn filter_blur() {
+    let line = |pixels: Vec<u32>| {
+        let mut canv = Canvas::new(pixels.len(), 1);
+        canv.load_pixels(pixels);
+        canv
+    };
+    let (black, white) = (0xFF000000, 0xFFFFFFFF);
+
+    // Edge modes
+    let mut canv = line(vec![white, black, black, black]);
+    canv.box_blur(1, EdgeMode::Clamp);
+    assert_eq!(canv.buffer(), &vec![0xFFAAAAAA, 0xFF555555, black, black]);
+    let mut canv = line(vec![white, black, black, black]);
+    canv.box_blur(1, EdgeMode::Wrap);
+    assert_eq!(canv.buffer(), &vec![0xFF555555, 0xFF555555, black, 0xFF555555]);
+
+    // Transparent edges also fade the single row into the transparent rows above and below it
+    let mut canv = line(vec![white, white, white]);
+    canv.box_blur(1, EdgeMode::Transparent);
+    assert_eq!(canv.buffer(), &vec![0x39FFFFFF, 0x55FFFFFF, 0x39FFFFFF]);
+
+    // Colour doesn't bleed from or into transparent pixels
+    let mut canv = line(vec![0xFFFF0000, 0, 0]);
+    canv.box_blur(1, EdgeMode::Transparent);
+    assert_eq!(canv.buffer(), &vec![0x1CFF0000, 0x1CFF0000, 0]);
+
+    // Both Gaussian methods keep a uniform canvas uniform and spread a square symmetrically
+    for radius in &[1.5, 6.0] {
+        let mut canv = Canvas::new(40, 40);
+        canv.clear(&ARGBColour::new(255, 10, 20, 30));
+        canv.gaussian_blur(*radius, EdgeMode::Clamp);
+        assert!(canv.buffer().iter().all(|px| *px == 0xFF0A141E));
+
+        let mut canv = Canvas::new(41, 41);
+        for y in 16..25 {
+            for x in 16..25 {
+                canv.buffer_mut()[y * 41 + x] = white;
+            }
+        }
+        canv.gaussian_blur(*radius, EdgeMode::Wrap);
+        let px = |x: usize, y: usize| canv.buffer()[canv.buffer_index(x, y)];
+        assert_eq!(px(18, 20), px(22, 20));
+        assert_eq!(px(20, 17), px(20, 23));
+        assert_eq!(px(18, 20), px(20, 18));
+        assert!(px(20, 20) >> 24 > px(16, 20) >> 24 && px(16, 20) >> 24 > px(12, 20) >> 24);
+        assert_eq!(px(20, 20) & 0x00FF_FFFF, 0x00FF_FFFF);
+    }
+
+    // Radii which aren't finite are ignored, and huge radii are limited to the Canvas size
+    let mut canv = line(vec![white, black, black, black]);
+    for radius in &[f64::NAN, f64::INFINITY, -1.0] {
+        canv.gaussian_blur(*radius, EdgeMode::Clamp);
+        assert_eq!(canv.buffer(), &vec![white, black, black, black]);
+    }
+    let mut huge = line(vec![white, black, black, black]);
+    huge.gaussian_blur(1e300, EdgeMode::Clamp);
+    canv.gaussian_blur(4.0, EdgeMode::Clamp);
+    assert_eq!(huge.buffer(), canv.buffer());
+    let mut huge = line(vec![white, black, black, black]);
+    huge.box_blur(usize::MAX, EdgeMode::Clamp);
+    let mut canv = line(vec![white, black, black, black]);
+    canv.box_blur(4, EdgeMode::Clamp);
+    assert_eq!(huge.buffer(), canv.buffer());
+}
+
+#[wasm_bindgen_test]
+fn filter_convolve() {
+    let pixels = vec![0xFF102030, 0x80FF0000, 0xFF00FF00, 0x00000000, 0xFFFFFFFF, 0xFF0000FF];
+    let source = || {
+        let mut canv = Canvas::new(3, 2);
+        canv.load_pixels(pixels.clone());
+        canv
+    };
+
+    // Identity kernel, in either alpha mode, and invalid kernels
+    let mut canv = source();
+    let identity = [0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    assert!(canv.convolve(&identity, 3, 3, 1.0, 0.0, EdgeMode::Clamp, true));
+    assert_eq!(canv.buffer(), &pixels);
+    assert!(canv.convolve_kernel(&Kernel::new(vec![2.0], 1, 1, 2.0, 0.0), EdgeMode::Clamp, false));
+    assert_eq!(canv.buffer(), &pixels);
+    assert!(!canv.convolve(&[1.0, 1.0], 3, 1, 1.0, 0.0, EdgeMode::Clamp, false));
+    assert!(!canv.convolve(&[], 0, 0, 1.0, 0.0, EdgeMode::Clamp, false));
+    assert!(!canv.convolve(&[1.0], usize::MAX, 2, 1.0, 0.0, EdgeMode::Clamp, false));
+    assert!(!canv.convolve_kernel(&Kernel::new(vec![1.0], 2, usize::MAX, 1.0, 0.0), EdgeMode::Clamp, false));
+
+    // Kernel orientation and edge modes: each pixel takes the value of its right-hand neighbour
+    let mut canv = source();
+    let shift = [0.0, 0.0, 1.0];
+    canv.convolve(&shift, 3, 1, 1.0, 0.0, EdgeMode::Wrap, true);
+    assert_eq!(canv.buffer(), &vec![0x80FF0000, 0xFF00FF00, 0xFF102030, 0xFFFFFFFF, 0xFF0000FF, 0x00000000]);
+    let mut canv = source();
+    canv.convolve(&shift, 3, 1, 1.0, 0.0, EdgeMode::Transparent, true);
+    assert_eq!(canv.buffer()[2], 0);
+
+    // Alpha is preserved unless included, and bias is added to the colour channels of pixels which
+    // aren't fully transparent
+    let mut canv = source();
+    canv.convolve(&[0.0], 1, 1, 1.0, 64.0, EdgeMode::Clamp, false);
+    assert_eq!(canv.buffer(), &vec![0xFF404040, 0x80404040, 0xFF404040, 0x00000000, 0xFF404040, 0xFF404040]);
+
+    // Preserving alpha, colours are still weighted by alpha, so the colour of transparent pixels
+    // doesn't bleed into their neighbours
+    let mut canv = Canvas::new(3, 1);
+    canv.load_pixels(vec![0xFFFF0000, 0x00FFFFFF, 0x80FF0000]);
+    canv.convolve(&[1.0, 1.0, 1.0], 3, 1, 3.0, 0.0, EdgeMode::Clamp, false);
+    assert_eq!(canv.buffer(), &vec![0xFFAA0000, 0x00FFFFFF, 0x80AA0000]);
+
+    // Presets leave flat areas unchanged, or black for edge detection
+    let flat = || {
+        let mut canv = Canvas::new(4, 4);
+        canv.clear(&ARGBColour::new(200, 100, 150, 50));
+        canv
+    };
+    let mut canv = flat();
+    canv.sharpen(EdgeMode::Clamp);
+    canv.emboss(EdgeMode::Clamp);
+    canv.unsharp_mask(2.0, 1.5, 0);
+    assert!(canv.buffer().iter().all(|px| *px == 0xC8649632));
+    let mut canv = flat();
+    canv.laplacian(EdgeMode::Wrap);
+    assert!(canv.buffer().iter().all(|px| *px == 0xC8000000));
+    let mut canv = flat();
+    canv.sobel(EdgeMode::Clamp);
+    assert!(canv.buffer().iter().all(|px| *px == 0xC8000000));
+
+    // Sobel is symmetric for a vertical edge, Laplacian highlights the bright side
+    let mut canv = Canvas::new(4, 3);
+    for (i, px) in canv.buffer_mut().iter_mut().enumerate() {
+        *px = if i % 4 < 2 { 0xFF000000 } else { 0xFF101010 };
+    }
+    let mut edges = canv.crop(0, 0, 4, 3);
+    edges.sobel(EdgeMode::Clamp);
+    assert_eq!(&edges.buffer()[4..8], &[0xFF000000, 0xFF404040, 0xFF404040, 0xFF000000]);
+    canv.laplacian(EdgeMode::Clamp);
+    assert_eq!(&canv.buffer()[4..8], &[0xFF000000, 0xFF000000, 0xFF303030, 0xFF000000]);
+
+    // Unsharp mask increases contrast across an edge, but not below the threshold
+    let mut canv = Canvas::new(4, 1);
+    canv.load_pixels(vec![0xFF404040, 0xFF404040, 0xFF484848, 0xFF484848]);
+    let mut thresholded = canv.crop(0, 0, 4, 1);
+    thresholded.unsharp_mask(1.0, 1.0, 16);
+    assert_eq!(thresholded.buffer(), canv.buffer());
+    canv.unsharp_mask(1.0, 1.0, 0);
+    assert!(canv.buffer()[1] & 0xFF < 0x40 && canv.buffer()[2] & 0xFF > 0x48);
+}
@@ -117,17 +117,18 @@ const demo_fill_polygon = () => {
   draw_colour.b = nb;
 
   const points = POLY_POINTS
-    .map((pt) => [
-      ((pt[0] * POLY_SIZE * Math.cos(counter)) - (pt[1] * POLY_SIZE * Math.sin(counter))) + (WIDTH  / 2),
-      ((pt[0] * POLY_SIZE * Math.sin(counter)) + (pt[1] * POLY_SIZE * Math.cos(counter))) + (HEIGHT / 2),
-    ])
+    .map((pt) => [pt[0] * POLY_SIZE, pt[1] * POLY_SIZE])
     .reduce((a, pt) => a.concat(pt), []);
 
+  rust_canvas.save();
+  rust_canvas.translate(WIDTH / 2, HEIGHT / 2);
+  rust_canvas.rotate(counter);
   fill_polygon(
     rust_canvas,
     draw_colour,
     points,
   );
+  rust_canvas.restore();
 };
 const demo_fill_rect = () => {
   let nr, ng, nb;
@@ -181,17 +182,18 @@ const demo_fill_triangle_rot = () => {
   draw_colour.b = nb;
 
   const pts = POLY_POINTS_TRI
-    .map((pt) => [
-      ((pt[0] * POLY_SIZE / 2 * Math.cos(counter * 1.8)) - (pt[1] * POLY_SIZE / 2 * Math.sin(counter * 1.8))) + (WIDTH  / 2),
-      ((pt[0] * POLY_SIZE / 2 * Math.sin(counter * 1.8)) + (pt[1] * POLY_SIZE / 2 * Math.cos(counter * 1.8))) + (HEIGHT / 2),
-    ])
+    .map((pt) => [pt[0] * POLY_SIZE / 2, pt[1] * POLY_SIZE / 2])
     .reduce((a, pt) => a.concat(pt), []);
 
+  rust_canvas.save();
+  rust_canvas.translate(WIDTH / 2, HEIGHT / 2);
+  rust_canvas.rotate(counter * 1.8);
   fill_triangle(
     rust_canvas,
     draw_colour,
     ...pts
   );
+  rust_canvas.restore();
 };
 const demo_hlines = () => {
   let nr = Math.floor(((Math.sin(counter * 1.1) + 1) / 2) * 255);
@@ -231,18 +233,19 @@ const demo_polygon = () => {
   draw_colour.b = nb;
 
   const points = POLY_POINTS
-    .map((pt) => [
-      ((pt[0] * POLY_SIZE * Math.cos(counter)) - (pt[1] * POLY_SIZE * Math.sin(counter))) + (WIDTH  / 2),
-      ((pt[0] * POLY_SIZE * Math.sin(counter)) + (pt[1] * POLY_SIZE * Math.cos(counter))) + (HEIGHT / 2),
-    ])
+    .map((pt) => [pt[0] * POLY_SIZE, pt[1] * POLY_SIZE])
     .reduce((a, pt) => a.concat(pt), []);
 
+  rust_canvas.save();
+  rust_canvas.translate(WIDTH / 2, HEIGHT / 2);
+  rust_canvas.rotate(counter);
   polygon(
     rust_canvas,
     draw_colour,
     false,
     points,
   );
+  rust_canvas.restore();
 };
 const demo_rect = () => {
   let nr, ng, nb;
@@ -301,8 +304,8 @@ const demo_draw_sprite = () => {
   if (!sprite_canv) {
     return;
   }
-  const x = Math.floor(((Math.sin(counter * 1.57) + 1) / 2) * (width  - sprite_canv.width()));
-  const y = Math.floor(((Math.cos(counter * 1.32) + 1) / 2) * (height - sprite_canv.height()));
+  const x = Math.floor(((Math.sin(counter * 1.57) + 1) / 2) * (width  + sprite_canv.width()))  - sprite_canv.width();
+  const y = Math.floor(((Math.cos(counter * 1.32) + 1) / 2) * (height + sprite_canv.height())) - sprite_canv.height();
   rust_canvas.draw_canvas(sprite_canv, x, y);
 };
 const demo_textured_triangle = () => {